@@ -0,0 +1,125 @@
+//! Per-cell field updates against the slice-based row kernels and a row-pipelined tiling of them,
+//! in cell updates per second.
+//!
+//! Run with `cargo bench --bench kernels [-- <size> <steps>]`; add `--features simd` for the
+//! explicit SIMD paths. Every variant must leave the same bits in the fields.
+
+use fdtd_wasm::engine::{update_e_fields, update_hx, update_hx_hy, update_hy};
+use fdtd_wasm::kernels::{curl_row, difference_row, Update};
+use fdtd_wasm::parameters::{MaterialDefinition, MaterialRegion, SimulationParameters, SourceDefinition};
+use fdtd_wasm::precision::Real;
+use fdtd_wasm::state::SimulationState;
+use fdtd_wasm::step::step;
+use std::time::Instant;
+
+/// The indexed TMz updates the row kernels replaced.
+fn baseline(state: &mut SimulationState) {
+    let (w, h) = (state.width, state.height);
+    for y in 0..h - 1 {
+        for x in 0..w {
+            let idx = y * w + x;
+            let c = &state.coefficients[state.materials[idx] as usize];
+            let (da, db) = (c.da as Real, c.db as Real);
+            state.hx[idx] = da * state.hx[idx] - db * (state.ez[idx + w] - state.ez[idx]);
+        }
+    }
+    for y in 0..h {
+        for x in 0..w - 1 {
+            let idx = y * w + x;
+            let c = &state.coefficients[state.materials[idx] as usize];
+            let (da, db) = (c.da as Real, c.db as Real);
+            state.hy[idx] = da * state.hy[idx] + db * (state.ez[idx + 1] - state.ez[idx]);
+        }
+    }
+    for y in 1..h - 1 {
+        for x in 1..w - 1 {
+            let idx = y * w + x;
+            let c = &state.coefficients[state.materials[idx] as usize];
+            let curl = (state.hy[idx] - state.hy[idx - 1]) - (state.hx[idx] - state.hx[idx - w]);
+            state.ez[idx] = c.ca as Real * state.ez[idx] + c.cb as Real * curl;
+        }
+    }
+}
+
+fn rows(state: &mut SimulationState) {
+    update_hx(state);
+    update_hy(state);
+    update_e_fields(state);
+}
+
+fn fused(state: &mut SimulationState) {
+    update_hx_hy(state);
+    update_e_fields(state);
+}
+
+/// Row-pipelined tiling: one sweep updates H in row `y`, then Ez in row `y - 1`, whose H
+/// neighbours are then final, so each Ez row is reused while it is still in cache.
+fn tiled(state: &mut SimulationState) {
+    let (w, h) = (state.width, state.height);
+    let (ez, hx, hy) = (&mut state.ez, &mut state.hx, &mut state.hy);
+    let (materials, coefficients) = (&state.materials, &state.coefficients);
+    for y in 0..h {
+        let row = y * w;
+        if y + 1 < h {
+            difference_row(&mut hx[row..row + w], &ez[row + w..row + 2 * w], &ez[row..row + w], &materials[row..row + w],
+                           coefficients, Update::Magnetic, -1.0);
+        }
+        difference_row(&mut hy[row..row + w - 1], &ez[row + 1..row + w], &ez[row..row + w - 1], &materials[row..row + w - 1],
+                       coefficients, Update::Magnetic, 1.0);
+        if (2..h).contains(&y) {
+            let row = row - w;
+            let inner = row + 1..row + w - 1;
+            curl_row(&mut ez[inner.clone()], &hy[inner.clone()], &hy[row..row + w - 2], &hx[inner.clone()], &hx[row + 1 - w..row - 1],
+                     &materials[inner], coefficients, Update::Electric);
+        }
+    }
+}
+
+/// Seconds taken by `steps` sweeps of `update`, and the final Ez field.
+fn time(params: &SimulationParameters, steps: usize, update: impl Fn(&mut SimulationState)) -> (f64, Vec<Real>) {
+    let mut state = SimulationState::from_parameters(params).unwrap();
+    state.ez.iter_mut().enumerate().for_each(|(i, v)| *v = ((i % 97) as Real - 48.0) * 1e-3);
+    let start = Instant::now();
+    for _ in 0..steps {
+        update(&mut state);
+    }
+    (start.elapsed().as_secs_f64(), state.ez)
+}
+
+fn main() {
+    // `cargo bench` passes `--bench`; only the numbers are ours.
+    let args: Vec<usize> = std::env::args().skip(1).filter_map(|a| a.parse().ok()).collect();
+    let size = args.first().copied().unwrap_or(1000);
+    let steps = args.get(1).copied().unwrap_or(100);
+    // A dielectric block across a quarter of the rows makes them mixed; the rest are uniform.
+    let params = SimulationParameters {
+        width: size, height: size,
+        sources: vec![SourceDefinition { x: size / 2, y: size / 2, frequency: 0.05, ..Default::default() }],
+        materials: vec![MaterialDefinition { name: "glass".to_string(), relative_permittivity: 4.0, ..Default::default() }],
+        regions: vec![MaterialRegion {
+            material: "glass".to_string(),
+            path: format!("M 0 {q} L {h} {q} L {h} {h} L 0 {h} Z", q = size / 4, h = size / 2),
+        }],
+        ..Default::default()
+    };
+    let cells = (size * size * steps) as f64;
+    let on_off = |on: bool| if on { "on" } else { "off" };
+    println!("{}x{} grid, {} steps, simd {}, f32 {}", size, size, steps, on_off(cfg!(feature = "simd")), on_off(cfg!(feature = "f32")));
+
+    let (reference, reference_ez) = time(&params, steps, baseline);
+    println!("{:<13}{:8.3} s  {:8.1} Mcells/s", "per-cell:", reference, cells / reference / 1e6);
+    for (name, update) in [("row kernels", rows as fn(&mut SimulationState)), ("fused", fused), ("tiled", tiled)] {
+        let (seconds, ez) = time(&params, steps, update);
+        println!("{:<13}{:8.3} s  {:8.1} Mcells/s  speed-up {:.2}x", format!("{}:", name), seconds, cells / seconds / 1e6, reference / seconds);
+        assert!(reference_ez.iter().zip(&ez).all(|(a, b)| a.to_bits() == b.to_bits()), "{} result differs", name);
+    }
+
+    // Whole steps include sources, boundaries and monitors, so they run below the bare kernels.
+    let mut state = SimulationState::from_parameters(&params).unwrap();
+    let start = Instant::now();
+    for _ in 0..steps {
+        step(&params, &mut state, None);
+    }
+    let seconds = start.elapsed().as_secs_f64();
+    println!("{:<13}{:8.3} s  {:8.1} Mcells/s", "full step:", seconds, cells / seconds / 1e6);
+}
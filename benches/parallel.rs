@@ -0,0 +1,83 @@
+//! Serial against threaded field updates on a large grid, and the fixed cost of one threaded pass.
+//!
+//! Run with `cargo bench --features parallel --bench parallel [-- <size> <steps> <threads>]`;
+//! the thread count defaults to one per core.
+//! Without the feature both runs are serial.
+
+use fdtd_wasm::parallel::{for_each_row, set_threads, threads, MIN_ROWS_PER_THREAD};
+use fdtd_wasm::parameters::{SimulationParameters, SourceDefinition};
+use fdtd_wasm::precision::Real;
+use fdtd_wasm::state::SimulationState;
+use fdtd_wasm::step::step;
+use std::time::Instant;
+
+/// Seconds per run and the final Ez field.
+fn run(params: &SimulationParameters, steps: usize, threads: usize) -> (f64, Vec<Real>) {
+    set_threads(threads);
+    let mut state = SimulationState::from_parameters(params).unwrap();
+    let start = Instant::now();
+    for _ in 0..steps {
+        step(params, &mut state, None);
+    }
+    (start.elapsed().as_secs_f64(), state.ez)
+}
+
+/// Microseconds per `for_each_row` pass over rows that do almost nothing, so that only the cost of
+/// handing them out remains.
+fn pass_overhead(rows: usize, threads: usize, passes: usize) -> f64 {
+    set_threads(threads);
+    let mut counts = vec![0u64; rows];
+    let start = Instant::now();
+    for _ in 0..passes {
+        for_each_row(counts.iter_mut(), |count| *count += 1);
+    }
+    let seconds = start.elapsed().as_secs_f64();
+    assert!(counts.iter().all(|&count| count == passes as u64));
+    seconds / passes as f64 * 1e6
+}
+
+/// The same passes with fresh scoped threads each time, for comparison with the pool.
+fn spawn_overhead(rows: usize, threads: usize, passes: usize) -> f64 {
+    let mut counts = vec![0u64; rows];
+    let per_band = rows.div_ceil(threads);
+    let start = Instant::now();
+    for _ in 0..passes {
+        std::thread::scope(|scope| {
+            for band in counts.chunks_mut(per_band) {
+                scope.spawn(move || band.iter_mut().for_each(|count| *count += 1));
+            }
+        });
+    }
+    start.elapsed().as_secs_f64() / passes as f64 * 1e6
+}
+
+fn main() {
+    // `cargo bench` passes `--bench`; only the numbers are ours.
+    let args: Vec<usize> = std::env::args().skip(1).filter_map(|a| a.parse().ok()).collect();
+    let size = args.first().copied().unwrap_or(2000);
+    let steps = args.get(1).copied().unwrap_or(50);
+    let params = SimulationParameters {
+        width: size, height: size,
+        sources: vec![SourceDefinition { x: size / 2, y: size / 2, frequency: 0.05, ..Default::default() }],
+        ..Default::default()
+    };
+    let cells = (size * size * steps) as f64;
+
+    set_threads(args.get(2).copied().unwrap_or(0));
+    let available = threads();
+    let (serial, serial_ez) = run(&params, steps, 1);
+    println!("{}x{} grid, {} steps", size, size, steps);
+    println!("serial:     {:8.3} s  {:8.1} Mcells/s", serial, cells / serial / 1e6);
+    let (threaded, threaded_ez) = run(&params, steps, available);
+    println!("{:2} threads: {:8.3} s  {:8.1} Mcells/s  speed-up {:.2}x", available, threaded, cells / threaded / 1e6, serial / threaded);
+    assert!(serial_ez.iter().zip(&threaded_ez).all(|(a, b)| a.to_bits() == b.to_bits()), "threaded result differs");
+
+    // A step makes a dozen or more passes (H, E, each CPML strip, boundaries), so this is paid
+    // that many times per step.
+    let rows = available * MIN_ROWS_PER_THREAD;
+    let passes = 10_000;
+    println!("per pass over {} empty rows:", rows);
+    println!("serial:     {:8.2} us", pass_overhead(rows, 1, passes));
+    println!("pool:       {:8.2} us", pass_overhead(rows, available, passes));
+    println!("spawning:   {:8.2} us", spawn_overhead(rows, available, passes / 10));
+}
@@ -0,0 +1,167 @@
+use crate::precision::{widen, Real};
+use crate::parameters::{BoundaryDefinition, EdgeBoundary, Polarization};
+use crate::state::SimulationState;
+
+/// How the primary field (Ez in TMz, Hz in TEz) on an outer node row or column is obtained.
+///
+/// The regular update skips these nodes, which holds them at zero: an electric wall in TMz
+/// and a magnetic wall in TEz. The other walls update them with an image of the missing
+/// neighbour outside the grid.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum EdgeNodes {
+    /// Held at zero.
+    Held,
+    /// Updated with the outside neighbour mirrored with opposite sign.
+    Mirror,
+    /// Low edge of a periodic axis: updated with the neighbour one period away, shifted by `-phase`.
+    Wrap(f64),
+    /// High edge of a periodic axis: copied from the low edge, shifted by `phase`.
+    Copy(f64),
+}
+
+impl EdgeNodes {
+    pub fn new(edge: &EdgeBoundary, polarization: Polarization, high: bool) -> Self {
+        let mirror_wall = match polarization {
+            Polarization::TMz => EdgeBoundary::Pmc,
+            Polarization::TEz => EdgeBoundary::Pec,
+        };
+        match edge.periodic_phase() {
+            Some(phase) if high => EdgeNodes::Copy(phase),
+            Some(phase) => EdgeNodes::Wrap(phase),
+            None if *edge == mirror_wall => EdgeNodes::Mirror,
+            None => EdgeNodes::Held,
+        }
+    }
+
+    /// Whether the update pass computes these nodes.
+    pub fn is_updated(self) -> bool {
+        matches!(self, EdgeNodes::Mirror | EdgeNodes::Wrap(_))
+    }
+}
+
+/// Edge node treatment of the four edges, as `[left, right, top, bottom]`.
+pub fn edge_nodes(boundary: &BoundaryDefinition, polarization: Polarization) -> [EdgeNodes; 4] {
+    [
+        EdgeNodes::new(&boundary.left, polarization, false),
+        EdgeNodes::new(&boundary.right, polarization, true),
+        EdgeNodes::new(&boundary.top, polarization, false),
+        EdgeNodes::new(&boundary.bottom, polarization, true),
+    ]
+}
+
+/// Multiplies `(re, im)` by `exp(i * phase)`.
+fn rotate((re, im): (f64, f64), phase: f64) -> (f64, f64) {
+    if phase == 0.0 {
+        return (re, im);
+    }
+    let (sin, cos) = phase.sin_cos();
+    (re * cos - im * sin, re * sin + im * cos)
+}
+
+/// Values of the staggered field before and after node `i` of an axis with `n` nodes.
+/// `get` returns the real and imaginary part of the staggered node `j` (between nodes `j` and `j + 1`).
+fn neighbours(i: usize, n: usize, low: EdgeNodes, high: EdgeNodes, get: impl Fn(usize) -> (f64, f64)) -> ((f64, f64), (f64, f64)) {
+    let negate = |(re, im): (f64, f64)| (-re, -im);
+    let before = match (i, low) {
+        (0, EdgeNodes::Wrap(phase)) => rotate(get(n - 2), -phase),
+        (0, _) => negate(get(0)),
+        _ => get(i - 1),
+    };
+    let after = if i == n - 1 && high == EdgeNodes::Mirror { negate(get(n - 2)) } else { get(i) };
+    (before, after)
+}
+
+/// Primary field and the staggered fields differenced along x and y, with the sign of the curl.
+fn curl_fields(state: &SimulationState) -> (&[Real], &[Real], &[Real], f64) {
+    match state.polarization {
+        Polarization::TMz => (&state.ez, &state.hy, &state.hx, 1.0),
+        Polarization::TEz => (&state.hz, &state.ey, &state.ex, -1.0),
+    }
+}
+
+/// Updates the primary field on the outer nodes of mirror and periodic edges.
+/// Runs right after the regular interior update (`update_e_fields` or `update_hz`), before the CPML correction.
+pub fn update_edge_nodes(state: &mut SimulationState, boundary: &BoundaryDefinition) {
+    let (w, h) = (state.width, state.height);
+    if w < 3 || h < 3 {
+        return;
+    }
+    let [left, right, top, bottom] = edge_nodes(boundary, state.polarization);
+    if ![left, right, top, bottom].iter().any(|e| e.is_updated()) {
+        return;
+    }
+    let axis = |i: usize, n: usize, low: EdgeNodes, high: EdgeNodes| {
+        if i == 0 { Some(low) } else if i == n - 1 { Some(high) } else { None }
+    };
+
+    let ring = (0..w).flat_map(|x| [(x, 0), (x, h - 1)]).chain((1..h - 1).flat_map(|y| [(0, y), (w - 1, y)]));
+    let mut updates = Vec::new();
+    {
+        let (primary, along_x, along_y, sign) = curl_fields(state);
+        let imag = state.bloch.as_deref().map(curl_fields);
+        let part = |re: &[Real], im: Option<&[Real]>, idx: usize| (widen(re[idx]), im.map_or(0.0, |f| widen(f[idx])));
+
+        for (x, y) in ring {
+            let on_edge = [axis(x, w, left, right), axis(y, h, top, bottom)];
+            if on_edge.iter().flatten().any(|e| !e.is_updated()) {
+                continue;
+            }
+            let idx = y * w + x;
+            let (x_before, x_after) = neighbours(x, w, left, right, |j| part(along_x, imag.map(|f| f.1), y * w + j));
+            let (y_before, y_after) = neighbours(y, h, top, bottom, |j| part(along_y, imag.map(|f| f.2), j * w + x));
+            let c = &state.coefficients[state.materials[idx] as usize];
+            let (c1, c2) = match state.polarization {
+                Polarization::TMz => (c.ca, c.cb),
+                Polarization::TEz => (c.da, c.db),
+            };
+            let value = |own: f64, dx: f64, dy: f64| c1 * own + sign * c2 * (dx - dy);
+            let (re, im) = part(primary, imag.map(|f| f.0), idx);
+            updates.push((
+                idx,
+                value(re, x_after.0 - x_before.0, y_after.0 - y_before.0),
+                value(im, x_after.1 - x_before.1, y_after.1 - y_before.1),
+            ));
+        }
+    }
+
+    for &(idx, re, _) in &updates {
+        state.primary_field_mut()[idx] = re as Real;
+    }
+    if let Some(imag) = state.bloch.as_deref_mut() {
+        for &(idx, _, im) in &updates {
+            imag.primary_field_mut()[idx] = im as Real;
+        }
+    }
+}
+
+/// Copies the low edge of each periodic axis onto its high edge, applying the Bloch phase.
+/// Runs after the sources so the copies include them.
+pub fn apply_periodic_copies(state: &mut SimulationState, boundary: &BoundaryDefinition) {
+    let (w, h) = (state.width, state.height);
+    if w < 3 || h < 3 {
+        return;
+    }
+    let [_, right, _, bottom] = edge_nodes(boundary, state.polarization);
+    if let EdgeNodes::Copy(phase) = right {
+        copy_nodes(state, phase, (0..h).map(|y| (y * w, y * w + w - 1)));
+    }
+    // Runs after the columns so the far corner picks up both phases.
+    if let EdgeNodes::Copy(phase) = bottom {
+        copy_nodes(state, phase, (0..w).map(|x| (x, (h - 1) * w + x)));
+    }
+}
+
+fn copy_nodes(state: &mut SimulationState, phase: f64, pairs: impl Iterator<Item = (usize, usize)>) {
+    let mut imag = state.bloch.as_deref_mut().map(|s| s.primary_field_mut());
+    let primary = match state.polarization {
+        Polarization::TMz => &mut state.ez,
+        Polarization::TEz => &mut state.hz,
+    };
+    for (from, to) in pairs {
+        let (re, im) = rotate((widen(primary[from]), imag.as_ref().map_or(0.0, |f| widen(f[from]))), phase);
+        primary[to] = re as Real;
+        if let Some(f) = imag.as_mut() {
+            f[to] = im as Real;
+        }
+    }
+}
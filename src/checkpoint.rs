@@ -0,0 +1,194 @@
+//! Compact binary checkpoints of a running simulation.
+//!
+//! A checkpoint starts with `MAGIC`, the format `VERSION` and the byte width of a field value
+//! (`size_of::<Real>()`), followed by the evolving state of each part in a fixed order. All numbers
+//! are little-endian and arrays are prefixed with their length. The configuration itself (grid,
+//! materials table, boundaries, monitors) is not stored: a checkpoint is restored into state built
+//! from the same parameters, and every array length is checked against it.
+
+use crate::precision::Real;
+
+/// First bytes of every checkpoint.
+pub const MAGIC: &[u8; 8] = b"FDTDCKPT";
+/// Current format version. Bump it whenever the layout changes.
+pub const VERSION: u32 = 4;
+
+/// Appends checkpoint values to a byte buffer.
+pub struct Writer {
+    bytes: Vec<u8>,
+}
+
+impl Writer {
+    /// Starts a checkpoint with its header.
+    pub fn new() -> Self {
+        let mut writer = Self { bytes: Vec::new() };
+        writer.bytes.extend_from_slice(MAGIC);
+        writer.u32(VERSION);
+        writer.u8(std::mem::size_of::<Real>() as u8);
+        writer
+    }
+
+    pub fn finish(self) -> Vec<u8> {
+        self.bytes
+    }
+
+    pub fn u8(&mut self, value: u8) {
+        self.bytes.push(value);
+    }
+
+    pub fn bool(&mut self, value: bool) {
+        self.u8(value as u8);
+    }
+
+    pub fn u32(&mut self, value: u32) {
+        self.bytes.extend_from_slice(&value.to_le_bytes());
+    }
+
+    pub fn usize(&mut self, value: usize) {
+        self.bytes.extend_from_slice(&(value as u64).to_le_bytes());
+    }
+
+    pub fn f64(&mut self, value: f64) {
+        self.bytes.extend_from_slice(&value.to_le_bytes());
+    }
+
+    pub fn bytes(&mut self, values: &[u8]) {
+        self.usize(values.len());
+        self.bytes.extend_from_slice(values);
+    }
+
+    pub fn str(&mut self, value: &str) {
+        self.bytes(value.as_bytes());
+    }
+
+    pub fn f64s(&mut self, values: &[f64]) {
+        self.usize(values.len());
+        values.iter().for_each(|v| self.bytes.extend_from_slice(&v.to_le_bytes()));
+    }
+
+    pub fn reals(&mut self, values: &[Real]) {
+        self.usize(values.len());
+        values.iter().for_each(|v| self.bytes.extend_from_slice(&v.to_le_bytes()));
+    }
+}
+
+impl Default for Writer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Reads checkpoint values back in the order they were written.
+pub struct Reader<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl<'a> Reader<'a> {
+    /// Checks the header of `bytes` and positions the reader after it.
+    pub fn new(bytes: &'a [u8]) -> Result<Self, String> {
+        let mut reader = Self { bytes, position: 0 };
+        if reader.take(MAGIC.len()).ok() != Some(&MAGIC[..]) {
+            return Err("Not a simulation checkpoint".to_string());
+        }
+        let version = reader.u32()?;
+        if version != VERSION {
+            return Err(format!("Checkpoint version {} is not supported (expected {})", version, VERSION));
+        }
+        let width = reader.u8()? as usize;
+        if width != std::mem::size_of::<Real>() {
+            return Err(format!("Checkpoint stores {}-byte fields but this build uses {}-byte fields",
+                               width, std::mem::size_of::<Real>()));
+        }
+        Ok(reader)
+    }
+
+    /// Fails unless every byte has been read.
+    pub fn finish(self) -> Result<(), String> {
+        if self.position == self.bytes.len() {
+            Ok(())
+        } else {
+            Err(format!("Checkpoint has {} unexpected trailing bytes", self.bytes.len() - self.position))
+        }
+    }
+
+    fn take(&mut self, len: usize) -> Result<&'a [u8], String> {
+        let end = self.position.checked_add(len).filter(|&end| end <= self.bytes.len())
+            .ok_or_else(|| "Checkpoint is truncated".to_string())?;
+        let slice = &self.bytes[self.position..end];
+        self.position = end;
+        Ok(slice)
+    }
+
+    fn array<const N: usize>(&mut self) -> Result<[u8; N], String> {
+        Ok(self.take(N)?.try_into().unwrap())
+    }
+
+    pub fn u8(&mut self) -> Result<u8, String> {
+        Ok(self.take(1)?[0])
+    }
+
+    pub fn bool(&mut self) -> Result<bool, String> {
+        match self.u8()? {
+            0 => Ok(false),
+            1 => Ok(true),
+            other => Err(format!("Invalid flag {} in checkpoint", other)),
+        }
+    }
+
+    pub fn u32(&mut self) -> Result<u32, String> {
+        Ok(u32::from_le_bytes(self.array()?))
+    }
+
+    pub fn usize(&mut self) -> Result<usize, String> {
+        usize::try_from(u64::from_le_bytes(self.array()?)).map_err(|e| e.to_string())
+    }
+
+    pub fn f64(&mut self) -> Result<f64, String> {
+        Ok(f64::from_le_bytes(self.array()?))
+    }
+
+    /// Reads a length prefix that must equal `expected`.
+    pub fn len(&mut self, expected: usize, what: &str) -> Result<(), String> {
+        let len = self.usize()?;
+        if len == expected {
+            Ok(())
+        } else {
+            Err(format!("Checkpoint has {} {} but the simulation has {}", len, what, expected))
+        }
+    }
+
+    pub fn bytes(&mut self) -> Result<Vec<u8>, String> {
+        let len = self.usize()?;
+        Ok(self.take(len)?.to_vec())
+    }
+
+    pub fn string(&mut self) -> Result<String, String> {
+        String::from_utf8(self.bytes()?).map_err(|e| e.to_string())
+    }
+
+    /// Fills `values`, whose length the checkpoint must match.
+    pub fn bytes_into(&mut self, values: &mut [u8], what: &str) -> Result<(), String> {
+        self.len(values.len(), what)?;
+        values.copy_from_slice(self.take(values.len())?);
+        Ok(())
+    }
+
+    /// Fills `values`, whose length the checkpoint must match.
+    pub fn f64s_into(&mut self, values: &mut [f64], what: &str) -> Result<(), String> {
+        self.len(values.len(), what)?;
+        for value in values {
+            *value = self.f64()?;
+        }
+        Ok(())
+    }
+
+    /// Fills `values`, whose length the checkpoint must match.
+    pub fn reals_into(&mut self, values: &mut [Real], what: &str) -> Result<(), String> {
+        self.len(values.len(), what)?;
+        for value in values {
+            *value = Real::from_le_bytes(self.array()?);
+        }
+        Ok(())
+    }
+}
@@ -1,6 +1,8 @@
 use crate::comms::packet::PacketDecoder;
 use crate::comms::modulator::ModulationScheme;
+use crate::checkpoint::{Reader, Writer};
 
+#[derive(Clone)]
 pub struct Demodulator {
     samples_per_symbol: usize,
     sample_counter: usize,
@@ -33,6 +35,31 @@ impl Demodulator {
         }
     }
 
+    pub fn save(&self, out: &mut Writer) {
+        out.usize(self.samples_per_symbol);
+        out.usize(self.sample_counter);
+        for value in [self.freq_0, self.freq_1, self.i0, self.q0, self.i1, self.q1] {
+            out.f64(value);
+        }
+        self.scheme.save(out);
+        self.decoder.save(out);
+        out.str(&self.last_message);
+        out.str(&self.received_bits_debug);
+    }
+
+    pub fn restore(&mut self, input: &mut Reader) -> Result<(), String> {
+        self.samples_per_symbol = input.usize()?;
+        self.sample_counter = input.usize()?;
+        for value in [&mut self.freq_0, &mut self.freq_1, &mut self.i0, &mut self.q0, &mut self.i1, &mut self.q1] {
+            *value = input.f64()?;
+        }
+        self.scheme = ModulationScheme::restore(input)?;
+        self.decoder.restore(input)?;
+        self.last_message = input.string()?;
+        self.received_bits_debug = input.string()?;
+        Ok(())
+    }
+
     pub fn set_scheme(&mut self, scheme: ModulationScheme) {
         self.scheme = scheme;
     }
@@ -121,6 +148,16 @@ impl Demodulator {
         self.decoder.history.clone()
     }
 
+    /// Number of decoder events so far, to pass to `packet_decoded_since` later.
+    pub fn event_count(&self) -> usize {
+        self.decoder.history.len()
+    }
+
+    /// Whether a packet with a valid CRC was completed after the first `events` decoder events.
+    pub fn packet_decoded_since(&self, events: usize) -> bool {
+        self.decoder.history.get(events..).unwrap_or_default().iter().any(|e| e.label == "CRC" && !e.is_error)
+    }
+
     pub fn get_current_bits(&self) -> String {
         self.decoder.current_bits_buffer.clone()
     }
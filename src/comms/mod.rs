@@ -1,3 +1,4 @@
 pub mod modulator;
 pub mod demodulator;
 pub mod packet;
+pub mod noise;
@@ -1,3 +1,5 @@
+use crate::checkpoint::{Reader, Writer};
+
 /// Converts a string to a vector of bits (0s and 1s).
 /// E.g., 'A' (0x41 = 01000001) -> [0, 1, 0, 0, 0, 0, 0, 1]
 pub fn text_to_bits(text: &str) -> Vec<u8> {
@@ -16,6 +18,21 @@ pub enum ModulationScheme {
     ASK,
 }
 
+impl ModulationScheme {
+    pub(crate) fn save(self, out: &mut Writer) {
+        out.u8(self as u8);
+    }
+
+    pub(crate) fn restore(input: &mut Reader) -> Result<Self, String> {
+        match input.u8()? {
+            0 => Ok(ModulationScheme::FSK),
+            1 => Ok(ModulationScheme::ASK),
+            other => Err(format!("Invalid modulation scheme {} in checkpoint", other)),
+        }
+    }
+}
+
+#[derive(Clone)]
 pub struct Modulator {
     bits: Vec<u8>,
     current_bit_idx: usize,
@@ -39,6 +56,27 @@ impl Modulator {
         }
     }
 
+    pub fn save(&self, out: &mut Writer) {
+        out.bytes(&self.bits);
+        out.usize(self.current_bit_idx);
+        out.usize(self.samples_per_symbol);
+        out.usize(self.sample_counter);
+        out.f64(self.freq_0);
+        out.f64(self.freq_1);
+        self.scheme.save(out);
+    }
+
+    pub fn restore(&mut self, input: &mut Reader) -> Result<(), String> {
+        self.bits = input.bytes()?;
+        self.current_bit_idx = input.usize()?;
+        self.samples_per_symbol = input.usize()?;
+        self.sample_counter = input.usize()?;
+        self.freq_0 = input.f64()?;
+        self.freq_1 = input.f64()?;
+        self.scheme = ModulationScheme::restore(input)?;
+        Ok(())
+    }
+
     pub fn set_scheme(&mut self, scheme: ModulationScheme) {
         self.scheme = scheme;
     }
@@ -0,0 +1,16 @@
+/// Uniform noise in `[-level, level]` for the receiver sample of `time_step`.
+///
+/// The value is a hash of the step rather than the output of a running generator, so the same step
+/// always gets the same noise: runs are repeatable and a restored checkpoint carries on unchanged.
+pub fn receiver_noise(time_step: usize, level: f64) -> f64 {
+    if level == 0.0 {
+        return 0.0;
+    }
+    // SplitMix64 finaliser.
+    let mut z = (time_step as u64).wrapping_add(0x9E37_79B9_7F4A_7C15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^= z >> 31;
+    let unit = (z >> 11) as f64 / (1u64 << 53) as f64;
+    level * (2.0 * unit - 1.0)
+}
@@ -1,4 +1,5 @@
 use serde::{Serialize, Deserialize};
+use crate::checkpoint::{Reader, Writer};
 
 #[derive(Debug, PartialEq, Clone, Copy, Serialize, Deserialize)]
 pub enum PacketState {
@@ -18,6 +19,7 @@ pub struct DecodeEvent {
     pub is_error: bool,
 }
 
+#[derive(Clone)]
 pub struct PacketDecoder {
     state: PacketState,
     buffer: u8,         // Shift register for detecting patterns
@@ -31,6 +33,12 @@ pub struct PacketDecoder {
     pub current_bits_buffer: String,
 }
 
+impl Default for PacketDecoder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl PacketDecoder {
     pub fn new() -> Self {
         Self {
@@ -54,6 +62,48 @@ impl PacketDecoder {
         self.current_bits_buffer.clear();
     }
 
+    pub fn save(&self, out: &mut Writer) {
+        out.u8(self.state as u8);
+        out.u8(self.buffer);
+        out.usize(self.bit_count);
+        out.u8(self.length);
+        out.bytes(&self.payload);
+        out.u8(self.crc);
+        out.usize(self.history.len());
+        for event in &self.history {
+            out.str(&event.label);
+            out.str(&event.bits);
+            out.bool(event.is_complete);
+            out.bool(event.is_error);
+        }
+        out.str(&self.current_bits_buffer);
+    }
+
+    pub fn restore(&mut self, input: &mut Reader) -> Result<(), String> {
+        const STATES: [PacketState; 6] = [
+            PacketState::SearchPreamble, PacketState::SearchSync, PacketState::ReadLength,
+            PacketState::ReadPayload, PacketState::ReadCRC, PacketState::Done,
+        ];
+        let state = input.u8()?;
+        self.state = *STATES.get(state as usize).ok_or_else(|| format!("Invalid decoder state {} in checkpoint", state))?;
+        self.buffer = input.u8()?;
+        self.bit_count = input.usize()?;
+        self.length = input.u8()?;
+        self.payload = input.bytes()?;
+        self.crc = input.u8()?;
+        let events = input.usize()?;
+        self.history = (0..events)
+            .map(|_| Ok(DecodeEvent {
+                label: input.string()?,
+                bits: input.string()?,
+                is_complete: input.bool()?,
+                is_error: input.bool()?,
+            }))
+            .collect::<Result<_, String>>()?;
+        self.current_bits_buffer = input.string()?;
+        Ok(())
+    }
+
     pub fn push_bit(&mut self, bit: u8) -> Option<String> {
         // Shift bit into buffer (LSB in)
         self.buffer = (self.buffer << 1) | (bit & 1);
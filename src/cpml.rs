@@ -0,0 +1,301 @@
+use crate::boundaries::EdgeNodes;
+use crate::checkpoint::{Reader, Writer};
+use crate::materials::UpdateCoefficients;
+use crate::parallel;
+use crate::precision::{widen, Real};
+use std::ops::Range;
+use crate::parameters::{BoundaryDefinition, CpmlDefinition, EdgeBoundary};
+
+/// Graded CPML coefficients for the cells of one axis that lie inside an absorbing layer.
+/// `positions` are grid indices along the axis; the other vectors are indexed in parallel.
+#[derive(Debug, Clone, Default)]
+pub struct LayerProfile {
+    pub positions: Vec<usize>,
+    pub b: Vec<f64>,
+    pub c: Vec<f64>,
+    /// `1 / kappa - 1`, the correction applied on top of the regular curl term.
+    pub inv_kappa_m1: Vec<f64>,
+}
+
+impl LayerProfile {
+    /// Builds the profile for one axis of `len` cells with layers of `lo` and `hi` cells.
+    /// `offset` is 0.0 for field components on integer nodes and 0.5 for staggered ones.
+    /// Only nodes in `first..last` are kept, matching the range the update loops visit.
+    fn build(len: usize, lo: Option<&CpmlDefinition>, hi: Option<&CpmlDefinition>, offset: f64,
+             first: usize, last: usize, courant: f64) -> Self {
+        let mut profile = LayerProfile::default();
+        let lo_depth = lo.map_or(0, |d| d.thickness.min(len / 2));
+        let hi_depth = hi.map_or(0, |d| d.thickness.min(len / 2));
+
+        for p in first..last {
+            let pos = p as f64 + offset;
+            let layer = if lo_depth > 0 && pos < lo_depth as f64 {
+                Some(((lo_depth as f64 - pos) / lo_depth as f64, lo.unwrap()))
+            } else if hi_depth > 0 && pos > (len - 1 - hi_depth) as f64 {
+                Some(((pos - (len - 1 - hi_depth) as f64) / hi_depth as f64, hi.unwrap()))
+            } else {
+                None
+            };
+
+            if let Some((rho, def)) = layer {
+                let (b, c, kappa) = coefficients(rho.min(1.0), def, courant);
+                profile.positions.push(p);
+                profile.b.push(b);
+                profile.c.push(c);
+                profile.inv_kappa_m1.push(1.0 / kappa - 1.0);
+            }
+        }
+        profile
+    }
+
+    pub fn len(&self) -> usize {
+        self.positions.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.positions.is_empty()
+    }
+}
+
+/// Computes the recursive-convolution coefficients `(b, c, kappa)` at normalised depth `rho`
+/// (0 at the inner interface, 1 at the outer wall).
+pub fn coefficients(rho: f64, def: &CpmlDefinition, courant: f64) -> (f64, f64, f64) {
+    let m = def.grading_order;
+    let sigma_max = def.sigma_max.unwrap_or(0.8 * (m + 1.0));
+    let graded = rho.powf(m);
+    let sigma = sigma_max * graded;
+    let kappa = 1.0 + (def.kappa_max - 1.0) * graded;
+    let alpha = def.alpha_max * (1.0 - rho).powf(def.alpha_grading_order);
+
+    let b = (-(sigma / kappa + alpha) * courant).exp();
+    let denom = sigma * kappa + kappa * kappa * alpha;
+    let c = if denom > 0.0 { sigma / denom * (b - 1.0) } else { 0.0 };
+    (b, c, kappa)
+}
+
+/// Auxiliary state of the convolutional PML.
+/// The psi arrays only cover the cells inside the absorbing layers.
+#[derive(Debug, Clone, Default)]
+pub struct CpmlState {
+    pub width: usize,
+    pub height: usize,
+
+    pub ez_x: LayerProfile,
+    pub hy_x: LayerProfile,
+    pub ez_y: LayerProfile,
+    pub hx_y: LayerProfile,
+
+    /// Laid out as `y * ez_x.len() + k`.
+    pub psi_ez_x: Vec<f64>,
+    /// Laid out as `y * hy_x.len() + k`.
+    pub psi_hy_x: Vec<f64>,
+    /// Laid out as `k * width + x`.
+    pub psi_ez_y: Vec<f64>,
+    /// Laid out as `k * width + x`.
+    pub psi_hx_y: Vec<f64>,
+
+    /// Rows and columns of primary-field nodes corrected by the x and y layers.
+    /// They include the outer nodes of edges that update them (see `include_edge_nodes`).
+    pub rows: Range<usize>,
+    pub columns: Range<usize>,
+}
+
+fn cpml_of(edge: &EdgeBoundary) -> Option<&CpmlDefinition> {
+    match edge {
+        EdgeBoundary::Cpml(def) => Some(def),
+        _ => None,
+    }
+}
+
+impl CpmlState {
+    /// Builds the CPML for the edges of `boundary` that request one.
+    /// Returns `None` if no edge uses a CPML.
+    pub fn new(width: usize, height: usize, boundary: &BoundaryDefinition, courant: f64) -> Option<Self> {
+        let left = cpml_of(&boundary.left);
+        let right = cpml_of(&boundary.right);
+        let top = cpml_of(&boundary.top);
+        let bottom = cpml_of(&boundary.bottom);
+        if left.is_none() && right.is_none() && top.is_none() && bottom.is_none() {
+            return None;
+        }
+        if width < 2 || height < 2 {
+            return None;
+        }
+
+        let ez_x = LayerProfile::build(width, left, right, 0.0, 1, width - 1, courant);
+        let hy_x = LayerProfile::build(width, left, right, 0.5, 0, width - 1, courant);
+        let ez_y = LayerProfile::build(height, top, bottom, 0.0, 1, height - 1, courant);
+        let hx_y = LayerProfile::build(height, top, bottom, 0.5, 0, height - 1, courant);
+
+        Some(Self {
+            width,
+            height,
+            psi_ez_x: vec![0.0; ez_x.len() * height],
+            psi_hy_x: vec![0.0; hy_x.len() * height],
+            psi_ez_y: vec![0.0; ez_y.len() * width],
+            psi_hx_y: vec![0.0; hx_y.len() * width],
+            ez_x,
+            hy_x,
+            ez_y,
+            hx_y,
+            rows: 1..height - 1,
+            columns: 1..width - 1,
+        })
+    }
+
+    /// Extends the corrected rows and columns to the outer nodes updated by the edges
+    /// (`[left, right, top, bottom]`), so a layer on one axis also covers them.
+    pub fn include_edge_nodes(&mut self, edges: [EdgeNodes; 4]) {
+        let [left, right, top, bottom] = edges;
+        self.columns = if left.is_updated() { 0 } else { 1 }..if right.is_updated() { self.width } else { self.width - 1 };
+        self.rows = if top.is_updated() { 0 } else { 1 }..if bottom.is_updated() { self.height } else { self.height - 1 };
+    }
+
+    pub fn reset(&mut self) {
+        self.psi_ez_x.fill(0.0);
+        self.psi_hy_x.fill(0.0);
+        self.psi_ez_y.fill(0.0);
+        self.psi_hx_y.fill(0.0);
+    }
+
+    pub fn save(&self, out: &mut Writer) {
+        for psi in [&self.psi_ez_x, &self.psi_hy_x, &self.psi_ez_y, &self.psi_hx_y] {
+            out.f64s(psi);
+        }
+    }
+
+    pub fn restore(&mut self, input: &mut Reader) -> Result<(), String> {
+        for psi in [&mut self.psi_ez_x, &mut self.psi_hy_x, &mut self.psi_ez_y, &mut self.psi_hx_y] {
+            input.f64s_into(psi, "CPML values")?;
+        }
+        Ok(())
+    }
+
+    /// Applies the CPML correction to Hx and Hy after the regular magnetic update.
+    /// The correction is scaled by the Db coefficient of each cell's material.
+    pub fn update_h(&mut self, ez: &[Real], hx: &mut [Real], hy: &mut [Real], materials: &[u8], coefficients: &[UpdateCoefficients]) {
+        let width = self.width;
+        let layer = &self.hy_x;
+        parallel::for_each_row(x_layer_rows(hy, &mut self.psi_hy_x, width, layer), |(y, hy, psi)| {
+            for (k, &x) in layer.positions.iter().enumerate() {
+                let idx = y * width + x;
+                let d = widen(ez[idx + 1] - ez[idx]);
+                psi[k] = layer.b[k] * psi[k] + layer.c[k] * d;
+                let db = coefficients[materials[idx] as usize].db;
+                hy[x] += (db * (layer.inv_kappa_m1[k] * d + psi[k])) as Real;
+            }
+        });
+
+        let layer = &self.hx_y;
+        parallel::for_each_row(y_layer_rows(hx, &mut self.psi_hx_y, width, layer), |(k, y, hx, psi)| {
+            for x in 0..width {
+                let idx = y * width + x;
+                let d = widen(ez[idx + width] - ez[idx]);
+                psi[x] = layer.b[k] * psi[x] + layer.c[k] * d;
+                let db = coefficients[materials[idx] as usize].db;
+                hx[x] -= (db * (layer.inv_kappa_m1[k] * d + psi[x])) as Real;
+            }
+        });
+    }
+
+    /// Applies the CPML correction to Ez after the regular electric update.
+    /// The correction is scaled by the Cb coefficient, so PEC cells (`cb = 0`) stay at zero.
+    pub fn update_e(&mut self, hx: &[Real], hy: &[Real], ez: &mut [Real], materials: &[u8], coefficients: &[UpdateCoefficients]) {
+        let width = self.width;
+        let (rows, columns) = (self.rows.clone(), self.columns.clone());
+        let layer = &self.ez_x;
+        let selected = x_layer_rows(ez, &mut self.psi_ez_x, width, layer).filter(|(y, _, _)| rows.contains(y));
+        parallel::for_each_row(selected, |(y, ez, psi)| {
+            for (k, &x) in layer.positions.iter().enumerate() {
+                let idx = y * width + x;
+                let d = widen(hy[idx] - hy[idx - 1]);
+                psi[k] = layer.b[k] * psi[k] + layer.c[k] * d;
+                let cb = coefficients[materials[idx] as usize].cb;
+                ez[x] += (cb * (layer.inv_kappa_m1[k] * d + psi[k])) as Real;
+            }
+        });
+
+        let layer = &self.ez_y;
+        parallel::for_each_row(y_layer_rows(ez, &mut self.psi_ez_y, width, layer), |(k, y, ez, psi)| {
+            for x in columns.clone() {
+                let idx = y * width + x;
+                let d = widen(hx[idx] - hx[idx - width]);
+                psi[x] = layer.b[k] * psi[x] + layer.c[k] * d;
+                let cb = coefficients[materials[idx] as usize].cb;
+                ez[x] -= (cb * (layer.inv_kappa_m1[k] * d + psi[x])) as Real;
+            }
+        });
+    }
+
+    /// TEz counterpart of `update_e`: corrects Ex and Ey after their regular update.
+    /// Ey shares the staggered x nodes of Hy and Ex the staggered y nodes of Hx.
+    pub fn update_tez_e(&mut self, hz: &[Real], ex: &mut [Real], ey: &mut [Real], materials: &[u8], coefficients: &[UpdateCoefficients]) {
+        let width = self.width;
+        let layer = &self.hy_x;
+        parallel::for_each_row(x_layer_rows(ey, &mut self.psi_hy_x, width, layer), |(y, ey, psi)| {
+            for (k, &x) in layer.positions.iter().enumerate() {
+                let idx = y * width + x;
+                let d = widen(hz[idx + 1] - hz[idx]);
+                psi[k] = layer.b[k] * psi[k] + layer.c[k] * d;
+                let cb = coefficients[materials[idx] as usize].cb;
+                ey[x] -= (cb * (layer.inv_kappa_m1[k] * d + psi[k])) as Real;
+            }
+        });
+
+        let layer = &self.hx_y;
+        parallel::for_each_row(y_layer_rows(ex, &mut self.psi_hx_y, width, layer), |(k, y, ex, psi)| {
+            for x in 0..width {
+                let idx = y * width + x;
+                let d = widen(hz[idx + width] - hz[idx]);
+                psi[x] = layer.b[k] * psi[x] + layer.c[k] * d;
+                let cb = coefficients[materials[idx] as usize].cb;
+                ex[x] += (cb * (layer.inv_kappa_m1[k] * d + psi[x])) as Real;
+            }
+        });
+    }
+
+    /// TEz counterpart of `update_h`: corrects Hz after its regular update.
+    pub fn update_tez_h(&mut self, ex: &[Real], ey: &[Real], hz: &mut [Real], materials: &[u8], coefficients: &[UpdateCoefficients]) {
+        let width = self.width;
+        let (rows, columns) = (self.rows.clone(), self.columns.clone());
+        let layer = &self.ez_x;
+        let selected = x_layer_rows(hz, &mut self.psi_ez_x, width, layer).filter(|(y, _, _)| rows.contains(y));
+        parallel::for_each_row(selected, |(y, hz, psi)| {
+            for (k, &x) in layer.positions.iter().enumerate() {
+                let idx = y * width + x;
+                let d = widen(ey[idx] - ey[idx - 1]);
+                psi[k] = layer.b[k] * psi[k] + layer.c[k] * d;
+                let db = coefficients[materials[idx] as usize].db;
+                hz[x] -= (db * (layer.inv_kappa_m1[k] * d + psi[k])) as Real;
+            }
+        });
+
+        let layer = &self.ez_y;
+        parallel::for_each_row(y_layer_rows(hz, &mut self.psi_ez_y, width, layer), |(k, y, hz, psi)| {
+            for x in columns.clone() {
+                let idx = y * width + x;
+                let d = widen(ex[idx] - ex[idx - width]);
+                psi[x] = layer.b[k] * psi[x] + layer.c[k] * d;
+                let db = coefficients[materials[idx] as usize].db;
+                hz[x] += (db * (layer.inv_kappa_m1[k] * d + psi[x])) as Real;
+            }
+        });
+    }
+}
+
+/// Every row `y` of `field` with its row of the psi array of an x layer (`y * layer.len() + k`).
+fn x_layer_rows<'a>(field: &'a mut [Real], psi: &'a mut [f64], width: usize, layer: &LayerProfile)
+    -> impl Iterator<Item = (usize, &'a mut [Real], &'a mut [f64])> {
+    // An empty layer has no psi rows, so the zip ends at once; `max(1)` only keeps `chunks_mut` valid.
+    field.chunks_mut(width).zip(psi.chunks_mut(layer.len().max(1))).enumerate().map(|(y, (row, psi))| (y, row, psi))
+}
+
+/// The rows of `field` inside a y layer, as `(k, y, row, psi row)` with the psi array laid out as `k * width + x`.
+fn y_layer_rows<'a>(field: &'a mut [Real], psi: &'a mut [f64], width: usize, layer: &'a LayerProfile)
+    -> impl Iterator<Item = (usize, usize, &'a mut [Real], &'a mut [f64])> {
+    field.chunks_mut(width).enumerate()
+        .filter(|(y, _)| layer.positions.binary_search(y).is_ok())
+        .zip(psi.chunks_mut(width))
+        .enumerate()
+        .map(|(k, ((y, row), psi))| (k, y, row, psi))
+}
@@ -0,0 +1,123 @@
+use crate::checkpoint::{Reader, Writer};
+use crate::precision::{widen, Real};
+use crate::parameters::{DftMonitorDefinition, Polarization};
+use crate::volume::parameters::FieldComponent;
+use std::f64::consts::PI;
+
+/// Accumulates `sum(v(t) * exp(-i 2 pi f t))` of one component over a rectangle of cells.
+#[derive(Debug, Clone)]
+pub struct DftMonitor {
+    pub name: String,
+    pub component: FieldComponent,
+    pub x_min: usize,
+    pub y_min: usize,
+    /// Size of the monitored rectangle; the maps are laid out as `y * width + x` within it.
+    pub width: usize,
+    pub height: usize,
+    pub frequencies: Vec<f64>,
+    start_time: f64,
+    /// Real and imaginary parts, one map per frequency.
+    re: Vec<Vec<f64>>,
+    im: Vec<Vec<f64>>,
+    samples: usize,
+}
+
+impl DftMonitor {
+    pub fn new(definition: &DftMonitorDefinition) -> Self {
+        let width = definition.x_max - definition.x_min + 1;
+        let height = definition.y_max - definition.y_min + 1;
+        let maps = vec![vec![0.0; width * height]; definition.frequencies.len()];
+        Self {
+            name: definition.name.clone(),
+            component: definition.component,
+            x_min: definition.x_min,
+            y_min: definition.y_min,
+            width,
+            height,
+            frequencies: definition.frequencies.clone(),
+            start_time: definition.start_time,
+            re: maps.clone(),
+            im: maps,
+            samples: 0,
+        }
+    }
+
+    /// Adds the samples of `field` (a full grid of `grid_width` columns) taken at time `t` (in steps).
+    pub fn accumulate(&mut self, field: &[Real], grid_width: usize, t: f64) {
+        if t < self.start_time {
+            return;
+        }
+        for (k, &frequency) in self.frequencies.iter().enumerate() {
+            let (sin, cos) = (2.0 * PI * frequency * t).sin_cos();
+            let (re, im) = (&mut self.re[k], &mut self.im[k]);
+            for y in 0..self.height {
+                let row = (self.y_min + y) * grid_width + self.x_min;
+                for x in 0..self.width {
+                    let v = widen(field[row + x]);
+                    re[y * self.width + x] += v * cos;
+                    im[y * self.width + x] -= v * sin;
+                }
+            }
+        }
+        self.samples += 1;
+    }
+
+    /// Number of time steps accumulated so far.
+    pub fn samples(&self) -> usize {
+        self.samples
+    }
+
+    /// Index of `frequency` in the monitor's list, matched to within a relative 1e-9.
+    pub fn frequency_index(&self, frequency: f64) -> Option<usize> {
+        self.frequencies.iter().position(|&f| (f - frequency).abs() <= 1e-9 * f.abs())
+    }
+
+    /// Magnitude map at frequency `index`, scaled by `2 / samples` so a steady sinusoid
+    /// of amplitude `A` observed over whole periods reads `A`.
+    pub fn magnitude(&self, index: usize) -> Vec<f64> {
+        let scale = if self.samples > 0 { 2.0 / self.samples as f64 } else { 0.0 };
+        self.re[index].iter().zip(&self.im[index]).map(|(re, im)| scale * re.hypot(*im)).collect()
+    }
+
+    /// Phase map (radians) at frequency `index`: `phi` for a field `A cos(2 pi f t + phi)`.
+    pub fn phase(&self, index: usize) -> Vec<f64> {
+        self.re[index].iter().zip(&self.im[index]).map(|(re, im)| im.atan2(*re)).collect()
+    }
+
+    /// Raw accumulated real and imaginary parts at frequency `index`.
+    pub fn spectrum(&self, index: usize) -> (&[f64], &[f64]) {
+        (&self.re[index], &self.im[index])
+    }
+
+    pub fn reset(&mut self) {
+        for map in self.re.iter_mut().chain(self.im.iter_mut()) {
+            map.fill(0.0);
+        }
+        self.samples = 0;
+    }
+
+    pub fn save(&self, out: &mut Writer) {
+        for map in self.re.iter().chain(&self.im) {
+            out.f64s(map);
+        }
+        out.usize(self.samples);
+    }
+
+    pub fn restore(&mut self, input: &mut Reader) -> Result<(), String> {
+        for map in self.re.iter_mut().chain(self.im.iter_mut()) {
+            input.f64s_into(map, "DFT values")?;
+        }
+        self.samples = input.usize()?;
+        Ok(())
+    }
+}
+
+/// Time level (in steps) of `component` at the end of step `time_step`. The component driven by
+/// the sources is advanced last and sits on whole steps; the other two lag by half a step.
+pub fn sample_time(component: FieldComponent, polarization: Polarization, time_step: usize) -> f64 {
+    let primary = match polarization {
+        Polarization::TMz => FieldComponent::Ez,
+        Polarization::TEz => FieldComponent::Hz,
+    };
+    if component == primary { time_step as f64 + 1.0 } else { time_step as f64 + 0.5 }
+}
@@ -0,0 +1,214 @@
+use crate::checkpoint::{Reader, Writer};
+use crate::precision::{widen, Real};
+use crate::materials::UpdateCoefficients;
+use crate::parameters::{DispersivePole, MaterialDefinition};
+use std::f64::consts::PI;
+
+/// Time-stepping coefficients of one pole, with the time step as unit.
+/// Polarisations are normalised (`p = P / eps0`) so they share the units of Ez.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PoleUpdate {
+    /// Drude and Lorentz: `p' = c1 * p + c2 * p_prev + c3 * E`, explicit in E.
+    Recursive { c1: f64, c2: f64, c3: f64 },
+    /// Debye: `p' = a * p + b * (E' + E)`, solved together with the E update.
+    Relaxation { a: f64, b: f64 },
+}
+
+impl PoleUpdate {
+    /// Builds the update of a pole given in per-time-step units.
+    pub fn new(pole: &DispersivePole) -> Self {
+        match *pole {
+            DispersivePole::Drude { plasma_frequency, collision_rate } => {
+                let wp = 2.0 * PI * plasma_frequency;
+                let den = 1.0 + collision_rate / 2.0;
+                PoleUpdate::Recursive {
+                    c1: 2.0 / den,
+                    c2: -(1.0 - collision_rate / 2.0) / den,
+                    c3: wp * wp / den,
+                }
+            },
+            DispersivePole::Lorentz { delta_epsilon, resonance_frequency, damping } => {
+                let w0 = 2.0 * PI * resonance_frequency;
+                let den = 1.0 + damping / 2.0;
+                PoleUpdate::Recursive {
+                    c1: (2.0 - w0 * w0) / den,
+                    c2: -(1.0 - damping / 2.0) / den,
+                    c3: delta_epsilon * w0 * w0 / den,
+                }
+            },
+            DispersivePole::Debye { delta_epsilon, relaxation_time } => {
+                let k = 1.0 / (2.0 * relaxation_time);
+                PoleUpdate::Relaxation {
+                    a: (1.0 - k) / (1.0 + k),
+                    b: k * delta_epsilon / (1.0 + k),
+                }
+            },
+        }
+    }
+}
+
+/// Complex relative permittivity `(re, im)` of a material at `frequency` (same units as its poles).
+/// Conductivity is not included.
+pub fn permittivity_at(material: &MaterialDefinition, frequency: f64) -> (f64, f64) {
+    let w = 2.0 * PI * frequency;
+    let mut re = material.relative_permittivity;
+    let mut im = 0.0;
+    for pole in &material.poles {
+        // num / (dr + i*di)
+        let (num, dr, di) = match *pole {
+            DispersivePole::Drude { plasma_frequency, collision_rate } => {
+                let wp = 2.0 * PI * plasma_frequency;
+                (-wp * wp, w * w, collision_rate * w)
+            },
+            DispersivePole::Lorentz { delta_epsilon, resonance_frequency, damping } => {
+                let w0 = 2.0 * PI * resonance_frequency;
+                (delta_epsilon * w0 * w0, w0 * w0 - w * w, -damping * w)
+            },
+            DispersivePole::Debye { delta_epsilon, relaxation_time } => (delta_epsilon, 1.0, -w * relaxation_time),
+        };
+        let mag = dr * dr + di * di;
+        re += num * dr / mag;
+        im -= num * di / mag;
+    }
+    (re, im)
+}
+
+/// Auxiliary polarisation arrays of every cell made of one dispersive material.
+#[derive(Debug, Clone)]
+pub struct DispersiveMaterial {
+    pub material: u8,
+    pub poles: Vec<PoleUpdate>,
+    /// Weight of the polarisation change in the E update: `1 / (eps_inf * (1 + loss))`.
+    pub cp: f64,
+    /// `1 + cp * sum(b)` over the Debye poles.
+    pub denom: f64,
+    pub cells: Vec<usize>,
+    /// Laid out as `cell * poles.len() + k`.
+    pub p: Vec<f64>,
+    pub p_prev: Vec<f64>,
+    /// Ez of every cell before the current E update.
+    pub e_prev: Vec<f64>,
+}
+
+/// Polarisation state of all dispersive materials in the grid.
+#[derive(Debug, Clone, Default)]
+pub struct DispersiveState {
+    pub materials: Vec<DispersiveMaterial>,
+}
+
+impl DispersiveState {
+    /// Collects the interior cells of every dispersive material. Returns `None` if there are none.
+    pub fn new(table: &[MaterialDefinition], coefficients: &[UpdateCoefficients], cell_materials: &[u8],
+               width: usize, height: usize, courant: f64) -> Option<Self> {
+        let mut materials = Vec::new();
+        for (id, definition) in table.iter().enumerate() {
+            if definition.poles.is_empty() {
+                continue;
+            }
+            let cells: Vec<usize> = cell_materials.iter().enumerate()
+                .filter(|(idx, &m)| {
+                    let (x, y) = (idx % width, idx / width);
+                    m as usize == id && x > 0 && y > 0 && x + 1 < width && y + 1 < height
+                })
+                .map(|(idx, _)| idx)
+                .collect();
+            if cells.is_empty() {
+                continue;
+            }
+
+            let poles: Vec<PoleUpdate> = definition.poles.iter().map(PoleUpdate::new).collect();
+            let cp = coefficients[id].cb / courant;
+            let debye_b: f64 = poles.iter().map(|p| match p {
+                PoleUpdate::Relaxation { b, .. } => *b,
+                PoleUpdate::Recursive { .. } => 0.0,
+            }).sum();
+            let slots = cells.len() * poles.len();
+            materials.push(DispersiveMaterial {
+                material: id as u8,
+                cp,
+                denom: 1.0 + cp * debye_b,
+                p: vec![0.0; slots],
+                p_prev: vec![0.0; slots],
+                e_prev: vec![0.0; cells.len()],
+                poles,
+                cells,
+            });
+        }
+        if materials.is_empty() { None } else { Some(Self { materials }) }
+    }
+
+    pub fn reset(&mut self) {
+        for m in &mut self.materials {
+            m.p.fill(0.0);
+            m.p_prev.fill(0.0);
+            m.e_prev.fill(0.0);
+        }
+    }
+
+    pub fn save(&self, out: &mut Writer) {
+        out.usize(self.materials.len());
+        for m in &self.materials {
+            out.f64s(&m.p);
+            out.f64s(&m.p_prev);
+            out.f64s(&m.e_prev);
+        }
+    }
+
+    pub fn restore(&mut self, input: &mut Reader) -> Result<(), String> {
+        input.len(self.materials.len(), "dispersive materials")?;
+        for m in &mut self.materials {
+            input.f64s_into(&mut m.p, "polarisation values")?;
+            input.f64s_into(&mut m.p_prev, "polarisation values")?;
+            input.f64s_into(&mut m.e_prev, "polarisation values")?;
+        }
+        Ok(())
+    }
+
+    /// Runs before the E update: remembers Ez and advances the Drude/Lorentz polarisations.
+    pub fn update_pre(&mut self, ez: &[Real]) {
+        for m in &mut self.materials {
+            let n = m.poles.len();
+            for (i, &idx) in m.cells.iter().enumerate() {
+                let e = widen(ez[idx]);
+                m.e_prev[i] = e;
+                for (k, pole) in m.poles.iter().enumerate() {
+                    if let PoleUpdate::Recursive { c1, c2, c3 } = *pole {
+                        let slot = i * n + k;
+                        let next = c1 * m.p[slot] + c2 * m.p_prev[slot] + c3 * e;
+                        m.p_prev[slot] = m.p[slot];
+                        m.p[slot] = next;
+                    }
+                }
+            }
+        }
+    }
+
+    /// Runs after the E update: subtracts the polarisation current from Ez and
+    /// advances the Debye polarisations with the new field.
+    pub fn update_post(&mut self, ez: &mut [Real]) {
+        for m in &mut self.materials {
+            let n = m.poles.len();
+            for (i, &idx) in m.cells.iter().enumerate() {
+                let e_old = m.e_prev[i];
+                let mut dp = 0.0;
+                for (k, pole) in m.poles.iter().enumerate() {
+                    let slot = i * n + k;
+                    dp += match *pole {
+                        PoleUpdate::Recursive { .. } => m.p[slot] - m.p_prev[slot],
+                        PoleUpdate::Relaxation { a, b } => (a - 1.0) * m.p[slot] + b * e_old,
+                    };
+                }
+                let e_new = (widen(ez[idx]) - m.cp * dp) / m.denom;
+                ez[idx] = e_new as Real;
+
+                for (k, pole) in m.poles.iter().enumerate() {
+                    if let PoleUpdate::Relaxation { a, b } = *pole {
+                        let slot = i * n + k;
+                        m.p_prev[slot] = m.p[slot];
+                        m.p[slot] = a * m.p[slot] + b * (e_new + e_old);
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,211 @@
+use crate::checkpoint::{Reader, Writer};
+use crate::precision::widen;
+use crate::parameters::{FarFieldDefinition, Polarization};
+use crate::state::SimulationState;
+use crate::volume::parameters::FieldComponent;
+use serde::Serialize;
+use std::f64::consts::PI;
+
+/// Pattern angles used when a far-field monitor does not set its own.
+pub const DEFAULT_FAR_FIELD_ANGLES: usize = 360;
+
+/// One contour segment: the primary-field nodes on either side of it, the tangential staggered
+/// node on it, its centre relative to the contour centre and its outward normal.
+#[derive(Debug, Clone)]
+struct ContourSample {
+    a: usize,
+    b: usize,
+    c: usize,
+    component: FieldComponent,
+    /// Sign turning `primary * staggered` into the outward Poynting component, as for flux lines.
+    sign: f64,
+    x: f64,
+    y: f64,
+    normal: (f64, f64),
+}
+
+/// Directivity against angle at one frequency.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct FarFieldPattern {
+    pub frequency: f64,
+    /// Angles in degrees from +x towards +y.
+    pub angles: Vec<f64>,
+    /// Radiation intensity over its average on the circle (`2 pi U / P`), so a line source reads 1.
+    pub directivity: Vec<f64>,
+}
+
+impl FarFieldPattern {
+    /// Directivity in dBi, floored at -100 dB.
+    pub fn directivity_db(&self) -> Vec<f64> {
+        self.directivity.iter().map(|d| 10.0 * d.max(1e-10).log10()).collect()
+    }
+
+    /// Angle (degrees) and directivity of the main lobe.
+    pub fn peak(&self) -> (f64, f64) {
+        self.angles.iter().zip(&self.directivity).fold((0.0, f64::MIN), |best, (&a, &d)| if d > best.1 { (a, d) } else { best })
+    }
+
+    /// The pattern as `angle_deg,directivity,directivity_db` lines with a header.
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from("angle_deg,directivity,directivity_db\n");
+        for ((angle, d), db) in self.angles.iter().zip(&self.directivity).zip(self.directivity_db()) {
+            csv.push_str(&format!("{},{},{}\n", angle, d, db));
+        }
+        csv
+    }
+}
+
+/// Near-to-far-field transformation. Accumulates running DFTs of the tangential fields on a closed
+/// contour; the equivalent currents `J = n x H` and `M = E x n` then radiate the 2D far field.
+#[derive(Debug, Clone)]
+pub struct FarFieldMonitor {
+    pub name: String,
+    pub frequencies: Vec<f64>,
+    angles: usize,
+    samples: Vec<ContourSample>,
+    /// `(re, im)` of the primary field averaged onto each segment and of the staggered field,
+    /// one list per frequency.
+    primary: Vec<Vec<(f64, f64)>>,
+    staggered: Vec<Vec<(f64, f64)>>,
+}
+
+impl FarFieldMonitor {
+    pub fn new(definition: &FarFieldDefinition, width: usize, polarization: Polarization) -> Self {
+        let d = definition;
+        let (vertical, horizontal) = match polarization {
+            Polarization::TMz => ((FieldComponent::Hy, -1.0), (FieldComponent::Hx, 1.0)),
+            Polarization::TEz => ((FieldComponent::Ey, 1.0), (FieldComponent::Ex, -1.0)),
+        };
+        let centre = ((d.x_min + d.x_max) as f64 / 2.0, (d.y_min + d.y_max) as f64 / 2.0);
+        let at = |x: usize, y: usize| y * width + x;
+        let mut samples = Vec::new();
+        for y in d.y_min..=d.y_max {
+            for (x, outward) in [(d.x_min - 1, -1.0), (d.x_max, 1.0)] {
+                samples.push(ContourSample {
+                    a: at(x, y), b: at(x + 1, y), c: at(x, y),
+                    component: vertical.0, sign: vertical.1,
+                    x: x as f64 + 0.5 - centre.0, y: y as f64 - centre.1,
+                    normal: (outward, 0.0),
+                });
+            }
+        }
+        for x in d.x_min..=d.x_max {
+            for (y, outward) in [(d.y_min - 1, -1.0), (d.y_max, 1.0)] {
+                samples.push(ContourSample {
+                    a: at(x, y), b: at(x, y + 1), c: at(x, y),
+                    component: horizontal.0, sign: horizontal.1,
+                    x: x as f64 - centre.0, y: y as f64 + 0.5 - centre.1,
+                    normal: (0.0, outward),
+                });
+            }
+        }
+        let spectra = vec![vec![(0.0, 0.0); samples.len()]; d.frequencies.len()];
+        Self {
+            name: d.name.clone(),
+            frequencies: d.frequencies.clone(),
+            angles: d.angles.unwrap_or(DEFAULT_FAR_FIELD_ANGLES),
+            samples,
+            primary: spectra.clone(),
+            staggered: spectra,
+        }
+    }
+
+    /// Adds the fields at the end of the current step.
+    pub fn accumulate(&mut self, state: &SimulationState) {
+        let primary = state.primary_field();
+        // The primary field sits on whole steps and the staggered one half a step behind (see `dft::sample_time`).
+        let t_primary = state.time_step as f64 + 1.0;
+        for (k, &frequency) in self.frequencies.iter().enumerate() {
+            let (sin_p, cos_p) = (2.0 * PI * frequency * t_primary).sin_cos();
+            let (sin_s, cos_s) = (2.0 * PI * frequency * (t_primary - 0.5)).sin_cos();
+            for (i, sample) in self.samples.iter().enumerate() {
+                let p = 0.5 * (widen(primary[sample.a]) + widen(primary[sample.b]));
+                let s = widen(state.field(sample.component)[sample.c]);
+                let (pr, pi) = &mut self.primary[k][i];
+                *pr += p * cos_p;
+                *pi -= p * sin_p;
+                let (sr, si) = &mut self.staggered[k][i];
+                *sr += s * cos_s;
+                *si -= s * sin_s;
+            }
+        }
+    }
+
+    /// Far-field pattern at frequency `index`. The contour is assumed to lie in vacuum.
+    /// Averaging the primary field onto the contour costs a few percent of ripple below
+    /// about 20 cells per wavelength.
+    pub fn pattern(&self, index: usize, courant: f64) -> FarFieldPattern {
+        let frequency = self.frequencies[index];
+        let angles: Vec<f64> = (0..self.angles).map(|n| 360.0 * n as f64 / self.angles as f64).collect();
+        let intensity: Vec<f64> = angles
+            .iter()
+            .map(|angle| {
+                let (sin, cos) = angle.to_radians().sin_cos();
+                let k = numerical_wavenumber(frequency, courant, cos, sin);
+                let (mut re, mut im) = (0.0, 0.0);
+                for (i, sample) in self.samples.iter().enumerate() {
+                    // Both currents projected on the observation direction: the electric current
+                    // radiates through the tangential staggered field, the magnetic one through the primary.
+                    let (p, s) = (self.primary[index][i], self.staggered[index][i]);
+                    let outward = sample.normal.0 + sample.normal.1;
+                    let obliquity = sample.normal.0 * cos + sample.normal.1 * sin;
+                    let source = (sample.sign * outward * s.0 + obliquity * p.0, sample.sign * outward * s.1 + obliquity * p.1);
+                    let (phase_sin, phase_cos) = (k * (sample.x * cos + sample.y * sin)).sin_cos();
+                    re += source.0 * phase_cos - source.1 * phase_sin;
+                    im += source.0 * phase_sin + source.1 * phase_cos;
+                }
+                re * re + im * im
+            })
+            .collect();
+        let mean = intensity.iter().sum::<f64>() / intensity.len() as f64;
+        let directivity = intensity.iter().map(|u| if mean > 0.0 { u / mean } else { 0.0 }).collect();
+        FarFieldPattern { frequency, angles, directivity }
+    }
+
+    pub fn reset(&mut self) {
+        for spectrum in self.primary.iter_mut().chain(self.staggered.iter_mut()) {
+            spectrum.fill((0.0, 0.0));
+        }
+    }
+
+    pub fn save(&self, out: &mut Writer) {
+        for spectrum in self.primary.iter().chain(&self.staggered) {
+            out.usize(spectrum.len());
+            for &(re, im) in spectrum {
+                out.f64(re);
+                out.f64(im);
+            }
+        }
+    }
+
+    pub fn restore(&mut self, input: &mut Reader) -> Result<(), String> {
+        for spectrum in self.primary.iter_mut().chain(self.staggered.iter_mut()) {
+            input.len(spectrum.len(), "far-field samples")?;
+            for value in spectrum.iter_mut() {
+                *value = (input.f64()?, input.f64()?);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Wavenumber (radians per cell) of a grid wave of `frequency` travelling along `(cos, sin)`.
+///
+/// Solves the Yee dispersion relation `sin^2(k cos/2) + sin^2(k sin/2) = (sin(pi f) / S)^2` by
+/// bisection. The numerical wave is slightly slower than light, and using its wavenumber keeps
+/// the phases of distant parts of the contour consistent.
+fn numerical_wavenumber(frequency: f64, courant: f64, cos: f64, sin: f64) -> f64 {
+    let target = ((PI * frequency).sin() / courant).powi(2);
+    let residual = |k: f64| (k * cos / 2.0).sin().powi(2) + (k * sin / 2.0).sin().powi(2) - target;
+    let physical = 2.0 * PI * frequency / courant;
+    // Above the physical wavenumber the residual grows until the grid stops resolving the wave.
+    let (mut low, mut high) = (0.0, physical);
+    while residual(high) < 0.0 && high < PI {
+        high = (high * 1.1).min(PI);
+    }
+    for _ in 0..60 {
+        let mid = 0.5 * (low + high);
+        if residual(mid) < 0.0 { low = mid } else { high = mid }
+    }
+    0.5 * (low + high)
+}
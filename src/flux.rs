@@ -0,0 +1,191 @@
+use crate::checkpoint::{Reader, Writer};
+use crate::precision::widen;
+use crate::parameters::{FluxLineDefinition, Polarization};
+use crate::state::SimulationState;
+use crate::volume::parameters::FieldComponent;
+use std::f64::consts::PI;
+
+/// Poynting flux through an axis-aligned line, in the time domain and at a list of frequencies.
+///
+/// Units match `electromagnetic_energy`: the power is the energy crossing the line per step.
+#[derive(Debug, Clone)]
+pub struct FluxLine {
+    pub name: String,
+    pub frequencies: Vec<f64>,
+    /// The two primary-field nodes on either side of the line and the staggered node between them.
+    cells: Vec<(usize, usize, usize)>,
+    /// Staggered component read between the nodes, and the sign that turns
+    /// `primary * staggered` into the normal component of `E x H`.
+    staggered: FieldComponent,
+    sign: f64,
+    power: f64,
+    energy: f64,
+    /// Running DFTs of the primary field (averaged onto the line) and of the staggered component,
+    /// one `(re, im)` map per frequency.
+    primary_spectrum: Vec<(Vec<f64>, Vec<f64>)>,
+    staggered_spectrum: Vec<(Vec<f64>, Vec<f64>)>,
+}
+
+impl FluxLine {
+    pub fn new(definition: &FluxLineDefinition, width: usize, polarization: Polarization) -> Self {
+        let d = definition;
+        let (cells, staggered, sign): (Vec<_>, _, _) = if d.x0 == d.x1 {
+            // Sx = -Ez Hy (TMz) or Ey Hz (TEz).
+            let cells = (d.y0.min(d.y1)..=d.y0.max(d.y1)).map(|y| (y * width + d.x0, y * width + d.x0 + 1, y * width + d.x0)).collect();
+            match polarization {
+                Polarization::TMz => (cells, FieldComponent::Hy, -1.0),
+                Polarization::TEz => (cells, FieldComponent::Ey, 1.0),
+            }
+        } else {
+            // Sy = Ez Hx (TMz) or -Ex Hz (TEz).
+            let cells = (d.x0.min(d.x1)..=d.x0.max(d.x1)).map(|x| (d.y0 * width + x, (d.y0 + 1) * width + x, d.y0 * width + x)).collect();
+            match polarization {
+                Polarization::TMz => (cells, FieldComponent::Hx, 1.0),
+                Polarization::TEz => (cells, FieldComponent::Ex, -1.0),
+            }
+        };
+        let maps = vec![(vec![0.0; cells.len()], vec![0.0; cells.len()]); d.frequencies.len()];
+        Self {
+            name: d.name.clone(),
+            frequencies: d.frequencies.clone(),
+            cells,
+            staggered,
+            sign,
+            power: 0.0,
+            energy: 0.0,
+            primary_spectrum: maps.clone(),
+            staggered_spectrum: maps,
+        }
+    }
+
+    /// Adds the flux of the current step. Runs at the end of `step`, after the sources and boundaries.
+    pub fn update(&mut self, state: &SimulationState) {
+        let primary = state.primary_field();
+        let staggered = state.field(self.staggered);
+        // Centre the staggered field on the primary one in time, as `electromagnetic_energy` does.
+        let w = state.width;
+        let sum: f64 = self.cells.iter().map(|&(a, b, c)| {
+            let centred = 0.5 * (widen(staggered[c]) + advanced(state, self.staggered, c % w, c / w));
+            0.5 * (widen(primary[a]) + widen(primary[b])) * centred
+        }).sum();
+        self.power = state.courant * self.sign * sum;
+        self.energy += self.power;
+
+        // The primary field sits on whole steps and the staggered one half a step behind (see `dft::sample_time`).
+        let t_primary = state.time_step as f64 + 1.0;
+        let t_staggered = t_primary - 0.5;
+        for (k, &frequency) in self.frequencies.iter().enumerate() {
+            let (sin_p, cos_p) = (2.0 * PI * frequency * t_primary).sin_cos();
+            let (sin_s, cos_s) = (2.0 * PI * frequency * t_staggered).sin_cos();
+            let (p_re, p_im) = &mut self.primary_spectrum[k];
+            let (s_re, s_im) = &mut self.staggered_spectrum[k];
+            for (i, &(a, b, c)) in self.cells.iter().enumerate() {
+                let (p, s) = (0.5 * (widen(primary[a]) + widen(primary[b])), widen(staggered[c]));
+                p_re[i] += p * cos_p;
+                p_im[i] -= p * sin_p;
+                s_re[i] += s * cos_s;
+                s_im[i] -= s * sin_s;
+            }
+        }
+    }
+
+    /// Power crossing the line during the latest step.
+    pub fn power(&self) -> f64 {
+        self.power
+    }
+
+    /// Energy that has crossed the line since the start (or the last reset).
+    pub fn energy(&self) -> f64 {
+        self.energy
+    }
+
+    /// Flux spectrum `Re(sum E(f) H*(f))` at each frequency, signed like `power`.
+    /// It is unnormalised: compare lines or runs by their ratio.
+    pub fn spectrum(&self) -> Vec<f64> {
+        self.primary_spectrum.iter().zip(&self.staggered_spectrum).map(|((p_re, p_im), (s_re, s_im))| {
+            let sum: f64 = (0..self.cells.len()).map(|i| p_re[i] * s_re[i] + p_im[i] * s_im[i]).sum();
+            self.sign * sum
+        }).collect()
+    }
+
+    pub fn reset(&mut self) {
+        self.power = 0.0;
+        self.energy = 0.0;
+        for (re, im) in self.primary_spectrum.iter_mut().chain(self.staggered_spectrum.iter_mut()) {
+            re.fill(0.0);
+            im.fill(0.0);
+        }
+    }
+
+    pub fn save(&self, out: &mut Writer) {
+        out.f64(self.power);
+        out.f64(self.energy);
+        for (re, im) in self.primary_spectrum.iter().chain(&self.staggered_spectrum) {
+            out.f64s(re);
+            out.f64s(im);
+        }
+    }
+
+    pub fn restore(&mut self, input: &mut Reader) -> Result<(), String> {
+        self.power = input.f64()?;
+        self.energy = input.f64()?;
+        for (re, im) in self.primary_spectrum.iter_mut().chain(self.staggered_spectrum.iter_mut()) {
+            input.f64s_into(re, "flux spectrum values")?;
+            input.f64s_into(im, "flux spectrum values")?;
+        }
+        Ok(())
+    }
+}
+
+/// Electromagnetic energy `1/2 * sum(eps_r * |E|^2 + mu_r * |H|^2)` over the cells of the rectangle
+/// `(x_min, y_min, x_max, y_max)` (inclusive), or over the whole grid.
+///
+/// The staggered components lag half a step behind the primary one, so their square is replaced
+/// by the product of their current value and the value the next update will give them. This is
+/// the energy the Yee scheme conserves exactly in lossless regions; it excludes the energy held by
+/// dispersive polarisations and is not meaningful inside absorbing layers.
+pub fn electromagnetic_energy(state: &SimulationState, region: Option<(usize, usize, usize, usize)>) -> f64 {
+    let (x_min, y_min, x_max, y_max) = region.unwrap_or((0, 0, state.width - 1, state.height - 1));
+    let primary = state.primary_field();
+    let staggered = match state.polarization {
+        Polarization::TMz => [FieldComponent::Hx, FieldComponent::Hy],
+        Polarization::TEz => [FieldComponent::Ex, FieldComponent::Ey],
+    };
+    let mut total = 0.0;
+    for y in y_min..=y_max {
+        for x in x_min..=x_max {
+            let idx = y * state.width + x;
+            let material = &state.material_table[state.materials[idx] as usize];
+            let (eps, mu) = (material.relative_permittivity, material.relative_permeability);
+            let products: f64 = staggered.iter().map(|&c| widen(state.field(c)[idx]) * advanced(state, c, x, y)).sum();
+            let p = widen(primary[idx]);
+            total += 0.5 * match state.polarization {
+                Polarization::TMz => eps * p * p + mu * products,
+                Polarization::TEz => mu * p * p + eps * products,
+            };
+        }
+    }
+    total
+}
+
+/// Value a staggered component at `(x, y)` takes after its next regular update from the current
+/// primary field. Nodes on the far edge, which are never updated, keep their value.
+fn advanced(state: &SimulationState, component: FieldComponent, x: usize, y: usize) -> f64 {
+    let w = state.width;
+    let idx = y * w + x;
+    let value = widen(state.field(component)[idx]);
+    let (next, sign) = match component {
+        FieldComponent::Hx if y + 1 < state.height => (idx + w, -1.0),
+        FieldComponent::Hy if x + 1 < w => (idx + 1, 1.0),
+        FieldComponent::Ex if y + 1 < state.height => (idx + w, 1.0),
+        FieldComponent::Ey if x + 1 < w => (idx + 1, -1.0),
+        _ => return value,
+    };
+    let c = &state.coefficients[state.materials[idx] as usize];
+    let (keep, gain) = match state.polarization {
+        Polarization::TMz => (c.da, c.db),
+        Polarization::TEz => (c.ca, c.cb),
+    };
+    let primary = state.primary_field();
+    keep * value + sign * gain * (widen(primary[next]) - widen(primary[idx]))
+}
@@ -0,0 +1,157 @@
+//! Row kernels behind the field updates.
+//!
+//! Each kernel works on whole rows passed as slices, so the inner loops carry no index
+//! arithmetic or bounds checks. Rows made of a single material take a fast path with scalar
+//! coefficients, which the compiler vectorises (or the `simd` feature does explicitly); mixed
+//! rows look the coefficients up per cell. Both paths perform the same floating-point
+//! operations as a per-cell update, so results do not depend on the path taken.
+
+use crate::materials::UpdateCoefficients;
+use crate::precision::Real;
+
+/// Which pair of update coefficients a field uses.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Update {
+    /// `ca`, `cb`: electric fields.
+    Electric,
+    /// `da`, `db`: magnetic fields.
+    Magnetic,
+}
+
+impl Update {
+    fn pick(self, c: &UpdateCoefficients) -> (Real, Real) {
+        match self {
+            Update::Electric => (c.ca as Real, c.cb as Real),
+            Update::Magnetic => (c.da as Real, c.db as Real),
+        }
+    }
+}
+
+/// Material shared by every cell of `materials`, if there is one.
+fn uniform(materials: &[u8]) -> Option<u8> {
+    let first = *materials.first()?;
+    materials.iter().all(|&m| m == first).then_some(first)
+}
+
+/// `out = keep * out + sign * gain * (plus - minus)` along a row.
+/// `sign` is +1 or -1; negating the gain is exact, so it matches a subtraction bit for bit.
+#[allow(clippy::too_many_arguments)]
+pub fn difference_row(out: &mut [Real], plus: &[Real], minus: &[Real], materials: &[u8],
+                      coefficients: &[UpdateCoefficients], update: Update, sign: Real) {
+    if let Some(material) = uniform(materials) {
+        let (keep, gain) = update.pick(&coefficients[material as usize]);
+        let done = simd::difference(out, plus, minus, keep, sign * gain);
+        for ((o, p), m) in out[done..].iter_mut().zip(&plus[done..]).zip(&minus[done..]) {
+            *o = keep * *o + sign * gain * (p - m);
+        }
+        return;
+    }
+    for (((o, p), m), &material) in out.iter_mut().zip(plus).zip(minus).zip(materials) {
+        let (keep, gain) = update.pick(&coefficients[material as usize]);
+        *o = keep * *o + sign * gain * (p - m);
+    }
+}
+
+/// `out = keep * out + gain * ((a1 - a0) - (b1 - b0))` along a row: the out-of-plane curl.
+#[allow(clippy::too_many_arguments)]
+pub fn curl_row(out: &mut [Real], a1: &[Real], a0: &[Real], b1: &[Real], b0: &[Real], materials: &[u8],
+                coefficients: &[UpdateCoefficients], update: Update) {
+    if let Some(material) = uniform(materials) {
+        let (keep, gain) = update.pick(&coefficients[material as usize]);
+        let done = simd::curl(out, [a1, a0, b1, b0], keep, gain);
+        let rest = out[done..].iter_mut().zip(&a1[done..]).zip(&a0[done..]).zip(&b1[done..]).zip(&b0[done..]);
+        for ((((o, a1), a0), b1), b0) in rest {
+            *o = keep * *o + gain * ((a1 - a0) - (b1 - b0));
+        }
+        return;
+    }
+    let cells = out.iter_mut().zip(a1).zip(a0).zip(b1).zip(b0).zip(materials);
+    for (((((o, a1), a0), b1), b0), &material) in cells {
+        let (keep, gain) = update.pick(&coefficients[material as usize]);
+        *o = keep * *o + gain * ((a1 - a0) - (b1 - b0));
+    }
+}
+
+/// Explicit two-lane kernels for uniform rows. Each returns how many leading cells it updated;
+/// the caller finishes the rest. Without the `simd` feature, with `f32` fields or on other
+/// targets, they do nothing.
+mod simd {
+
+    #[cfg(all(feature = "simd", not(feature = "f32"), target_arch = "x86_64"))]
+    pub fn difference(out: &mut [f64], plus: &[f64], minus: &[f64], keep: f64, gain: f64) -> usize {
+        use std::arch::x86_64::*;
+        let n = out.len().min(plus.len()).min(minus.len()) & !1;
+        // SAFETY: SSE2 is part of the x86_64 baseline and every access is below `n`.
+        unsafe {
+            let (keep, gain) = (_mm_set1_pd(keep), _mm_set1_pd(gain));
+            for i in (0..n).step_by(2) {
+                let d = _mm_sub_pd(_mm_loadu_pd(plus.as_ptr().add(i)), _mm_loadu_pd(minus.as_ptr().add(i)));
+                let o = out.as_mut_ptr().add(i);
+                _mm_storeu_pd(o, _mm_add_pd(_mm_mul_pd(keep, _mm_loadu_pd(o)), _mm_mul_pd(gain, d)));
+            }
+        }
+        n
+    }
+
+    #[cfg(all(feature = "simd", not(feature = "f32"), target_arch = "x86_64"))]
+    pub fn curl(out: &mut [f64], [a1, a0, b1, b0]: [&[f64]; 4], keep: f64, gain: f64) -> usize {
+        use std::arch::x86_64::*;
+        let n = [a1.len(), a0.len(), b1.len(), b0.len()].into_iter().fold(out.len(), usize::min) & !1;
+        // SAFETY: SSE2 is part of the x86_64 baseline and every access is below `n`.
+        unsafe {
+            let (keep, gain) = (_mm_set1_pd(keep), _mm_set1_pd(gain));
+            let load = |s: &[f64], i: usize| _mm_loadu_pd(s.as_ptr().add(i));
+            for i in (0..n).step_by(2) {
+                let da = _mm_sub_pd(load(a1, i), load(a0, i));
+                let db = _mm_sub_pd(load(b1, i), load(b0, i));
+                let o = out.as_mut_ptr().add(i);
+                _mm_storeu_pd(o, _mm_add_pd(_mm_mul_pd(keep, _mm_loadu_pd(o)), _mm_mul_pd(gain, _mm_sub_pd(da, db))));
+            }
+        }
+        n
+    }
+
+    #[cfg(all(feature = "simd", not(feature = "f32"), target_arch = "wasm32", target_feature = "simd128"))]
+    pub fn difference(out: &mut [f64], plus: &[f64], minus: &[f64], keep: f64, gain: f64) -> usize {
+        use std::arch::wasm32::*;
+        let n = out.len().min(plus.len()).min(minus.len()) & !1;
+        // SAFETY: simd128 is enabled at compile time and every access is below `n`.
+        unsafe {
+            let (keep, gain) = (f64x2_splat(keep), f64x2_splat(gain));
+            for i in (0..n).step_by(2) {
+                let d = f64x2_sub(v128_load(plus.as_ptr().add(i) as *const v128), v128_load(minus.as_ptr().add(i) as *const v128));
+                let o = out.as_mut_ptr().add(i) as *mut v128;
+                v128_store(o, f64x2_add(f64x2_mul(keep, v128_load(o)), f64x2_mul(gain, d)));
+            }
+        }
+        n
+    }
+
+    #[cfg(all(feature = "simd", not(feature = "f32"), target_arch = "wasm32", target_feature = "simd128"))]
+    pub fn curl(out: &mut [f64], [a1, a0, b1, b0]: [&[f64]; 4], keep: f64, gain: f64) -> usize {
+        use std::arch::wasm32::*;
+        let n = [a1.len(), a0.len(), b1.len(), b0.len()].into_iter().fold(out.len(), usize::min) & !1;
+        // SAFETY: simd128 is enabled at compile time and every access is below `n`.
+        unsafe {
+            let (keep, gain) = (f64x2_splat(keep), f64x2_splat(gain));
+            let load = |s: &[f64], i: usize| v128_load(s.as_ptr().add(i) as *const v128);
+            for i in (0..n).step_by(2) {
+                let da = f64x2_sub(load(a1, i), load(a0, i));
+                let db = f64x2_sub(load(b1, i), load(b0, i));
+                let o = out.as_mut_ptr().add(i) as *mut v128;
+                v128_store(o, f64x2_add(f64x2_mul(keep, v128_load(o)), f64x2_mul(gain, f64x2_sub(da, db))));
+            }
+        }
+        n
+    }
+
+    #[cfg(not(all(feature = "simd", not(feature = "f32"), any(target_arch = "x86_64", all(target_arch = "wasm32", target_feature = "simd128")))))]
+    pub fn difference(_: &mut [super::Real], _: &[super::Real], _: &[super::Real], _: super::Real, _: super::Real) -> usize {
+        0
+    }
+
+    #[cfg(not(all(feature = "simd", not(feature = "f32"), any(target_arch = "x86_64", all(target_arch = "wasm32", target_feature = "simd128")))))]
+    pub fn curl(_: &mut [super::Real], _: [&[super::Real]; 4], _: super::Real, _: super::Real) -> usize {
+        0
+    }
+}
@@ -1,17 +1,38 @@
 pub mod parameters;
 pub mod state;
 pub mod engine;
+pub mod parallel;
+pub mod kernels;
+pub mod precision;
+pub mod checkpoint;
+pub mod cpml;
+pub mod boundaries;
+pub mod mur;
+pub mod materials;
+pub mod dispersive;
+pub mod tfsf;
+pub mod probes;
+pub mod dft;
+pub mod farfield;
+pub mod flux;
+pub mod lumped;
+pub mod ports;
+pub mod modes;
 pub mod rasterizer;
 pub mod utils;
 pub mod step;
+pub mod run;
 pub mod renderer;
 pub mod comms;
+pub mod volume;
 
 use wasm_bindgen::prelude::*;
 use parameters::SimulationParameters;
 use state::SimulationState;
 use comms::modulator::{Modulator, ModulationScheme};
 use comms::demodulator::Demodulator;
+use volume::parameters::{Axis, FieldComponent, VolumeParameters};
+use volume::state::VolumeState;
 
 #[wasm_bindgen]
 pub struct FdtdSimulator {
@@ -20,6 +41,10 @@ pub struct FdtdSimulator {
     modulator: Modulator,
     demodulator: Demodulator,
     is_transmitting: bool,
+    // Physical duration of one step, when the grid was configured in metres.
+    time_step_seconds: Option<f64>,
+    // Cell size in metres, to report lumped-element ports in volts and amperes.
+    cell_size: Option<f64>,
 }
 
 #[wasm_bindgen]
@@ -28,29 +53,7 @@ impl FdtdSimulator {
     pub fn new(val: JsValue) -> Result<FdtdSimulator, JsValue> {
         utils::set_panic_hook();
         let params: SimulationParameters = serde_wasm_bindgen::from_value(val)?;
-        
-        if let Err(e) = parameters::validate_parameters(&params) {
-            return Err(JsValue::from_str(&e));
-        }
-
-        let width = params.width;
-        let height = params.height;
-        
-        // Comms Parameters from Definition
-        let samples_per_symbol = params.comms.symbol_duration;
-        let carrier = params.comms.carrier_frequency;
-        let dev = params.comms.deviation;
-        
-        let freq_0 = carrier - dev;
-        let freq_1 = carrier + dev;
-
-        Ok(FdtdSimulator {
-            params,
-            state: SimulationState::new(width, height),
-            modulator: Modulator::new(freq_0, freq_1, samples_per_symbol),
-            demodulator: Demodulator::new(freq_0, freq_1, samples_per_symbol),
-            is_transmitting: false,
-        })
+        FdtdSimulator::from_parameters(params).map_err(|e| JsValue::from_str(&e))
     }
 
     /// Advances the simulation by one step.
@@ -60,7 +63,7 @@ impl FdtdSimulator {
         // 1. Modulator Override if transmitting
         if self.is_transmitting {
             if let Some((freq, amp_factor)) = self.modulator.next_modulation() {
-                 let max_amplitude = self.params.source.amplitude;
+                 let max_amplitude = self.params.transmitter().amplitude;
                  let amplitude = amp_factor * max_amplitude;
                  
                  // We need to compute the instantaneous value of the signal (Sine wave)
@@ -77,6 +80,62 @@ impl FdtdSimulator {
         
         // 2. Run Physics
         step::step(&self.params, &mut self.state, forced_source);
+
+        // 3. Demodulate the receiver probe, if one is selected, with the receiver noise added
+        if let Some(name) = &self.params.comms.receiver_probe {
+            if let Some(val) = self.state.probe(name).and_then(|p| p.latest()) {
+                let noise = comms::noise::receiver_noise(self.state.time_step, self.params.comms.receiver_noise);
+                self.demodulator.process_sample(val + noise, self.state.time_step as f64);
+            }
+        }
+    }
+
+    /// Advances up to `steps` steps in one call, stopping at `duration_steps`.
+    /// Returns `{ steps, time_step, duration_steps, progress, finished, condition_met }`.
+    pub fn run(&mut self, steps: usize) -> JsValue {
+        serde_wasm_bindgen::to_value(&self.advance(steps, None)).unwrap()
+    }
+
+    /// Advances until `duration_steps` is reached. Blocks until then, so keep runs short in a browser.
+    pub fn run_until_done(&mut self) -> JsValue {
+        serde_wasm_bindgen::to_value(&self.advance(usize::MAX, None)).unwrap()
+    }
+
+    /// Advances until a stop condition holds (e.g. `"PacketDecoded"` or
+    /// `{ ProbeThreshold: { probe, threshold } }`) or `duration_steps` is reached.
+    pub fn run_until(&mut self, condition: JsValue) -> Result<JsValue, JsValue> {
+        let condition: run::StopCondition = serde_wasm_bindgen::from_value(condition)?;
+        condition.validate(&self.state).map_err(|e| JsValue::from_str(&e))?;
+        Ok(serde_wasm_bindgen::to_value(&self.advance(usize::MAX, Some(&condition)))?)
+    }
+
+    /// Whether `duration_steps` has been reached.
+    pub fn is_finished(&self) -> bool {
+        self.state.time_step >= self.params.duration_steps
+    }
+
+    /// Fraction of `duration_steps` simulated so far, from 0 to 1.
+    pub fn get_progress(&self) -> f64 {
+        (self.state.time_step as f64 / self.params.duration_steps as f64).min(1.0)
+    }
+
+    /// Returns the number of steps a full run takes.
+    pub fn get_duration_steps(&self) -> usize {
+        self.params.duration_steps
+    }
+
+    /// Rasterizes an additional SVG obstacle path into the running simulation.
+    pub fn add_obstacle(&mut self, path: &str) -> Result<(), JsValue> {
+        self.state.add_obstacle(path).map_err(|e| JsValue::from_str(&e))?;
+        self.params.obstacles.push(path.to_string());
+        Ok(())
+    }
+
+    /// Removes every PEC obstacle from the grid. Material regions are kept.
+    pub fn clear_obstacles(&mut self) {
+        self.params.obstacles.clear();
+        // Regions were validated on construction, so re-rasterizing them cannot fail.
+        let _ = self.state.rasterize_geometry(&self.params);
     }
 
     pub fn set_comms_scheme(&mut self, is_ask: bool) {
@@ -141,20 +200,455 @@ impl FdtdSimulator {
         self.state.time_step
     }
 
-    /// Returns the electric field value at a specific coordinate.
+    /// Returns the duration of one step in seconds, or `undefined` for a normalised grid.
+    pub fn get_time_step_seconds(&self) -> Option<f64> {
+        self.time_step_seconds
+    }
+
+    /// Returns the Courant number used by the update equations.
+    pub fn get_courant_number(&self) -> f64 {
+        self.state.courant
+    }
+
+    /// Returns the out-of-plane field value at a specific coordinate (Ez in TMz mode, Hz in TEz mode).
     pub fn get_field_at(&self, x: usize, y: usize) -> f64 {
         if x < self.params.width && y < self.params.height {
-            self.state.ez[y * self.params.width + x]
+            precision::widen(self.state.primary_field()[y * self.params.width + x])
         } else {
             0.0
         }
     }
     
-    /// Feeds a value into the demodulator manually (called from JS or internal?)
-    /// Better internal. But I need receiver coords.
-    /// Temporary solution: JS calls `process_receiver_signal(val)` every step.
-    /// This avoids Rust struct changes.
+    /// Returns the names of the declared probes.
+    pub fn get_probe_names(&self) -> JsValue {
+        let names: Vec<&str> = self.state.probes.iter().map(|p| p.name.as_str()).collect();
+        serde_wasm_bindgen::to_value(&names).unwrap()
+    }
+
+    /// Returns the samples held by a probe, oldest first, as a Float64Array.
+    /// The last sample was taken at the end of the latest step.
+    pub fn get_probe_samples(&self, name: &str) -> Result<Vec<f64>, JsValue> {
+        self.probe_samples(name).map_err(|e| JsValue::from_str(&e))
+    }
+
+    /// Returns the latest sample of a probe, or `undefined` before the first step.
+    pub fn get_probe_latest(&self, name: &str) -> Result<Option<f64>, JsValue> {
+        self.state.probe(name).map(|p| p.latest()).ok_or_else(|| JsValue::from_str(&format!("Unknown probe \"{}\"", name)))
+    }
+
+    /// Finds the resonant modes ringing in a probe's samples, e.g. after a broadband pulse in a
+    /// closed cavity. `search` may set `{ skip, stride, max_modes, tolerance, min_frequency, max_frequency }`.
+    /// Returns `[{ frequency, decay_rate, q, amplitude, phase }]`, in hertz and per second on a physical grid.
+    pub fn find_probe_modes(&self, name: &str, search: JsValue) -> Result<JsValue, JsValue> {
+        let search: modes::ModeSearch = if search.is_undefined() { Default::default() } else { serde_wasm_bindgen::from_value(search)? };
+        let modes = self.probe_modes(name, &search).map_err(|e| JsValue::from_str(&e))?;
+        Ok(serde_wasm_bindgen::to_value(&modes)?)
+    }
+
+    /// Selects the probe that feeds the demodulator after every step, or none to feed it manually.
+    pub fn set_receiver_probe(&mut self, name: Option<String>) -> Result<(), JsValue> {
+        if let Some(name) = &name {
+            if self.state.probe(name).is_none() {
+                return Err(JsValue::from_str(&format!("Unknown probe \"{}\"", name)));
+            }
+        }
+        self.params.comms.receiver_probe = name;
+        Ok(())
+    }
+
+    /// Sets the peak of the uniform noise added to the receiver probe's samples.
+    pub fn set_receiver_noise(&mut self, level: f64) -> Result<(), JsValue> {
+        if !(level >= 0.0 && level.is_finite()) {
+            return Err(JsValue::from_str("Receiver noise must be a finite level of at least 0"));
+        }
+        self.params.comms.receiver_noise = level;
+        Ok(())
+    }
+
+    /// Returns the receiver probe's samples with the receiver noise added, as the demodulator saw
+    /// them, oldest first.
+    pub fn get_receiver_samples(&self) -> Result<Vec<f64>, JsValue> {
+        self.receiver_samples().map_err(|e| JsValue::from_str(&e))
+    }
+
+    /// Returns the DFT magnitude map of a monitor at its `index`-th frequency (row-major over its rectangle).
+    pub fn get_dft_magnitude(&self, name: &str, index: usize) -> Result<Vec<f64>, JsValue> {
+        Ok(self.dft_monitor(name, index).map_err(|e| JsValue::from_str(&e))?.magnitude(index))
+    }
+
+    /// Returns the DFT phase map (radians) of a monitor at its `index`-th frequency.
+    pub fn get_dft_phase(&self, name: &str, index: usize) -> Result<Vec<f64>, JsValue> {
+        Ok(self.dft_monitor(name, index).map_err(|e| JsValue::from_str(&e))?.phase(index))
+    }
+
+    /// Returns the RGBA image of a monitor's magnitude, or of its phase when `phase` is true.
+    pub fn get_dft_image(&self, name: &str, index: usize, phase: bool) -> Result<Vec<u8>, JsValue> {
+        let monitor = self.dft_monitor(name, index).map_err(|e| JsValue::from_str(&e))?;
+        let view = if phase { renderer::DftView::Phase } else { renderer::DftView::Magnitude };
+        Ok(renderer::render_dft(&self.state, monitor, index, view))
+    }
+
+    /// Width in pixels of a monitor's maps.
+    pub fn get_dft_width(&self, name: &str) -> Result<usize, JsValue> {
+        Ok(self.dft_monitor(name, 0).map_err(|e| JsValue::from_str(&e))?.width)
+    }
+
+    /// Height in pixels of a monitor's maps.
+    pub fn get_dft_height(&self, name: &str) -> Result<usize, JsValue> {
+        Ok(self.dft_monitor(name, 0).map_err(|e| JsValue::from_str(&e))?.height)
+    }
+
+    /// Returns the power that crossed a flux line during the latest step.
+    pub fn get_flux_power(&self, name: &str) -> Result<f64, JsValue> {
+        Ok(self.flux_line(name).map_err(|e| JsValue::from_str(&e))?.power())
+    }
+
+    /// Returns the energy that has crossed a flux line since the start.
+    pub fn get_flux_energy(&self, name: &str) -> Result<f64, JsValue> {
+        Ok(self.flux_line(name).map_err(|e| JsValue::from_str(&e))?.energy())
+    }
+
+    /// Returns a flux line's spectrum, one value per declared frequency.
+    pub fn get_flux_spectrum(&self, name: &str) -> Result<Vec<f64>, JsValue> {
+        Ok(self.flux_line(name).map_err(|e| JsValue::from_str(&e))?.spectrum())
+    }
+
+    /// Returns the far-field pattern of a monitor at its `index`-th frequency as
+    /// `{ frequency, angles, directivity }` (angles in degrees, directivity linear).
+    pub fn get_far_field_pattern(&self, name: &str, index: usize) -> Result<JsValue, JsValue> {
+        let pattern = self.far_field_pattern(name, index).map_err(|e| JsValue::from_str(&e))?;
+        Ok(serde_wasm_bindgen::to_value(&pattern)?)
+    }
+
+    /// Returns the far-field pattern as CSV text for export.
+    pub fn get_far_field_csv(&self, name: &str, index: usize) -> Result<String, JsValue> {
+        Ok(self.far_field_pattern(name, index).map_err(|e| JsValue::from_str(&e))?.to_csv())
+    }
+
+    /// Returns the electromagnetic energy stored in the whole grid.
+    pub fn get_energy(&self) -> f64 {
+        flux::electromagnetic_energy(&self.state, None)
+    }
+
+    /// Returns the electromagnetic energy stored in a rectangle of cells (bounds inclusive).
+    pub fn get_region_energy(&self, x_min: usize, y_min: usize, x_max: usize, y_max: usize) -> Result<f64, JsValue> {
+        if x_min > x_max || y_min > y_max || x_max >= self.state.width || y_max >= self.state.height {
+            return Err(JsValue::from_str("Energy region must lie within the grid"));
+        }
+        Ok(flux::electromagnetic_energy(&self.state, Some((x_min, y_min, x_max, y_max))))
+    }
+
+    /// Returns the S-parameters measured so far by this run's ports as
+    /// `{ frequencies, ports, parameters: [{ name, to, from, re, im }] }`: the column of the excited port.
+    pub fn get_s_parameters(&self) -> Result<JsValue, JsValue> {
+        let s_parameters = self.s_parameters().map_err(|e| JsValue::from_str(&e))?;
+        Ok(serde_wasm_bindgen::to_value(&s_parameters)?)
+    }
+
+    /// Returns the full S-parameter matrix in the same form. Runs a separate simulation for each
+    /// port, exciting it up to `duration_steps`; this simulation is left unchanged.
+    pub fn get_s_parameter_sweep(&self) -> Result<JsValue, JsValue> {
+        let s_parameters = self.s_parameter_sweep().map_err(|e| JsValue::from_str(&e))?;
+        Ok(serde_wasm_bindgen::to_value(&s_parameters)?)
+    }
+
+    /// Returns the two-port S-parameters as Touchstone (`.s2p`) text, running the sweep first.
+    /// The reference is each port's modal impedance, declared as `R 1`.
+    pub fn get_touchstone(&self) -> Result<String, JsValue> {
+        let s_parameters = self.s_parameter_sweep().map_err(|e| JsValue::from_str(&e))?;
+        s_parameters.to_touchstone(self.time_step_seconds).map_err(|e| JsValue::from_str(&e))
+    }
+
+    /// Returns the voltage across a lumped element at the latest half step, in volts on a physical grid.
+    pub fn get_lumped_voltage(&self, name: &str) -> Result<f64, JsValue> {
+        Ok(self.lumped_port(name).map_err(|e| JsValue::from_str(&e))?.0)
+    }
+
+    /// Returns the current through a lumped element along +z, in amperes on a physical grid.
+    /// A voltage source delivers the opposite of this current.
+    pub fn get_lumped_current(&self, name: &str) -> Result<f64, JsValue> {
+        Ok(self.lumped_port(name).map_err(|e| JsValue::from_str(&e))?.1)
+    }
+
+    /// Feeds a value into the demodulator manually. Not needed when a receiver probe is selected,
+    /// which also adds `comms.receiver_noise`.
     pub fn process_receiver_signal(&mut self, val: f64) {
         self.demodulator.process_sample(val, self.state.time_step as f64);
     }
-}
\ No newline at end of file
+
+    /// Returns a binary checkpoint of the whole running simulation, to resume later with `load_checkpoint`.
+    pub fn save_checkpoint(&self) -> Vec<u8> {
+        let mut out = checkpoint::Writer::new();
+        out.usize(self.params.obstacles.len());
+        self.params.obstacles.iter().for_each(|path| out.str(path));
+        out.bool(self.params.comms.receiver_probe.is_some());
+        out.str(self.params.comms.receiver_probe.as_deref().unwrap_or_default());
+        self.state.save(&mut out);
+        self.modulator.save(&mut out);
+        self.demodulator.save(&mut out);
+        out.bool(self.is_transmitting);
+        out.finish()
+    }
+
+    /// Resumes from a checkpoint saved by a simulator built from the same parameters.
+    /// The simulation is left unchanged if the checkpoint is rejected.
+    pub fn load_checkpoint(&mut self, bytes: &[u8]) -> Result<(), JsValue> {
+        self.restore_checkpoint(bytes).map_err(|e| JsValue::from_str(&e))
+    }
+}
+
+impl FdtdSimulator {
+    /// Builds a simulator from already-deserialized parameters.
+    /// Validates the configuration and rasterizes every obstacle into the material grid.
+    pub fn from_parameters(params: SimulationParameters) -> Result<FdtdSimulator, String> {
+        parameters::validate_parameters(&params)?;
+        let time_step_seconds = params.time_step_seconds();
+        let cell_size = params.grid.cell_size;
+        // The engine works in cycles per time step; keep only the normalised view.
+        let params = params.resolve_units();
+
+        let state = SimulationState::from_parameters(&params)?;
+
+        // Comms Parameters from Definition
+        let samples_per_symbol = params.comms.symbol_duration;
+        let carrier = params.comms.carrier_frequency;
+        let dev = params.comms.deviation;
+        
+        let freq_0 = carrier - dev;
+        let freq_1 = carrier + dev;
+
+        Ok(FdtdSimulator {
+            params,
+            state,
+            modulator: Modulator::new(freq_0, freq_1, samples_per_symbol),
+            demodulator: Demodulator::new(freq_0, freq_1, samples_per_symbol),
+            is_transmitting: false,
+            time_step_seconds,
+            cell_size,
+        })
+    }
+
+    pub fn state(&self) -> &SimulationState {
+        &self.state
+    }
+
+    /// Steps up to `steps` times, stopping early at `duration_steps` or once `condition` holds.
+    /// The condition should have been checked with `StopCondition::validate`.
+    pub fn advance(&mut self, steps: usize, condition: Option<&run::StopCondition>) -> run::RunStatus {
+        let (mut taken, mut condition_met) = (0, false);
+        while taken < steps && !self.is_finished() && !condition_met {
+            let events = self.demodulator.event_count();
+            self.step();
+            taken += 1;
+            condition_met = condition.is_some_and(|c| c.is_met(&self.state, self.demodulator.packet_decoded_since(events)));
+        }
+        run::RunStatus::new(taken, self.state.time_step, self.params.duration_steps, condition_met)
+    }
+
+    /// Restores a checkpoint written by `save_checkpoint`. Everything is read into fresh copies
+    /// first, so a rejected checkpoint leaves the simulation untouched.
+    pub fn restore_checkpoint(&mut self, bytes: &[u8]) -> Result<(), String> {
+        let mut input = checkpoint::Reader::new(bytes)?;
+        let mut params = self.params.clone();
+        let obstacles = input.usize()?;
+        params.obstacles = (0..obstacles).map(|_| input.string()).collect::<Result<_, _>>()?;
+        let has_receiver = input.bool()?;
+        let receiver = input.string()?;
+        params.comms.receiver_probe = has_receiver.then_some(receiver);
+
+        let mut state = SimulationState::from_parameters(&params)?;
+        state.restore(&mut input)?;
+        let mut modulator = self.modulator.clone();
+        modulator.restore(&mut input)?;
+        let mut demodulator = self.demodulator.clone();
+        demodulator.restore(&mut input)?;
+        let is_transmitting = input.bool()?;
+        input.finish()?;
+
+        self.params = params;
+        self.state = state;
+        self.modulator = modulator;
+        self.demodulator = demodulator;
+        self.is_transmitting = is_transmitting;
+        Ok(())
+    }
+
+    /// The DFT monitor called `name`, checking that it has a frequency at `index`.
+    pub fn dft_monitor(&self, name: &str, index: usize) -> Result<&dft::DftMonitor, String> {
+        let monitor = self.state.dft_monitor(name).ok_or_else(|| format!("Unknown DFT monitor \"{}\"", name))?;
+        if index >= monitor.frequencies.len() {
+            return Err(format!("DFT monitor \"{}\" has {} frequencies", name, monitor.frequencies.len()));
+        }
+        Ok(monitor)
+    }
+
+    /// The flux line called `name`.
+    pub fn flux_line(&self, name: &str) -> Result<&flux::FluxLine, String> {
+        self.state.flux_line(name).ok_or_else(|| format!("Unknown flux line \"{}\"", name))
+    }
+
+    /// Far-field pattern of the monitor called `name` at its `index`-th frequency.
+    pub fn far_field_pattern(&self, name: &str, index: usize) -> Result<farfield::FarFieldPattern, String> {
+        let far_field = self.state.far_field(name).ok_or_else(|| format!("Unknown far-field monitor \"{}\"", name))?;
+        if index >= far_field.frequencies.len() {
+            return Err(format!("Far-field monitor \"{}\" has {} frequencies", name, far_field.frequencies.len()));
+        }
+        Ok(far_field.pattern(index, self.state.courant))
+    }
+
+    /// Voltage and current of the lumped element called `name`, in volts and amperes when the grid
+    /// has a physical cell size and in normalised units (`Ez * dx`, `eta0 * I / dx`) otherwise.
+    pub fn lumped_port(&self, name: &str) -> Result<(f64, f64), String> {
+        let element = self.state.lumped_element(name).ok_or_else(|| format!("Unknown lumped element \"{}\"", name))?;
+        Ok(match self.cell_size {
+            Some(dx) => (element.voltage() * dx, element.current() * dx / parameters::FREE_SPACE_IMPEDANCE),
+            None => (element.voltage(), element.current()),
+        })
+    }
+
+    /// S-parameters from the spectra the ports have accumulated so far. A run measures the
+    /// parameters into every port from its excited port.
+    pub fn s_parameters(&self) -> Result<ports::SParameters, String> {
+        if self.state.ports.is_empty() {
+            return Err("No ports are defined".to_string());
+        }
+        Ok(ports::SParameters::measure(&self.state.ports))
+    }
+
+    /// The full S-parameter matrix: excites each port in turn in a fresh simulation run to
+    /// `duration_steps`, and merges the columns.
+    pub fn s_parameter_sweep(&self) -> Result<ports::SParameters, String> {
+        let count = self.params.s_parameters.as_ref().map_or(0, |s| s.ports.len());
+        let mut sweep: Option<ports::SParameters> = None;
+        for index in 0..count {
+            let mut params = self.params.clone();
+            if let Some(s_parameters) = params.s_parameters.as_mut() {
+                s_parameters.excited_port = index;
+            }
+            let mut simulator = FdtdSimulator::from_parameters(params)?;
+            simulator.advance(usize::MAX, None);
+            let column = simulator.s_parameters()?;
+            match sweep.as_mut() {
+                Some(sweep) => sweep.merge(column)?,
+                None => sweep = Some(column),
+            }
+        }
+        sweep.ok_or_else(|| "No ports are defined".to_string())
+    }
+
+    /// Resonant modes in the samples of the probe called `name` (see `modes::find_modes`), with
+    /// frequencies in hertz on a physical grid and in cycles per time step otherwise.
+    pub fn probe_modes(&self, name: &str, search: &modes::ModeSearch) -> Result<Vec<modes::ResonantMode>, String> {
+        modes::find_modes(&self.probe_samples(name)?, self.time_step_seconds.unwrap_or(1.0), search)
+    }
+
+    /// Samples held by the receiver probe plus the noise the demodulator saw with each, oldest first.
+    pub fn receiver_samples(&self) -> Result<Vec<f64>, String> {
+        let name = self.params.comms.receiver_probe.as_deref().ok_or("No receiver probe is selected")?;
+        let samples = self.probe_samples(name)?;
+        // The latest sample was taken at the current step, the one before it a step earlier, and so on.
+        let first = self.state.time_step + 1 - samples.len();
+        let level = self.params.comms.receiver_noise;
+        Ok(samples.iter().enumerate().map(|(i, v)| v + comms::noise::receiver_noise(first + i, level)).collect())
+    }
+
+    /// Samples held by the probe called `name`, oldest first.
+    pub fn probe_samples(&self, name: &str) -> Result<Vec<f64>, String> {
+        self.state.probe(name).map(|p| p.samples()).ok_or_else(|| format!("Unknown probe \"{}\"", name))
+    }
+}
+
+/// 3D simulation with all six field components, shown one slice at a time.
+#[wasm_bindgen]
+pub struct FdtdVolumeSimulator {
+    params: VolumeParameters,
+    state: VolumeState,
+    time_step_seconds: Option<f64>,
+}
+
+#[wasm_bindgen]
+impl FdtdVolumeSimulator {
+    #[wasm_bindgen(constructor)]
+    pub fn new(val: JsValue) -> Result<FdtdVolumeSimulator, JsValue> {
+        utils::set_panic_hook();
+        let params: VolumeParameters = serde_wasm_bindgen::from_value(val)?;
+        FdtdVolumeSimulator::from_parameters(params).map_err(|e| JsValue::from_str(&e))
+    }
+
+    /// Advances the simulation by one step.
+    pub fn step(&mut self) {
+        volume::step::step(&self.params, &mut self.state);
+    }
+
+    /// Zeroes every field and restarts the time count. Materials are kept.
+    pub fn reset(&mut self) {
+        self.state.reset();
+    }
+
+    /// Returns the current simulation time step.
+    pub fn get_current_step(&self) -> usize {
+        self.state.time_step
+    }
+
+    /// Returns the duration of one step in seconds, or `undefined` for a normalised grid.
+    pub fn get_time_step_seconds(&self) -> Option<f64> {
+        self.time_step_seconds
+    }
+
+    /// Width in pixels of a slice normal to `normal` (`"x"`, `"y"` or `"z"`).
+    pub fn get_slice_width(&self, normal: &str) -> Result<usize, JsValue> {
+        let normal = Axis::parse(normal).map_err(|e| JsValue::from_str(&e))?;
+        Ok(self.state.slice_dimensions(normal).0)
+    }
+
+    /// Height in pixels of a slice normal to `normal` (`"x"`, `"y"` or `"z"`).
+    pub fn get_slice_height(&self, normal: &str) -> Result<usize, JsValue> {
+        let normal = Axis::parse(normal).map_err(|e| JsValue::from_str(&e))?;
+        Ok(self.state.slice_dimensions(normal).1)
+    }
+
+    /// Returns the RGBA image of `component` (e.g. `"Ez"`) on the plane normal to `normal` at `position`.
+    pub fn get_slice_buffer(&self, component: &str, normal: &str, position: usize) -> Result<Vec<u8>, JsValue> {
+        self.slice_buffer(component, normal, position).map_err(|e| JsValue::from_str(&e))
+    }
+
+    /// Returns the value of `component` at a voxel, or 0 outside the grid.
+    pub fn get_field_at(&self, component: &str, x: usize, y: usize, z: usize) -> Result<f64, JsValue> {
+        let component = FieldComponent::parse(component).map_err(|e| JsValue::from_str(&e))?;
+        if x < self.state.width && y < self.state.height && z < self.state.depth {
+            Ok(self.state.field(component)[self.state.index(x, y, z)])
+        } else {
+            Ok(0.0)
+        }
+    }
+}
+
+impl FdtdVolumeSimulator {
+    /// Builds a 3D simulator from already-deserialized parameters.
+    pub fn from_parameters(params: VolumeParameters) -> Result<FdtdVolumeSimulator, String> {
+        volume::parameters::validate_volume_parameters(&params)?;
+        let time_step_seconds = params.time_step_seconds();
+        let params = params.resolve_units();
+        let state = VolumeState::from_parameters(&params)?;
+        Ok(FdtdVolumeSimulator { params, state, time_step_seconds })
+    }
+
+    pub fn state(&self) -> &VolumeState {
+        &self.state
+    }
+
+    /// Renders a slice, checking the component name, axis and position.
+    pub fn slice_buffer(&self, component: &str, normal: &str, position: usize) -> Result<Vec<u8>, String> {
+        let component = FieldComponent::parse(component)?;
+        let normal = Axis::parse(normal)?;
+        let len = match normal {
+            Axis::X => self.state.width,
+            Axis::Y => self.state.height,
+            Axis::Z => self.state.depth,
+        };
+        if position >= len {
+            return Err(format!("Slice position {} must be below {}", position, len));
+        }
+        Ok(renderer::render_slice(&self.state, component, normal, position))
+    }
+}
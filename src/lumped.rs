@@ -0,0 +1,138 @@
+use crate::checkpoint::{Reader, Writer};
+use crate::engine::compute_source_signal;
+use crate::materials::UpdateCoefficients;
+use crate::parameters::{LumpedElement as Kind, LumpedElementDefinition};
+use crate::precision::{widen, Real};
+
+/// A lumped element on one Ez node, in normalised units (see `LumpedElement`).
+///
+/// Its current enters Ampere's law of the cell as `Ez = ca * Ez + cb * (curl(H) - i)`. With
+/// `v = (Ez_new + Ez_old) / 2` at the half step, the resistor (`i = (v - emf) / r`), capacitor
+/// (`i = c / S * dEz`) and inductor (`dI = S / l * v`, with `i` the mean of `I` over the step) terms
+/// are all solved implicitly, so they are stable for any value.
+#[derive(Debug, Clone)]
+pub struct LumpedElement {
+    pub name: String,
+    pub kind: Kind,
+    index: usize,
+    /// Ez before the latest update.
+    previous: f64,
+    /// Port voltage and current at the latest half step.
+    voltage: f64,
+    current: f64,
+    /// Inductor current at the latest whole step.
+    inductor_current: f64,
+    /// EMF that replaces the source's own waveform for the next update (the comms feed).
+    drive: Option<f64>,
+}
+
+impl LumpedElement {
+    pub fn new(definition: &LumpedElementDefinition, width: usize) -> Self {
+        Self {
+            name: definition.name.clone(),
+            kind: definition.element.clone(),
+            index: definition.y * width + definition.x,
+            previous: 0.0,
+            voltage: 0.0,
+            current: 0.0,
+            inductor_current: 0.0,
+            drive: None,
+        }
+    }
+
+    /// The same element without its EMF, for the imaginary part of Bloch-periodic fields.
+    pub fn passive(&self) -> Self {
+        let kind = match self.kind {
+            Kind::VoltageSource { resistance, .. } => Kind::Resistor { resistance },
+            ref kind => kind.clone(),
+        };
+        Self { kind, drive: None, ..self.clone() }
+    }
+
+    /// Index of the Ez node in the grid.
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    /// `Ez * dx` across the element at the latest half step.
+    pub fn voltage(&self) -> f64 {
+        self.voltage
+    }
+
+    /// Current through the element along +z at the latest half step, normalised to `dx / eta0`.
+    pub fn current(&self) -> f64 {
+        self.current
+    }
+
+    /// Sets the EMF of a voltage source for the next update only.
+    pub fn drive(&mut self, emf: f64) {
+        self.drive = Some(emf);
+    }
+
+    /// Remembers Ez before the curl update. Runs at the start of `update_e_fields`.
+    pub fn record(&mut self, ez: &[Real]) {
+        self.previous = widen(ez[self.index]);
+    }
+
+    /// Corrects Ez after the curl update and updates the port voltage and current.
+    /// `time_step` is the step being computed, so the EMF is taken at its half step.
+    pub fn apply(&mut self, ez: &mut [Real], materials: &[u8], coefficients: &[UpdateCoefficients], courant: f64, time_step: usize) {
+        let cb = coefficients[materials[self.index] as usize].cb;
+        let (updated, old) = (widen(ez[self.index]), self.previous);
+        let (new, current) = match self.kind {
+            Kind::Resistor { resistance } => {
+                let new = Self::resistive(updated, old, cb, resistance, 0.0);
+                (new, (new + old) / 2.0 / resistance)
+            },
+            Kind::VoltageSource { resistance, amplitude, frequency, ref signal_type } => {
+                let emf = self.drive.take()
+                    .unwrap_or_else(|| compute_source_signal(time_step as f64 + 0.5, frequency, amplitude, signal_type));
+                let new = Self::resistive(updated, old, cb, resistance, emf);
+                (new, ((new + old) / 2.0 - emf) / resistance)
+            },
+            Kind::Capacitor { capacitance } => {
+                let k = cb * capacitance / courant;
+                let new = (updated + k * old) / (1.0 + k);
+                (new, capacitance / courant * (new - old))
+            },
+            Kind::Inductor { inductance } => {
+                // Trapezoidal rule: I_new = I_old + S / l * v and i = (I_old + I_new) / 2.
+                let g = cb * courant / (4.0 * inductance);
+                let new = (updated - cb * self.inductor_current - g * old) / (1.0 + g);
+                let previous = self.inductor_current;
+                self.inductor_current += courant / inductance * (new + old) / 2.0;
+                (new, (previous + self.inductor_current) / 2.0)
+            },
+        };
+        self.voltage = (new + old) / 2.0;
+        self.current = current;
+        ez[self.index] = new as Real;
+    }
+
+    /// Solves `new = updated - cb * ((new + old) / 2 - emf) / r` for `new`.
+    fn resistive(updated: f64, old: f64, cb: f64, resistance: f64, emf: f64) -> f64 {
+        let g = cb / resistance;
+        (updated - g * (old / 2.0 - emf)) / (1.0 + g / 2.0)
+    }
+
+    pub fn reset(&mut self) {
+        self.previous = 0.0;
+        self.voltage = 0.0;
+        self.current = 0.0;
+        self.inductor_current = 0.0;
+        self.drive = None;
+    }
+
+    pub fn save(&self, out: &mut Writer) {
+        out.f64(self.voltage);
+        out.f64(self.current);
+        out.f64(self.inductor_current);
+    }
+
+    pub fn restore(&mut self, input: &mut Reader) -> Result<(), String> {
+        self.voltage = input.f64()?;
+        self.current = input.f64()?;
+        self.inductor_current = input.f64()?;
+        Ok(())
+    }
+}
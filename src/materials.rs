@@ -0,0 +1,69 @@
+use crate::parameters::MaterialDefinition;
+
+/// Material id of free space. Every cell starts with it.
+pub const VACUUM: u8 = 0;
+/// Material id of the perfect electric conductor used for `obstacles`.
+pub const PEC: u8 = 1;
+
+/// Names reserved for the built-in materials.
+pub const VACUUM_NAME: &str = "vacuum";
+pub const PEC_NAME: &str = "pec";
+
+/// Update coefficients of one material for the normalised Yee equations:
+/// `Ez = ca * Ez + cb * curl(H)` and `H = da * H - db * curl(Ez)`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct UpdateCoefficients {
+    pub ca: f64,
+    pub cb: f64,
+    pub da: f64,
+    pub db: f64,
+}
+
+impl UpdateCoefficients {
+    /// Coefficients of a lossy dielectric. `conductivity` is normalised to `1 / (eta0 * dx)`.
+    pub fn for_material(material: &MaterialDefinition, courant: f64) -> Self {
+        let eps = material.relative_permittivity;
+        let loss = material.conductivity * courant / (2.0 * eps);
+        Self {
+            ca: (1.0 - loss) / (1.0 + loss),
+            cb: courant / eps / (1.0 + loss),
+            da: 1.0,
+            db: courant / material.relative_permeability,
+        }
+    }
+
+    /// Coefficients of a perfect electric conductor: Ez is forced to zero.
+    pub fn pec(courant: f64) -> Self {
+        Self { ca: 0.0, cb: 0.0, da: 1.0, db: courant }
+    }
+}
+
+/// Returns the material table used by the state: vacuum and PEC followed by the user materials.
+pub fn build_material_table(definitions: &[MaterialDefinition]) -> Vec<MaterialDefinition> {
+    let mut table = vec![
+        MaterialDefinition { name: VACUUM_NAME.to_string(), ..Default::default() },
+        MaterialDefinition { name: PEC_NAME.to_string(), ..Default::default() },
+    ];
+    table.extend_from_slice(definitions);
+    table
+}
+
+/// Computes the update coefficients for every entry of a material table.
+pub fn compute_coefficients(table: &[MaterialDefinition], courant: f64) -> Vec<UpdateCoefficients> {
+    table
+        .iter()
+        .enumerate()
+        .map(|(id, material)| {
+            if id == PEC as usize {
+                UpdateCoefficients::pec(courant)
+            } else {
+                UpdateCoefficients::for_material(material, courant)
+            }
+        })
+        .collect()
+}
+
+/// Looks up the material id for a name in a table built by `build_material_table`.
+pub fn find_material(table: &[MaterialDefinition], name: &str) -> Option<u8> {
+    table.iter().position(|m| m.name == name).map(|id| id as u8)
+}
@@ -0,0 +1,406 @@
+use serde::{Deserialize, Serialize};
+use std::f64::consts::PI;
+use std::ops::{Add, Div, Mul, Sub};
+
+/// Settings for `find_modes`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ModeSearch {
+    /// Samples skipped at the start of the series, e.g. while the exciting pulse is still on.
+    #[serde(default)]
+    pub skip: usize,
+    /// Only every `stride`-th sample is used. Decimating a heavily oversampled series lets the fit
+    /// span more cycles and separate close modes; frequencies must stay below `1 / (2 stride interval)`.
+    #[serde(default = "default_stride")]
+    pub stride: usize,
+    /// Largest number of modes the fit may use.
+    #[serde(default = "default_max_modes")]
+    pub max_modes: usize,
+    /// Singular values of the data below this fraction of the largest are treated as noise.
+    #[serde(default = "default_tolerance")]
+    pub tolerance: f64,
+    /// Band of the reported modes, in the units of the sample interval.
+    #[serde(default)]
+    pub min_frequency: Option<f64>,
+    #[serde(default)]
+    pub max_frequency: Option<f64>,
+}
+
+fn default_stride() -> usize {
+    1
+}
+
+fn default_max_modes() -> usize {
+    20
+}
+
+fn default_tolerance() -> f64 {
+    1e-6
+}
+
+impl Default for ModeSearch {
+    fn default() -> Self {
+        Self { skip: 0, stride: default_stride(), max_modes: default_max_modes(), tolerance: default_tolerance(), min_frequency: None, max_frequency: None }
+    }
+}
+
+/// One decaying sinusoid `amplitude * exp(-decay_rate * t) * cos(2 pi frequency t + phase)`, with `t`
+/// measured from the first analysed sample.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct ResonantMode {
+    pub frequency: f64,
+    /// Decay rate of the amplitude, per unit of time.
+    pub decay_rate: f64,
+    /// Quality factor `pi * frequency / decay_rate`; infinite for modes that do not decay.
+    pub q: f64,
+    pub amplitude: f64,
+    /// Phase in radians.
+    pub phase: f64,
+}
+
+/// Fits decaying sinusoids to `samples` taken every `interval` with the matrix-pencil method, and
+/// returns the modes found, by increasing frequency.
+///
+/// The data's Hankel matrix is truncated to the singular values above `search.tolerance`, which sets
+/// the number of poles; the poles are the eigenvalues of the shifted pencil of its right singular
+/// vectors, and the amplitudes follow by least squares over every sample.
+pub fn find_modes(samples: &[f64], interval: f64, search: &ModeSearch) -> Result<Vec<ResonantMode>, String> {
+    if interval <= 0.0 || !interval.is_finite() {
+        return Err("The sample interval must be positive".to_string());
+    }
+    if search.stride == 0 || search.max_modes == 0 || !(search.tolerance > 0.0 && search.tolerance < 1.0) {
+        return Err("The mode search needs stride > 0, max_modes > 0 and 0 < tolerance < 1".to_string());
+    }
+    let data: Vec<f64> = samples.get(search.skip..).unwrap_or_default().iter().step_by(search.stride).copied().collect();
+    let interval = interval * search.stride as f64;
+    let max_poles = 2 * search.max_modes;
+    // Pencil parameter between the number of poles and a third of the series, bounded for speed.
+    let pencil = (data.len() / 3).min(4 * max_poles);
+    if pencil < 2 || data.iter().any(|v| !v.is_finite()) {
+        return Err("The mode search needs at least 6 finite samples after skipping and decimation".to_string());
+    }
+
+    // Right singular vectors of the Hankel matrix Y[i][j] = data[i + j], from its Gram matrix.
+    let columns = pencil + 1;
+    let rows = data.len() - pencil;
+    let mut gram = vec![vec![0.0; columns]; columns];
+    for (j, row) in gram.iter_mut().enumerate() {
+        for (k, value) in row.iter_mut().enumerate().skip(j) {
+            *value = (0..rows).map(|i| data[i + j] * data[i + k]).sum();
+        }
+    }
+    for j in 1..columns {
+        let (upper, lower) = gram.split_at_mut(j);
+        for (k, row) in upper.iter().enumerate() {
+            lower[0][k] = row[j];
+        }
+    }
+    let (eigenvalues, vectors) = symmetric_eigen(gram);
+    let largest = eigenvalues.first().copied().unwrap_or(0.0);
+    if largest <= 0.0 {
+        return Ok(Vec::new());
+    }
+    let poles = eigenvalues.iter().take_while(|&&e| e > largest * search.tolerance * search.tolerance).count().min(max_poles).min(pencil);
+
+    // Poles: eigenvalues of pinv(V1) V2, where V1 and V2 drop the last and first row of the kept vectors.
+    let v = |row: usize, k: usize| vectors[k][row];
+    let mut normal = vec![vec![0.0; poles]; poles];
+    let mut right = vec![vec![0.0; poles]; poles];
+    for a in 0..poles {
+        for b in 0..poles {
+            normal[a][b] = (0..pencil).map(|r| v(r, a) * v(r, b)).sum();
+            right[a][b] = (0..pencil).map(|r| v(r, a) * v(r + 1, b)).sum();
+        }
+    }
+    let pencil_matrix = solve(normal, right)?;
+    let z = eigenvalues_of(pencil_matrix)?;
+
+    // Complex amplitudes by least squares: data[n] = sum(c_k z_k^n).
+    let powers: Vec<Vec<Complex>> = z.iter().map(|&zk| {
+        let mut power = Complex::new(1.0, 0.0);
+        data.iter().map(|_| {
+            let current = power;
+            power = power * zk;
+            current
+        }).collect()
+    }).collect();
+    let mut gram = vec![vec![Complex::default(); poles]; poles];
+    let mut projection = vec![vec![Complex::default(); 1]; poles];
+    for a in 0..poles {
+        for b in 0..poles {
+            gram[a][b] = powers[a].iter().zip(&powers[b]).fold(Complex::default(), |sum, (&p, &q)| sum + p.conj() * q);
+        }
+        projection[a][0] = powers[a].iter().zip(&data).fold(Complex::default(), |sum, (&p, &d)| sum + p.conj() * Complex::new(d, 0.0));
+    }
+    let amplitudes = solve(gram, projection)?;
+
+    let mut modes: Vec<ResonantMode> = z.iter().zip(&amplitudes).filter_map(|(zk, ck)| {
+        let frequency = zk.arg() / (2.0 * PI * interval);
+        let decay_rate = -zk.abs().ln() / interval;
+        let in_band = search.min_frequency.is_none_or(|f| frequency >= f) && search.max_frequency.is_none_or(|f| frequency <= f);
+        // A real signal pairs every pole with its conjugate; report the positive frequency only.
+        (frequency > 0.0 && in_band).then(|| ResonantMode {
+            frequency,
+            decay_rate,
+            q: if decay_rate > 0.0 { PI * frequency / decay_rate } else { f64::INFINITY },
+            amplitude: 2.0 * ck[0].abs(),
+            phase: ck[0].arg(),
+        })
+    }).collect();
+    modes.sort_by(|a, b| a.frequency.total_cmp(&b.frequency));
+    Ok(modes)
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+struct Complex {
+    re: f64,
+    im: f64,
+}
+
+impl Complex {
+    fn new(re: f64, im: f64) -> Self {
+        Self { re, im }
+    }
+
+    fn conj(self) -> Self {
+        Self::new(self.re, -self.im)
+    }
+
+    fn abs(self) -> f64 {
+        self.re.hypot(self.im)
+    }
+
+    fn arg(self) -> f64 {
+        self.im.atan2(self.re)
+    }
+
+    fn scale(self, factor: f64) -> Self {
+        Self::new(self.re * factor, self.im * factor)
+    }
+
+    fn sqrt(self) -> Self {
+        let r = self.abs();
+        let im = ((r - self.re) / 2.0).sqrt();
+        Self::new(((r + self.re) / 2.0).sqrt(), if self.im < 0.0 { -im } else { im })
+    }
+}
+
+impl Add for Complex {
+    type Output = Self;
+    fn add(self, other: Self) -> Self {
+        Self::new(self.re + other.re, self.im + other.im)
+    }
+}
+
+impl Sub for Complex {
+    type Output = Self;
+    fn sub(self, other: Self) -> Self {
+        Self::new(self.re - other.re, self.im - other.im)
+    }
+}
+
+impl Mul for Complex {
+    type Output = Self;
+    fn mul(self, other: Self) -> Self {
+        Self::new(self.re * other.re - self.im * other.im, self.re * other.im + self.im * other.re)
+    }
+}
+
+impl Div for Complex {
+    type Output = Self;
+    fn div(self, other: Self) -> Self {
+        let norm = other.re * other.re + other.im * other.im;
+        Self::new((self.re * other.re + self.im * other.im) / norm, (self.im * other.re - self.re * other.im) / norm)
+    }
+}
+
+/// Scalars `solve` works with.
+trait Scalar: Copy + Sub<Output = Self> + Mul<Output = Self> + Div<Output = Self> {
+    fn magnitude(self) -> f64;
+}
+
+impl Scalar for f64 {
+    fn magnitude(self) -> f64 {
+        self.abs()
+    }
+}
+
+impl Scalar for Complex {
+    fn magnitude(self) -> f64 {
+        self.abs()
+    }
+}
+
+/// Solves `a x = b` for the columns of `b` by Gaussian elimination with partial pivoting.
+#[allow(clippy::needless_range_loop)] // row operations index two rows at once
+fn solve<T: Scalar>(mut a: Vec<Vec<T>>, mut b: Vec<Vec<T>>) -> Result<Vec<Vec<T>>, String> {
+    let n = a.len();
+    for col in 0..n {
+        let pivot = (col..n).max_by(|&i, &j| a[i][col].magnitude().total_cmp(&a[j][col].magnitude())).unwrap();
+        if a[pivot][col].magnitude() == 0.0 {
+            return Err("The mode fit is singular; try a smaller max_modes".to_string());
+        }
+        a.swap(col, pivot);
+        b.swap(col, pivot);
+        for row in col + 1..n {
+            let factor = a[row][col] / a[col][col];
+            for k in col..n {
+                a[row][k] = a[row][k] - factor * a[col][k];
+            }
+            for k in 0..b[row].len() {
+                b[row][k] = b[row][k] - factor * b[col][k];
+            }
+        }
+    }
+    for col in (0..n).rev() {
+        for k in 0..b[col].len() {
+            let mut value = b[col][k];
+            for j in col + 1..n {
+                value = value - a[col][j] * b[j][k];
+            }
+            b[col][k] = value / a[col][col];
+        }
+    }
+    Ok(b)
+}
+
+/// Eigenvalues (descending) and eigenvectors of a symmetric matrix by cyclic Jacobi rotations.
+/// `vectors[k]` belongs to `values[k]`.
+#[allow(clippy::needless_range_loop)] // each rotation mixes two rows and two columns
+fn symmetric_eigen(mut a: Vec<Vec<f64>>) -> (Vec<f64>, Vec<Vec<f64>>) {
+    let n = a.len();
+    let mut v: Vec<Vec<f64>> = (0..n).map(|i| (0..n).map(|j| if i == j { 1.0 } else { 0.0 }).collect()).collect();
+    for _ in 0..100 {
+        let off: f64 = (0..n).flat_map(|i| (0..n).filter(move |&j| j != i).map(move |j| (i, j))).map(|(i, j)| a[i][j] * a[i][j]).sum();
+        let total: f64 = off + (0..n).map(|i| a[i][i] * a[i][i]).sum::<f64>();
+        if off <= 1e-30 * total {
+            break;
+        }
+        for p in 0..n {
+            for q in p + 1..n {
+                if a[p][q] == 0.0 {
+                    continue;
+                }
+                let theta = (a[q][q] - a[p][p]) / (2.0 * a[p][q]);
+                let t = theta.signum() / (theta.abs() + (theta * theta + 1.0).sqrt());
+                let c = 1.0 / (t * t + 1.0).sqrt();
+                let s = t * c;
+                for k in 0..n {
+                    let (akp, akq) = (a[k][p], a[k][q]);
+                    a[k][p] = c * akp - s * akq;
+                    a[k][q] = s * akp + c * akq;
+                }
+                for k in 0..n {
+                    let (apk, aqk) = (a[p][k], a[q][k]);
+                    a[p][k] = c * apk - s * aqk;
+                    a[q][k] = s * apk + c * aqk;
+                }
+                for row in v.iter_mut() {
+                    let (vp, vq) = (row[p], row[q]);
+                    row[p] = c * vp - s * vq;
+                    row[q] = s * vp + c * vq;
+                }
+            }
+        }
+    }
+    let mut order: Vec<usize> = (0..n).collect();
+    order.sort_by(|&i, &j| a[j][j].total_cmp(&a[i][i]));
+    let values = order.iter().map(|&k| a[k][k]).collect();
+    let vectors = order.iter().map(|&k| v.iter().map(|row| row[k]).collect()).collect();
+    (values, vectors)
+}
+
+/// Eigenvalues of a real square matrix: Householder reduction to Hessenberg form, then shifted
+/// complex QR iterations with deflation.
+#[allow(clippy::needless_range_loop)] // reflections and rotations work on index ranges
+fn eigenvalues_of(mut a: Vec<Vec<f64>>) -> Result<Vec<Complex>, String> {
+    let n = a.len();
+    for k in 0..n.saturating_sub(2) {
+        let norm = (k + 1..n).map(|i| a[i][k] * a[i][k]).sum::<f64>().sqrt();
+        if norm == 0.0 {
+            continue;
+        }
+        let mut u: Vec<f64> = (k + 1..n).map(|i| a[i][k]).collect();
+        u[0] += if u[0] < 0.0 { -norm } else { norm };
+        let uu: f64 = u.iter().map(|x| x * x).sum();
+        // a = (I - 2 u u^T / u^T u) a (I - 2 u u^T / u^T u), with u living on rows and columns k+1..n.
+        for j in 0..n {
+            let dot: f64 = u.iter().enumerate().map(|(i, ui)| ui * a[k + 1 + i][j]).sum::<f64>() * 2.0 / uu;
+            u.iter().enumerate().for_each(|(i, ui)| a[k + 1 + i][j] -= dot * ui);
+        }
+        for row in a.iter_mut() {
+            let dot: f64 = u.iter().enumerate().map(|(i, ui)| ui * row[k + 1 + i]).sum::<f64>() * 2.0 / uu;
+            u.iter().enumerate().for_each(|(i, ui)| row[k + 1 + i] -= dot * ui);
+        }
+    }
+
+    let mut h: Vec<Vec<Complex>> = a.iter().map(|row| row.iter().map(|&x| Complex::new(x, 0.0)).collect()).collect();
+    let mut values = Vec::with_capacity(n);
+    let mut hi = n;
+    let mut iterations = 0;
+    while hi > 0 {
+        let end = hi - 1;
+        // Start of the unreduced block that ends at `end`.
+        let mut lo = end;
+        while lo > 0 {
+            let scale = h[lo][lo].abs() + h[lo - 1][lo - 1].abs();
+            if h[lo][lo - 1].abs() <= f64::EPSILON * scale.max(f64::MIN_POSITIVE) {
+                h[lo][lo - 1] = Complex::default();
+                break;
+            }
+            lo -= 1;
+        }
+        if lo == end {
+            values.push(h[end][end]);
+            hi -= 1;
+            iterations = 0;
+            continue;
+        }
+        iterations += 1;
+        if iterations > 100 * n {
+            return Err("The mode fit did not converge".to_string());
+        }
+        // Wilkinson shift from the trailing 2x2 block, with an occasional exceptional shift.
+        let (p, q, r, s) = (h[end - 1][end - 1], h[end - 1][end], h[end][end - 1], h[end][end]);
+        let half = (p - s).scale(0.5);
+        let root = (half * half + q * r).sqrt();
+        let (one, two) = (s - root + half, s + root + half);
+        let mut shift = if (one - s).abs() < (two - s).abs() { one } else { two };
+        if iterations % 11 == 0 {
+            shift = shift + Complex::new(h[end][end - 1].abs(), 0.0);
+        }
+
+        for i in lo..=end {
+            h[i][i] = h[i][i] - shift;
+        }
+        let mut rotations = Vec::with_capacity(end - lo);
+        for k in lo..end {
+            let (x, y) = (h[k][k], h[k + 1][k]);
+            let norm = x.abs().hypot(y.abs());
+            let (c, s) = if norm == 0.0 {
+                (1.0, Complex::default())
+            } else if x.abs() == 0.0 {
+                (0.0, y.conj().scale(1.0 / y.abs()))
+            } else {
+                (x.abs() / norm, x.scale(1.0 / x.abs()) * y.conj().scale(1.0 / norm))
+            };
+            for j in k..=end {
+                let (top, bottom) = (h[k][j], h[k + 1][j]);
+                h[k][j] = top.scale(c) + s * bottom;
+                h[k + 1][j] = bottom.scale(c) - s.conj() * top;
+            }
+            rotations.push((c, s));
+        }
+        for (offset, &(c, s)) in rotations.iter().enumerate() {
+            let k = lo + offset;
+            for row in h.iter_mut().take((k + 2).min(end) + 1).skip(lo) {
+                let (left, right) = (row[k], row[k + 1]);
+                row[k] = left.scale(c) + right * s.conj();
+                row[k + 1] = right.scale(c) - left * s;
+            }
+        }
+        for i in lo..=end {
+            h[i][i] = h[i][i] + shift;
+        }
+    }
+    Ok(values)
+}
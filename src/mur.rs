@@ -0,0 +1,128 @@
+use crate::checkpoint::{Reader, Writer};
+use crate::precision::{widen, Real};
+use crate::parameters::{BoundaryDefinition, EdgeBoundary, MurOrder};
+
+/// One edge terminated by a Mur absorbing boundary.
+/// Node `j` of line `k` (0 on the edge, 1 just inside) has grid index `origin + k * inward + j * along`.
+#[derive(Debug, Clone)]
+struct MurEdge {
+    order: MurOrder,
+    origin: isize,
+    inward: isize,
+    along: isize,
+    len: usize,
+    /// Lines 0 and 1 at the previous two time steps, laid out as `k * len + j`.
+    previous: Vec<f64>,
+    before_previous: Vec<f64>,
+}
+
+impl MurEdge {
+    fn new(order: MurOrder, origin: usize, inward: isize, along: usize, len: usize) -> Self {
+        Self {
+            order,
+            origin: origin as isize,
+            inward,
+            along: along as isize,
+            len,
+            previous: vec![0.0; 2 * len],
+            before_previous: vec![0.0; 2 * len],
+        }
+    }
+
+    fn index(&self, k: usize, j: usize) -> usize {
+        (self.origin + k as isize * self.inward + j as isize * self.along) as usize
+    }
+
+    /// Computes the edge line from the freshly updated interior line, then records both lines.
+    fn apply(&mut self, field: &mut [Real], courant: f64) {
+        let len = self.len;
+        let k1 = (courant - 1.0) / (courant + 1.0);
+        let k2 = 2.0 / (courant + 1.0);
+        let k3 = courant * courant / (2.0 * (courant + 1.0));
+        let (old, older) = (&self.previous, &self.before_previous);
+
+        for j in 0..len {
+            let inner = widen(field[self.index(1, j)]);
+            let first = old[len + j] + k1 * (inner - old[j]);
+            // The transverse term needs both neighbours, so the ends of the line fall back to first order.
+            let edge = match self.order {
+                MurOrder::Second if j > 0 && j + 1 < len => {
+                    let laplacian = |k: usize| old[k * len + j + 1] - 2.0 * old[k * len + j] + old[k * len + j - 1];
+                    -older[len + j] + k1 * (inner + older[j]) + k2 * (old[j] + old[len + j])
+                        + k3 * (laplacian(0) + laplacian(1))
+                },
+                _ => first,
+            };
+            field[self.index(0, j)] = edge as Real;
+        }
+
+        std::mem::swap(&mut self.previous, &mut self.before_previous);
+        for k in 0..2 {
+            for j in 0..len {
+                self.previous[k * len + j] = widen(field[self.index(k, j)]);
+            }
+        }
+    }
+}
+
+/// Mur absorbing boundaries on the edges that request one.
+/// They act on the primary field (Ez in TMz, Hz in TEz), which obeys the scalar wave equation.
+#[derive(Debug, Clone)]
+pub struct MurState {
+    edges: Vec<MurEdge>,
+}
+
+impl MurState {
+    /// Returns `None` if no edge uses a Mur boundary.
+    pub fn new(width: usize, height: usize, boundary: &BoundaryDefinition) -> Option<Self> {
+        if width < 3 || height < 3 {
+            return None;
+        }
+        let w = width as isize;
+        let sides = [
+            (&boundary.left, 0, 1, width, height),
+            (&boundary.right, width - 1, -1, width, height),
+            (&boundary.top, 0, w, 1, width),
+            (&boundary.bottom, (height - 1) * width, -w, 1, width),
+        ];
+        let edges: Vec<MurEdge> = sides
+            .into_iter()
+            .filter_map(|(edge, origin, inward, along, len)| match edge {
+                EdgeBoundary::Mur(order) => Some(MurEdge::new(*order, origin, inward, along, len)),
+                _ => None,
+            })
+            .collect();
+        if edges.is_empty() { None } else { Some(Self { edges }) }
+    }
+
+    /// Updates the edge nodes of every Mur edge. Runs once per step, after the field updates and sources.
+    pub fn apply(&mut self, field: &mut [Real], courant: f64) {
+        for edge in &mut self.edges {
+            edge.apply(field, courant);
+        }
+    }
+
+    pub fn reset(&mut self) {
+        for edge in &mut self.edges {
+            edge.previous.fill(0.0);
+            edge.before_previous.fill(0.0);
+        }
+    }
+
+    pub fn save(&self, out: &mut Writer) {
+        out.usize(self.edges.len());
+        for edge in &self.edges {
+            out.f64s(&edge.previous);
+            out.f64s(&edge.before_previous);
+        }
+    }
+
+    pub fn restore(&mut self, input: &mut Reader) -> Result<(), String> {
+        input.len(self.edges.len(), "Mur edges")?;
+        for edge in &mut self.edges {
+            input.f64s_into(&mut edge.previous, "Mur values")?;
+            input.f64s_into(&mut edge.before_previous, "Mur values")?;
+        }
+        Ok(())
+    }
+}
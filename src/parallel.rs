@@ -0,0 +1,68 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Fewest rows worth handing to a thread of their own.
+pub const MIN_ROWS_PER_THREAD: usize = 32;
+
+/// Thread count requested through `set_threads`; 0 means one per available core.
+static THREADS: AtomicUsize = AtomicUsize::new(0);
+
+/// Sets how many threads the field updates may use (0 restores one per core).
+/// Has no effect unless the `parallel` feature is enabled.
+pub fn set_threads(threads: usize) {
+    THREADS.store(threads, Ordering::Relaxed);
+}
+
+/// Number of threads the field updates will use.
+pub fn threads() -> usize {
+    if !cfg!(feature = "parallel") {
+        return 1;
+    }
+    match THREADS.load(Ordering::Relaxed) {
+        0 => std::thread::available_parallelism().map_or(1, |n| n.get()),
+        n => n,
+    }
+}
+
+/// Pool of long-lived workers for the current thread count, rebuilt only when `set_threads` changes it.
+#[cfg(feature = "parallel")]
+static POOL: std::sync::Mutex<Option<(usize, std::sync::Arc<rayon::ThreadPool>)>> = std::sync::Mutex::new(None);
+
+#[cfg(feature = "parallel")]
+fn pool(threads: usize) -> std::sync::Arc<rayon::ThreadPool> {
+    let mut pool = POOL.lock().unwrap_or_else(|e| e.into_inner());
+    match &*pool {
+        Some((count, workers)) if *count == threads => workers.clone(),
+        _ => {
+            let workers = std::sync::Arc::new(rayon::ThreadPoolBuilder::new()
+                .num_threads(threads)
+                .thread_name(|i| format!("fdtd-worker-{}", i))
+                .build()
+                .expect("failed to start the field update threads"));
+            *pool = Some((threads, workers.clone()));
+            workers
+        },
+    }
+}
+
+/// Runs `update` on every row, spreading bands of at least `MIN_ROWS_PER_THREAD` rows over a
+/// persistent thread pool when the `parallel` feature is enabled. Rows must not depend on each
+/// other within a pass, so the result is the same however they are split.
+pub fn for_each_row<T: Send>(rows: impl Iterator<Item = T>, update: impl Fn(T) + Sync) {
+    let threads = threads();
+    if threads <= 1 {
+        rows.for_each(update);
+        return;
+    }
+    #[cfg(feature = "parallel")]
+    {
+        use rayon::prelude::*;
+        let rows: Vec<T> = rows.collect();
+        if rows.len() < 2 * MIN_ROWS_PER_THREAD {
+            rows.into_iter().for_each(update);
+            return;
+        }
+        pool(threads).install(|| rows.into_par_iter().with_min_len(MIN_ROWS_PER_THREAD).for_each(&update));
+    }
+    #[cfg(not(feature = "parallel"))]
+    rows.for_each(update);
+}
@@ -1,24 +1,167 @@
+use crate::tfsf::numerical_wavenumber;
+use crate::volume::parameters::FieldComponent;
 use serde::{Deserialize, Serialize};
 
+/// Speed of light in vacuum (m/s).
+pub const SPEED_OF_LIGHT: f64 = 299_792_458.0;
+/// Impedance of free space (ohms).
+pub const FREE_SPACE_IMPEDANCE: f64 = 376.730_313_668;
+/// Courant number `c * dt / dx` used when none is configured.
+pub const DEFAULT_COURANT_NUMBER: f64 = 0.5;
+/// Stability limit of the 2D Yee scheme on a square grid: `1 / sqrt(2)`.
+pub const MAX_COURANT_NUMBER_2D: f64 = std::f64::consts::FRAC_1_SQRT_2;
+/// Stability limit of the 3D Yee scheme on a cubic grid: `1 / sqrt(3)`.
+pub const MAX_COURANT_NUMBER_3D: f64 = 0.577_350_269_189_625_8;
+
 /// Represents the simulation configuration provided by the user.
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct SimulationParameters {
     pub width: usize,
     pub height: usize,
-    pub source: SourceDefinition,
+    pub sources: Vec<SourceDefinition>,
     pub comms: CommsDefinition,
     pub obstacles: Vec<String>, // SVG path strings
     pub duration_steps: usize,
+    #[serde(default)]
+    pub boundary: BoundaryDefinition,
+    #[serde(default)]
+    pub grid: GridDefinition,
+    #[serde(default)]
+    pub materials: Vec<MaterialDefinition>,
+    #[serde(default)]
+    pub regions: Vec<MaterialRegion>,
+    #[serde(default)]
+    pub polarization: Polarization,
+    #[serde(default)]
+    pub plane_wave: Option<PlaneWaveDefinition>,
+    #[serde(default)]
+    pub probes: Vec<ProbeDefinition>,
+    #[serde(default)]
+    pub dft_monitors: Vec<DftMonitorDefinition>,
+    #[serde(default)]
+    pub flux_lines: Vec<FluxLineDefinition>,
+    #[serde(default)]
+    pub far_fields: Vec<FarFieldDefinition>,
+    #[serde(default)]
+    pub lumped_elements: Vec<LumpedElementDefinition>,
+    #[serde(default)]
+    pub s_parameters: Option<SParameterDefinition>,
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+impl Default for SimulationParameters {
+    fn default() -> Self {
+        Self {
+            width: 100,
+            height: 100,
+            sources: vec![SourceDefinition { x: 50, y: 50, ..Default::default() }],
+            comms: CommsDefinition { carrier_frequency: 0.05, deviation: 0.01, symbol_duration: 100, symbol_rate: None, transmitter: None, transmitter_feed: None, receiver_probe: None, receiver_noise: 0.0 },
+            obstacles: Vec::new(),
+            duration_steps: 1000,
+            boundary: BoundaryDefinition::default(),
+            grid: GridDefinition::default(),
+            materials: Vec::new(),
+            regions: Vec::new(),
+            polarization: Polarization::default(),
+            plane_wave: None,
+            probes: Vec::new(),
+            dft_monitors: Vec::new(),
+            flux_lines: Vec::new(),
+            far_fields: Vec::new(),
+            lumped_elements: Vec::new(),
+            s_parameters: None,
+        }
+    }
+}
+
+/// Field polarisation solved on the 2D grid.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Default)]
+pub enum Polarization {
+    /// Ez, Hx, Hy. Sources and rendering use Ez.
+    #[default]
+    TMz,
+    /// Hz, Ex, Ey. Sources and rendering use Hz.
+    TEz,
+}
+
+/// Waveform of a source. The source `frequency` is the carrier (or start frequency of a chirp).
+///
+/// Widths, delays and durations are in time steps, or seconds when the grid has a physical
+/// cell size; bandwidths are in the units of `frequency`. A missing delay defaults to four
+/// envelope widths, so the pulse starts below 1e-6 of its peak.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub enum SignalType {
     ContinuousSine,
     ContinuousSquare,
     PulseSine,
     PulseSquare,
+    /// `exp(-((t - delay) / width)^2)`.
+    Gaussian { width: f64, #[serde(default)] delay: Option<f64> },
+    /// First derivative of the Gaussian, scaled to a unit peak. It has no DC content.
+    DifferentiatedGaussian { width: f64, #[serde(default)] delay: Option<f64> },
+    /// Gaussian envelope on a sine carrier. The spectrum falls to `1/e` at `frequency +- bandwidth`.
+    ModulatedGaussian { bandwidth: f64, #[serde(default)] delay: Option<f64> },
+    /// Mexican-hat wavelet whose spectrum peaks at `frequency`.
+    Ricker { #[serde(default)] delay: Option<f64> },
+    /// Sine sweeping linearly from `frequency` to `frequency + bandwidth` over `duration`, then silent.
+    LinearChirp { bandwidth: f64, duration: f64 },
+    /// Sine sweeping exponentially from `frequency` to `frequency + bandwidth` over `duration`, then silent.
+    ExponentialChirp { bandwidth: f64, duration: f64 },
+    /// Recorded waveform: sample `k` is played at `k * interval` and the source is silent
+    /// outside the recording. `frequency` is ignored.
+    Sampled {
+        samples: Vec<f64>,
+        interval: f64,
+        #[serde(default)]
+        interpolation: Interpolation,
+    },
+}
+
+/// How a sampled waveform is resampled onto the FDTD time step.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Default)]
+pub enum Interpolation {
+    #[default]
+    Linear,
+    /// Lanczos-windowed sinc over 8 samples on each side; band-limited, suited to baseband captures.
+    Sinc,
 }
 
+impl SignalType {
+    /// Whether the waveform has a sine carrier at `frequency` that a source `phase` can offset.
+    pub fn has_carrier(&self) -> bool {
+        matches!(self, SignalType::ContinuousSine | SignalType::ContinuousSquare | SignalType::PulseSine
+            | SignalType::ModulatedGaussian { .. } | SignalType::LinearChirp { .. } | SignalType::ExponentialChirp { .. })
+    }
+
+    /// Converts the waveform parameters to per-time-step units given the time step `dt` in seconds.
+    pub fn normalized(&self, dt: f64) -> SignalType {
+        let time = |t: f64| t / dt;
+        match *self {
+            SignalType::Gaussian { width, delay } => SignalType::Gaussian { width: time(width), delay: delay.map(time) },
+            SignalType::DifferentiatedGaussian { width, delay } => {
+                SignalType::DifferentiatedGaussian { width: time(width), delay: delay.map(time) }
+            },
+            SignalType::ModulatedGaussian { bandwidth, delay } => {
+                SignalType::ModulatedGaussian { bandwidth: bandwidth * dt, delay: delay.map(time) }
+            },
+            SignalType::Ricker { delay } => SignalType::Ricker { delay: delay.map(time) },
+            SignalType::LinearChirp { bandwidth, duration } => {
+                SignalType::LinearChirp { bandwidth: bandwidth * dt, duration: time(duration) }
+            },
+            SignalType::ExponentialChirp { bandwidth, duration } => {
+                SignalType::ExponentialChirp { bandwidth: bandwidth * dt, duration: time(duration) }
+            },
+            SignalType::Sampled { ref samples, interval, interpolation } => {
+                SignalType::Sampled { samples: samples.clone(), interval: time(interval), interpolation }
+            },
+            ref other => other.clone(),
+        }
+    }
+}
+
+/// A soft source on a single cell.
+///
+/// `start_time` and `stop_time` are in time steps, or seconds when the grid has a physical
+/// cell size. The waveform starts at `start_time`, so pulses are delayed by it.
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct SourceDefinition {
     pub x: usize,
@@ -26,13 +169,527 @@ pub struct SourceDefinition {
     pub amplitude: f64,
     pub frequency: f64,
     pub signal_type: SignalType,
+    /// Phase offset in radians, added to the carrier without moving the envelope. Only waveforms with
+    /// a carrier (see `SignalType::has_carrier`) accept a non-zero phase.
+    #[serde(default)]
+    pub phase: f64,
+    /// Length of a raised-cosine turn-on envelope, for switching continuous waveforms on smoothly.
+    #[serde(default)]
+    pub ramp_time: Option<f64>,
+    #[serde(default)]
+    pub start_time: f64,
+    /// The source is silent from this time on. `None` keeps it running.
+    #[serde(default)]
+    pub stop_time: Option<f64>,
+}
+
+impl Default for SourceDefinition {
+    fn default() -> Self {
+        Self {
+            x: 0,
+            y: 0,
+            amplitude: 1.0,
+            frequency: 0.05,
+            signal_type: SignalType::ContinuousSine,
+            phase: 0.0,
+            ramp_time: None,
+            start_time: 0.0,
+            stop_time: None,
+        }
+    }
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct CommsDefinition {
     pub carrier_frequency: f64,
     pub deviation: f64,
+    #[serde(default)]
     pub symbol_duration: usize,
+    /// Symbols per unit time. When set it overrides `symbol_duration`.
+    #[serde(default)]
+    pub symbol_rate: Option<f64>,
+    /// Where the modulated signal is injected. Without it the transmitter takes the
+    /// place of the first source, which is muted while a message is sent.
+    #[serde(default)]
+    pub transmitter: Option<TransmitterDefinition>,
+    /// Name of a lumped voltage source that carries the modulated signal instead of a soft source,
+    /// for a matched feed. It takes precedence over `transmitter`.
+    #[serde(default)]
+    pub transmitter_feed: Option<String>,
+    /// Name of the probe whose samples feed the demodulator after every step.
+    #[serde(default)]
+    pub receiver_probe: Option<String>,
+    /// Peak of the uniform noise added to the receiver probe's samples before they are demodulated,
+    /// in the units of the samples.
+    #[serde(default)]
+    pub receiver_noise: f64,
+}
+
+/// Named field recorder sampled at the end of every step.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ProbeDefinition {
+    pub name: String,
+    /// Component of the active polarisation (Ez, Hx or Hy in TMz; Hz, Ex or Ey in TEz),
+    /// read at its own Yee position in each cell.
+    pub component: FieldComponent,
+    pub shape: ProbeShape,
+    /// Number of samples kept; older ones are overwritten. Defaults to `duration_steps`.
+    #[serde(default)]
+    pub capacity: Option<usize>,
+}
+
+/// Cells averaged by a probe. Coordinates are inclusive.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub enum ProbeShape {
+    Point { x: usize, y: usize },
+    /// Cells on the straight line between the two end points.
+    Line { x0: usize, y0: usize, x1: usize, y1: usize },
+    Area { x_min: usize, y_min: usize, x_max: usize, y_max: usize },
+}
+
+/// Running discrete Fourier transform of one field component over a rectangle
+/// (`x_min..=x_max`, `y_min..=y_max`) at a list of frequencies.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct DftMonitorDefinition {
+    pub name: String,
+    pub component: FieldComponent,
+    pub x_min: usize,
+    pub y_min: usize,
+    pub x_max: usize,
+    pub y_max: usize,
+    pub frequencies: Vec<f64>,
+    /// Time before which samples are ignored, to leave out the turn-on transient.
+    #[serde(default)]
+    pub start_time: f64,
+}
+
+/// Poynting-flux monitor on an axis-aligned segment between nodes `(x0, y0)` and `(x1, y1)`.
+///
+/// A vertical segment (`x0 == x1`) measures the power crossing the line `x = x0 + 1/2` towards +x,
+/// a horizontal one the power crossing `y = y0 + 1/2` towards +y.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct FluxLineDefinition {
+    pub name: String,
+    pub x0: usize,
+    pub y0: usize,
+    pub x1: usize,
+    pub y1: usize,
+    /// Frequencies of the flux spectrum; empty for time-domain flux only.
+    #[serde(default)]
+    pub frequencies: Vec<f64>,
+}
+
+/// Near-to-far-field transformation on the closed contour around the cells `x_min..=x_max`, `y_min..=y_max`.
+///
+/// The contour runs half a cell outside the rectangle. It must enclose every source and
+/// scatterer and lie in vacuum, outside any absorbing layer.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct FarFieldDefinition {
+    pub name: String,
+    pub x_min: usize,
+    pub y_min: usize,
+    pub x_max: usize,
+    pub y_max: usize,
+    pub frequencies: Vec<f64>,
+    /// Number of equally spaced pattern angles, starting at +x and turning towards +y (360 by default).
+    #[serde(default)]
+    pub angles: Option<usize>,
+}
+
+/// Waveguide ports for S-parameter extraction (TMz). The excited port launches a pulse into the
+/// device and every port splits its fields into incident and reflected waves at `frequencies`.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct SParameterDefinition {
+    pub ports: Vec<PortDefinition>,
+    pub frequencies: Vec<f64>,
+    /// Index in `ports` of the port that launches the pulse.
+    #[serde(default)]
+    pub excited_port: usize,
+    pub amplitude: f64,
+    /// Centre frequency of the pulse, for waveforms that have one.
+    pub frequency: f64,
+    pub signal_type: SignalType,
+}
+
+/// A port across the Ez nodes of a horizontal or vertical line that spans a waveguide.
+///
+/// The reference plane lies half a cell past the line, between it and the next row or column of
+/// nodes, and the pulse is launched one node behind it. The port measures the fundamental mode: a
+/// half sine when the nodes beyond both ends of the line are PEC, uniform when neither is.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct PortDefinition {
+    pub name: String,
+    pub x0: usize,
+    pub y0: usize,
+    pub x1: usize,
+    pub y1: usize,
+    /// Direction from the port into the device.
+    pub direction: PortDirection,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub enum PortDirection {
+    PositiveX,
+    NegativeX,
+    PositiveY,
+    NegativeY,
+}
+
+/// Plane wave injected with the total-field / scattered-field technique (TMz only).
+///
+/// The total-field region spans the Ez nodes `x_min..=x_max`, `y_min..=y_max`; outside it only
+/// the scattered field remains. `angle` is the propagation direction in degrees from +x towards +y.
+/// The waveform starts five cells of travel before the corner the wave reaches first; its phase
+/// velocity is matched to the grid at `frequency`.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct PlaneWaveDefinition {
+    pub x_min: usize,
+    pub y_min: usize,
+    pub x_max: usize,
+    pub y_max: usize,
+    pub angle: f64,
+    pub amplitude: f64,
+    pub frequency: f64,
+    pub signal_type: SignalType,
+}
+
+/// Position and peak amplitude of the comms transmitter.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct TransmitterDefinition {
+    pub x: usize,
+    pub y: usize,
+    pub amplitude: f64,
+}
+
+/// A circuit element across one Ez cell (TMz), which it treats as a cube of side `dx`.
+/// Its voltage is `Ez * dx` and its current flows along +z.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct LumpedElementDefinition {
+    pub name: String,
+    pub x: usize,
+    pub y: usize,
+    pub element: LumpedElement,
+}
+
+/// Kind and value of a lumped element. Resistances are in ohms, capacitances in farads, inductances
+/// in henries and voltages in volts when the grid has a physical cell size; otherwise they are
+/// normalised to `eta0`, `eps0 * dx`, `mu0 * dx` and `dx` (an EMF then reads directly as Ez).
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub enum LumpedElement {
+    Resistor { resistance: f64 },
+    Capacitor { capacitance: f64 },
+    Inductor { inductance: f64 },
+    /// An EMF in series with its internal resistance, e.g. a matched feed.
+    VoltageSource { resistance: f64, amplitude: f64, frequency: f64, signal_type: SignalType },
+}
+
+/// A linear, isotropic medium that can be assigned to regions of the grid.
+///
+/// `conductivity` is in S/m when the grid has a physical cell size, otherwise it is
+/// normalised to `1 / (eta0 * dx)`.
+///
+/// With `poles` the material is dispersive and `relative_permittivity` is the
+/// high-frequency limit (epsilon infinity).
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct MaterialDefinition {
+    pub name: String,
+    pub relative_permittivity: f64,
+    pub relative_permeability: f64,
+    pub conductivity: f64,
+    pub poles: Vec<DispersivePole>,
+}
+
+/// A single term of a dispersive permittivity model (time dependence `exp(-i*w*t)`).
+///
+/// Frequencies are in hertz, rates in 1/s and times in seconds when the grid has a
+/// physical cell size; otherwise they are per time step.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub enum DispersivePole {
+    /// `-wp^2 / (w^2 + i*gamma*w)` with `wp = 2*pi*plasma_frequency`.
+    Drude { plasma_frequency: f64, collision_rate: f64 },
+    /// `delta_epsilon * w0^2 / (w0^2 - w^2 - i*gamma*w)` with `w0 = 2*pi*resonance_frequency`.
+    Lorentz { delta_epsilon: f64, resonance_frequency: f64, damping: f64 },
+    /// `delta_epsilon / (1 - i*w*tau)`.
+    Debye { delta_epsilon: f64, relaxation_time: f64 },
+}
+
+impl DispersivePole {
+    /// Converts the pole to per-time-step units given the time step `dt` in seconds.
+    pub fn normalized(&self, dt: f64) -> DispersivePole {
+        match *self {
+            DispersivePole::Drude { plasma_frequency, collision_rate } => DispersivePole::Drude {
+                plasma_frequency: plasma_frequency * dt,
+                collision_rate: collision_rate * dt,
+            },
+            DispersivePole::Lorentz { delta_epsilon, resonance_frequency, damping } => DispersivePole::Lorentz {
+                delta_epsilon,
+                resonance_frequency: resonance_frequency * dt,
+                damping: damping * dt,
+            },
+            DispersivePole::Debye { delta_epsilon, relaxation_time } => DispersivePole::Debye {
+                delta_epsilon,
+                relaxation_time: relaxation_time / dt,
+            },
+        }
+    }
+}
+
+impl Default for MaterialDefinition {
+    fn default() -> Self {
+        Self {
+            name: String::new(),
+            relative_permittivity: 1.0,
+            relative_permeability: 1.0,
+            conductivity: 0.0,
+            poles: Vec::new(),
+        }
+    }
+}
+
+/// Fills the interior of an SVG path with a named material.
+/// `"vacuum"` and `"pec"` are always available.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct MaterialRegion {
+    pub material: String,
+    pub path: String,
+}
+
+/// Physical scale of the grid.
+///
+/// Without a `cell_size` everything is in normalised units: frequencies are in cycles per
+/// time step. With a `cell_size` (metres), source and comms frequencies are in hertz and the
+/// time step is derived from the Courant number.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+#[serde(default)]
+pub struct GridDefinition {
+    pub cell_size: Option<f64>,
+    pub courant: Option<f64>,
+}
+
+impl SimulationParameters {
+    /// Courant number `c * dt / dx` of the simulation.
+    pub fn courant_number(&self) -> f64 {
+        self.grid.courant.unwrap_or(DEFAULT_COURANT_NUMBER)
+    }
+
+    /// Time step in seconds, if the grid has a physical cell size.
+    pub fn time_step_seconds(&self) -> Option<f64> {
+        self.grid.cell_size.map(|dx| self.courant_number() * dx / SPEED_OF_LIGHT)
+    }
+
+    /// Converts a frequency given in the configured units to cycles per time step.
+    pub fn normalize_frequency(&self, frequency: f64) -> f64 {
+        match self.time_step_seconds() {
+            Some(dt) => frequency * dt,
+            None => frequency,
+        }
+    }
+
+    /// Converts a time given in the configured units to time steps.
+    pub fn normalize_time(&self, time: f64) -> f64 {
+        match self.time_step_seconds() {
+            Some(dt) => time / dt,
+            None => time,
+        }
+    }
+
+    /// The comms transmitter: the configured one, or the first source (centre of the grid
+    /// with unit amplitude if there are no sources).
+    pub fn transmitter(&self) -> TransmitterDefinition {
+        if let Some(feed) = self.transmitter_feed() {
+            if let LumpedElement::VoltageSource { amplitude, .. } = feed.element {
+                return TransmitterDefinition { x: feed.x, y: feed.y, amplitude };
+            }
+        }
+        if let Some(transmitter) = &self.comms.transmitter {
+            return transmitter.clone();
+        }
+        match self.sources.first() {
+            Some(source) => TransmitterDefinition { x: source.x, y: source.y, amplitude: source.amplitude },
+            None => TransmitterDefinition { x: self.width / 2, y: self.height / 2, amplitude: 1.0 },
+        }
+    }
+
+    /// The lumped element named by `comms.transmitter_feed`, if any.
+    pub fn transmitter_feed(&self) -> Option<&LumpedElementDefinition> {
+        let name = self.comms.transmitter_feed.as_ref()?;
+        self.lumped_elements.iter().find(|e| &e.name == name)
+    }
+
+    /// Returns a copy with every frequency in cycles per time step and the
+    /// symbol duration resolved, which is what the engine consumes.
+    pub fn resolve_units(&self) -> SimulationParameters {
+        let mut resolved = self.clone();
+        if let Some(plane_wave) = &mut resolved.plane_wave {
+            plane_wave.frequency = self.normalize_frequency(plane_wave.frequency);
+            if let Some(dt) = self.time_step_seconds() {
+                plane_wave.signal_type = plane_wave.signal_type.normalized(dt);
+            }
+        }
+        for source in &mut resolved.sources {
+            source.frequency = self.normalize_frequency(source.frequency);
+            source.start_time = self.normalize_time(source.start_time);
+            source.stop_time = source.stop_time.map(|t| self.normalize_time(t));
+            source.ramp_time = source.ramp_time.map(|t| self.normalize_time(t));
+            if let Some(dt) = self.time_step_seconds() {
+                source.signal_type = source.signal_type.normalized(dt);
+            }
+        }
+        for monitor in &mut resolved.dft_monitors {
+            monitor.frequencies = monitor.frequencies.iter().map(|&f| self.normalize_frequency(f)).collect();
+            monitor.start_time = self.normalize_time(monitor.start_time);
+        }
+        for line in &mut resolved.flux_lines {
+            line.frequencies = line.frequencies.iter().map(|&f| self.normalize_frequency(f)).collect();
+        }
+        for far_field in &mut resolved.far_fields {
+            far_field.frequencies = far_field.frequencies.iter().map(|&f| self.normalize_frequency(f)).collect();
+        }
+        for lumped in &mut resolved.lumped_elements {
+            lumped.element = self.normalize_lumped_element(&lumped.element);
+        }
+        if let Some(s_parameters) = &mut resolved.s_parameters {
+            s_parameters.frequencies = s_parameters.frequencies.iter().map(|&f| self.normalize_frequency(f)).collect();
+            s_parameters.frequency = self.normalize_frequency(s_parameters.frequency);
+            if let Some(dt) = self.time_step_seconds() {
+                s_parameters.signal_type = s_parameters.signal_type.normalized(dt);
+            }
+        }
+        resolved.comms.carrier_frequency = self.normalize_frequency(self.comms.carrier_frequency);
+        resolved.comms.deviation = self.normalize_frequency(self.comms.deviation);
+        if let Some(rate) = self.comms.symbol_rate {
+            let per_step = self.normalize_frequency(rate);
+            resolved.comms.symbol_duration = if per_step > 0.0 { (1.0 / per_step).round() as usize } else { 0 };
+            resolved.comms.symbol_rate = None;
+        }
+        if let (Some(dx), Some(dt)) = (self.grid.cell_size, self.time_step_seconds()) {
+            for material in &mut resolved.materials {
+                material.conductivity *= FREE_SPACE_IMPEDANCE * dx;
+                material.poles = material.poles.iter().map(|p| p.normalized(dt)).collect();
+            }
+        }
+        resolved.grid.cell_size = None;
+        resolved
+    }
+
+    /// Converts a lumped element's values to the normalised units described on `LumpedElement`.
+    fn normalize_lumped_element(&self, element: &LumpedElement) -> LumpedElement {
+        let (Some(dx), Some(dt)) = (self.grid.cell_size, self.time_step_seconds()) else {
+            return element.clone();
+        };
+        let eps0 = 1.0 / (FREE_SPACE_IMPEDANCE * SPEED_OF_LIGHT);
+        let mu0 = FREE_SPACE_IMPEDANCE / SPEED_OF_LIGHT;
+        match *element {
+            LumpedElement::Resistor { resistance } => LumpedElement::Resistor { resistance: resistance / FREE_SPACE_IMPEDANCE },
+            LumpedElement::Capacitor { capacitance } => LumpedElement::Capacitor { capacitance: capacitance / (eps0 * dx) },
+            LumpedElement::Inductor { inductance } => LumpedElement::Inductor { inductance: inductance / (mu0 * dx) },
+            LumpedElement::VoltageSource { resistance, amplitude, frequency, ref signal_type } => LumpedElement::VoltageSource {
+                resistance: resistance / FREE_SPACE_IMPEDANCE,
+                amplitude: amplitude / dx,
+                frequency: frequency * dt,
+                signal_type: signal_type.normalized(dt),
+            },
+        }
+    }
+}
+
+/// Treatment applied to a single edge of the grid.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub enum EdgeBoundary {
+    /// Legacy multiplicative damping layer (quadratic ramp over 20 cells).
+    Damping,
+    /// Convolutional PML with auxiliary psi fields.
+    Cpml(CpmlDefinition),
+    /// Mur absorbing boundary: a one-way wave equation on the edge nodes, cheaper than a PML.
+    Mur(MurOrder),
+    /// Perfect electric conductor: tangential E vanishes on the edge.
+    Pec,
+    /// Perfect magnetic conductor: tangential H vanishes on the edge (a symmetry plane for E).
+    Pmc,
+    /// Wraps around to the opposite edge, which must be periodic as well.
+    /// The last node row or column repeats the first, so the period is `width - 1` (or `height - 1`) cells.
+    Periodic,
+    /// Periodic with a phase advance (radians) across one period, for oblique modes of periodic structures.
+    /// Both edges of the axis must use the same phase.
+    BlochPeriodic { phase: f64 },
+}
+
+impl EdgeBoundary {
+    /// Phase advance across the period if the edge wraps around.
+    pub fn periodic_phase(&self) -> Option<f64> {
+        match *self {
+            EdgeBoundary::Periodic => Some(0.0),
+            EdgeBoundary::BlochPeriodic { phase } => Some(phase),
+            _ => None,
+        }
+    }
+}
+
+/// Order of the one-way wave equation used by a Mur boundary.
+/// The second order adds a transverse term that improves absorption at oblique incidence.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub enum MurOrder {
+    First,
+    Second,
+}
+
+/// Grading profile of a convolutional PML layer.
+/// Conductivities are normalised to `c / dx` (free-space units of the grid).
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct CpmlDefinition {
+    pub thickness: usize,
+    /// Polynomial order `m` of the sigma and kappa grading.
+    pub grading_order: f64,
+    /// Peak conductivity. `None` selects the usual optimum `0.8 * (m + 1)`.
+    pub sigma_max: Option<f64>,
+    pub kappa_max: f64,
+    pub alpha_max: f64,
+    /// Polynomial order of the alpha grading (alpha is largest at the inner interface).
+    pub alpha_grading_order: f64,
+}
+
+impl Default for CpmlDefinition {
+    fn default() -> Self {
+        Self {
+            thickness: 20,
+            grading_order: 3.0,
+            sigma_max: None,
+            kappa_max: 1.0,
+            alpha_max: 0.0,
+            alpha_grading_order: 1.0,
+        }
+    }
+}
+
+/// Boundary treatment for each edge of the simulation space.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct BoundaryDefinition {
+    pub left: EdgeBoundary,
+    pub right: EdgeBoundary,
+    pub top: EdgeBoundary,
+    pub bottom: EdgeBoundary,
+}
+
+impl BoundaryDefinition {
+    /// Uses the same boundary on all four edges.
+    pub fn uniform(edge: EdgeBoundary) -> Self {
+        Self { left: edge.clone(), right: edge.clone(), top: edge.clone(), bottom: edge }
+    }
+
+    /// Whether any edge needs the imaginary part of the fields (a Bloch phase).
+    pub fn is_complex(&self) -> bool {
+        [&self.left, &self.right, &self.top, &self.bottom]
+            .iter()
+            .any(|edge| matches!(edge, EdgeBoundary::BlochPeriodic { .. }))
+    }
+}
+
+impl Default for BoundaryDefinition {
+    fn default() -> Self {
+        Self::uniform(EdgeBoundary::Cpml(CpmlDefinition::default()))
+    }
 }
 
 /// Validates the parameters (e.g., source within bounds).
@@ -40,19 +697,385 @@ pub fn validate_parameters(params: &SimulationParameters) -> Result<(), String>
     if params.width == 0 || params.height == 0 {
         return Err("Width and height must be greater than 0".to_string());
     }
-    // Source position must be 0-indexed and strictly less than width/height
-    if params.source.x >= params.width || params.source.y >= params.height {
-        return Err(format!("Source position ({}, {}) must be within simulation bounds (0..{}x0..{})",
-                           params.source.x, params.source.y, params.width - 1, params.height - 1));
+    for source in &params.sources {
+        validate_source(source, params.width, params.height)?;
     }
-    if params.source.frequency <= 0.0 {
-        return Err("Source frequency must be greater than 0".to_string());
+    if let Some(transmitter) = &params.comms.transmitter {
+        if transmitter.x >= params.width || transmitter.y >= params.height {
+            return Err(format!("Transmitter position ({}, {}) must be within simulation bounds (0..{}x0..{})",
+                               transmitter.x, transmitter.y, params.width - 1, params.height - 1));
+        }
+    }
+    if let Some(dx) = params.grid.cell_size {
+        if dx <= 0.0 || !dx.is_finite() {
+            return Err("Cell size must be a positive number of metres".to_string());
+        }
+    }
+    let courant = params.courant_number();
+    if !(courant > 0.0 && courant <= MAX_COURANT_NUMBER_2D) {
+        return Err(format!("Courant number {} violates the 2D CFL limit (0 < S <= {:.6})",
+                           courant, MAX_COURANT_NUMBER_2D));
+    }
+    if let Some(plane_wave) = &params.plane_wave {
+        validate_plane_wave(plane_wave, params)?;
     }
-    if params.comms.symbol_duration == 0 {
+    validate_materials(params)?;
+    validate_monitors(params)?;
+    validate_lumped_elements(params)?;
+    if let Some(s_parameters) = &params.s_parameters {
+        validate_s_parameters(s_parameters, params)?;
+    }
+    if params.comms.symbol_rate.is_some_and(|r| r <= 0.0) {
+        return Err("Symbol rate must be greater than 0".to_string());
+    }
+    if params.resolve_units().comms.symbol_duration == 0 {
         return Err("Symbol duration must be greater than 0".to_string());
     }
     if params.duration_steps == 0 {
         return Err("Duration steps must be greater than 0".to_string());
     }
+    validate_boundary(&params.boundary)
+}
+
+fn validate_boundary(boundary: &BoundaryDefinition) -> Result<(), String> {
+    for edge in [&boundary.left, &boundary.right, &boundary.top, &boundary.bottom] {
+        match edge {
+            EdgeBoundary::Cpml(cpml) if !cpml_is_valid(cpml) => {
+                return Err("CPML requires finite grading_order >= 0, sigma_max >= 0, kappa_max >= 1, alpha_max >= 0 \
+                            and alpha_grading_order >= 0".to_string());
+            },
+            EdgeBoundary::BlochPeriodic { phase } if !phase.is_finite() => {
+                return Err("Bloch-periodic phase must be a finite number of radians".to_string());
+            },
+            _ => {},
+        }
+    }
+    for (axis, low, high) in [("left/right", &boundary.left, &boundary.right), ("top/bottom", &boundary.top, &boundary.bottom)] {
+        if (low.periodic_phase().is_some() || high.periodic_phase().is_some()) && low != high {
+            return Err(format!("Periodic {} edges must be paired with the same boundary", axis));
+        }
+    }
     Ok(())
 }
+
+/// Every grading parameter is finite and within its physical range.
+fn cpml_is_valid(cpml: &CpmlDefinition) -> bool {
+    let at_least = |value: f64, min: f64| value.is_finite() && value >= min;
+    at_least(cpml.grading_order, 0.0)
+        && cpml.sigma_max.is_none_or(|sigma| at_least(sigma, 0.0))
+        && at_least(cpml.kappa_max, 1.0)
+        && at_least(cpml.alpha_max, 0.0)
+        && at_least(cpml.alpha_grading_order, 0.0)
+}
+
+/// Checks that a source lies on the grid and has a usable frequency and time window.
+pub(crate) fn validate_source(source: &SourceDefinition, width: usize, height: usize) -> Result<(), String> {
+    // Source position must be 0-indexed and strictly less than width/height
+    if source.x >= width || source.y >= height {
+        return Err(format!("Source position ({}, {}) must be within simulation bounds (0..{}x0..{})",
+                           source.x, source.y, width - 1, height - 1));
+    }
+    // A recording plays at its own sample rate and ignores the frequency.
+    let sampled = matches!(source.signal_type, SignalType::Sampled { .. });
+    let positive = source.frequency > 0.0 && source.frequency.is_finite();
+    if !(sampled || positive) {
+        return Err("Source frequency must be greater than 0".to_string());
+    }
+    if !source.amplitude.is_finite() {
+        return Err(format!("Source at ({}, {}) needs a finite amplitude", source.x, source.y));
+    }
+    let window = source.start_time.is_finite() && source.start_time >= 0.0
+        && source.stop_time.is_none_or(|stop| stop > source.start_time);
+    if !window {
+        return Err(format!("Source at ({}, {}) needs 0 <= start_time < stop_time", source.x, source.y));
+    }
+    if source.ramp_time.is_some_and(|ramp| !(ramp.is_finite() && ramp > 0.0)) {
+        return Err(format!("Source at ({}, {}) needs a positive ramp_time", source.x, source.y));
+    }
+    if !source.phase.is_finite() || (source.phase != 0.0 && !source.signal_type.has_carrier()) {
+        return Err(format!("Source at ({}, {}) can only take a finite phase on a waveform with a carrier", source.x, source.y));
+    }
+    validate_waveform(&source.signal_type)
+        .map_err(|e| format!("Source at ({}, {}) {}", source.x, source.y, e))
+}
+
+fn validate_waveform(signal_type: &SignalType) -> Result<(), String> {
+    let valid = match *signal_type {
+        SignalType::Gaussian { width, delay } | SignalType::DifferentiatedGaussian { width, delay } => {
+            width > 0.0 && delay.is_none_or(|d| d >= 0.0)
+        },
+        SignalType::ModulatedGaussian { bandwidth, delay } => bandwidth > 0.0 && delay.is_none_or(|d| d >= 0.0),
+        SignalType::Ricker { delay } => delay.is_none_or(|d| d >= 0.0),
+        SignalType::LinearChirp { bandwidth, duration } | SignalType::ExponentialChirp { bandwidth, duration } => {
+            bandwidth > 0.0 && duration > 0.0
+        },
+        SignalType::Sampled { ref samples, interval, .. } => {
+            !samples.is_empty() && samples.iter().all(|v| v.is_finite()) && interval > 0.0 && interval.is_finite()
+        },
+        _ => true,
+    };
+    if valid {
+        Ok(())
+    } else {
+        Err(format!("has invalid waveform parameters {:?}", signal_type))
+    }
+}
+
+fn validate_plane_wave(plane_wave: &PlaneWaveDefinition, params: &SimulationParameters) -> Result<(), String> {
+    if params.polarization != Polarization::TMz {
+        return Err("Plane-wave sources are only supported in TMz mode".to_string());
+    }
+    // One scattered-field cell is needed on every side of the total-field region.
+    if plane_wave.x_min == 0 || plane_wave.y_min == 0 || plane_wave.x_min >= plane_wave.x_max
+        || plane_wave.y_min >= plane_wave.y_max || plane_wave.x_max + 1 >= params.width
+        || plane_wave.y_max + 1 >= params.height {
+        return Err(format!("Plane-wave region ({}, {})..({}, {}) must be a non-empty rectangle strictly inside the grid",
+                           plane_wave.x_min, plane_wave.y_min, plane_wave.x_max, plane_wave.y_max));
+    }
+    if !plane_wave.frequency.is_finite() || plane_wave.frequency <= 0.0 || !plane_wave.angle.is_finite() {
+        return Err("Plane wave needs a positive frequency and a finite angle".to_string());
+    }
+    // The auxiliary grid needs a real wavenumber at the carrier.
+    let omega = 2.0 * std::f64::consts::PI * params.normalize_frequency(plane_wave.frequency);
+    if numerical_wavenumber(omega, plane_wave.angle.to_radians(), params.courant_number()).is_none() {
+        return Err(format!("Plane-wave frequency {} is beyond the grid's band at {} degrees", plane_wave.frequency, plane_wave.angle));
+    }
+    validate_waveform(&plane_wave.signal_type).map_err(|e| format!("Plane wave {}", e))
+}
+
+fn validate_monitors(params: &SimulationParameters) -> Result<(), String> {
+    let active = match params.polarization {
+        Polarization::TMz => [FieldComponent::Ez, FieldComponent::Hx, FieldComponent::Hy],
+        Polarization::TEz => [FieldComponent::Hz, FieldComponent::Ex, FieldComponent::Ey],
+    };
+    let inside = |x: usize, y: usize| x < params.width && y < params.height;
+    for (i, probe) in params.probes.iter().enumerate() {
+        if probe.name.is_empty() || params.probes[..i].iter().any(|p| p.name == probe.name) {
+            return Err(format!("Probe names must be unique and non-empty (\"{}\")", probe.name));
+        }
+        if !active.contains(&probe.component) {
+            return Err(format!("Probe \"{}\" reads {:?}, which is not solved in {:?} mode",
+                               probe.name, probe.component, params.polarization));
+        }
+        let valid = match probe.shape {
+            ProbeShape::Point { x, y } => inside(x, y),
+            ProbeShape::Line { x0, y0, x1, y1 } => inside(x0, y0) && inside(x1, y1),
+            ProbeShape::Area { x_min, y_min, x_max, y_max } => x_min <= x_max && y_min <= y_max && inside(x_max, y_max),
+        };
+        if !valid {
+            return Err(format!("Probe \"{}\" must lie within the simulation bounds", probe.name));
+        }
+        if probe.capacity == Some(0) {
+            return Err(format!("Probe \"{}\" needs a capacity greater than 0", probe.name));
+        }
+    }
+    for (i, monitor) in params.dft_monitors.iter().enumerate() {
+        if monitor.name.is_empty() || params.dft_monitors[..i].iter().any(|m| m.name == monitor.name) {
+            return Err(format!("DFT monitor names must be unique and non-empty (\"{}\")", monitor.name));
+        }
+        if !active.contains(&monitor.component) {
+            return Err(format!("DFT monitor \"{}\" reads {:?}, which is not solved in {:?} mode",
+                               monitor.name, monitor.component, params.polarization));
+        }
+        if monitor.x_min > monitor.x_max || monitor.y_min > monitor.y_max || !inside(monitor.x_max, monitor.y_max) {
+            return Err(format!("DFT monitor \"{}\" must be a rectangle within the simulation bounds", monitor.name));
+        }
+        if monitor.frequencies.is_empty() || monitor.frequencies.iter().any(|f| !f.is_finite() || *f <= 0.0) {
+            return Err(format!("DFT monitor \"{}\" needs at least one positive frequency", monitor.name));
+        }
+        if !monitor.start_time.is_finite() || monitor.start_time < 0.0 {
+            return Err(format!("DFT monitor \"{}\" needs a start_time >= 0", monitor.name));
+        }
+    }
+    for (i, line) in params.flux_lines.iter().enumerate() {
+        if line.name.is_empty() || params.flux_lines[..i].iter().any(|l| l.name == line.name) {
+            return Err(format!("Flux line names must be unique and non-empty (\"{}\")", line.name));
+        }
+        // The staggered H (TMz) or E (TEz) component sits between the line and the next node.
+        let vertical = line.x0 == line.x1 && line.x0 + 1 < params.width && line.y0.max(line.y1) < params.height;
+        let horizontal = line.y0 == line.y1 && line.y0 + 1 < params.height && line.x0.max(line.x1) < params.width;
+        if !vertical && !horizontal {
+            return Err(format!("Flux line \"{}\" must be horizontal or vertical and leave one cell to the far edge", line.name));
+        }
+        if line.frequencies.iter().any(|f| !f.is_finite() || *f <= 0.0) {
+            return Err(format!("Flux line \"{}\" needs positive frequencies", line.name));
+        }
+    }
+    for (i, far_field) in params.far_fields.iter().enumerate() {
+        if far_field.name.is_empty() || params.far_fields[..i].iter().any(|f| f.name == far_field.name) {
+            return Err(format!("Far-field monitor names must be unique and non-empty (\"{}\")", far_field.name));
+        }
+        // The contour samples the nodes just outside the rectangle.
+        let f = far_field;
+        if f.x_min == 0 || f.y_min == 0 || f.x_min > f.x_max || f.y_min > f.y_max || f.x_max + 1 >= params.width || f.y_max + 1 >= params.height {
+            return Err(format!("Far-field monitor \"{}\" must be a rectangle at least one cell from every edge", f.name));
+        }
+        if f.frequencies.is_empty() || f.frequencies.iter().any(|f| !f.is_finite() || *f <= 0.0) {
+            return Err(format!("Far-field monitor \"{}\" needs at least one positive frequency", f.name));
+        }
+        if f.angles == Some(0) {
+            return Err(format!("Far-field monitor \"{}\" needs at least one angle", f.name));
+        }
+    }
+    if let Some(name) = &params.comms.receiver_probe {
+        if !params.probes.iter().any(|p| &p.name == name) {
+            return Err(format!("Receiver probe \"{}\" is not defined", name));
+        }
+    }
+    if !(params.comms.receiver_noise >= 0.0 && params.comms.receiver_noise.is_finite()) {
+        return Err("Receiver noise must be a finite level of at least 0".to_string());
+    }
+    Ok(())
+}
+
+fn validate_lumped_elements(params: &SimulationParameters) -> Result<(), String> {
+    for (i, lumped) in params.lumped_elements.iter().enumerate() {
+        if params.polarization != Polarization::TMz {
+            return Err("Lumped elements are only supported in TMz mode".to_string());
+        }
+        if lumped.name.is_empty() || params.lumped_elements[..i].iter().any(|e| e.name == lumped.name) {
+            return Err(format!("Lumped element names must be unique and non-empty (\"{}\")", lumped.name));
+        }
+        // Only interior Ez nodes are updated by the curl equation.
+        if lumped.x == 0 || lumped.y == 0 || lumped.x + 1 >= params.width || lumped.y + 1 >= params.height {
+            return Err(format!("Lumped element \"{}\" must lie at least one cell from every edge", lumped.name));
+        }
+        if params.lumped_elements[..i].iter().any(|e| (e.x, e.y) == (lumped.x, lumped.y)) {
+            return Err(format!("Lumped element \"{}\" shares its cell with another element", lumped.name));
+        }
+        let positive = |v: f64| v > 0.0 && v.is_finite();
+        let valid = match lumped.element {
+            LumpedElement::Resistor { resistance: value }
+            | LumpedElement::Capacitor { capacitance: value }
+            | LumpedElement::Inductor { inductance: value } => positive(value),
+            LumpedElement::VoltageSource { resistance, amplitude, frequency, ref signal_type } => {
+                validate_waveform(signal_type).map_err(|e| format!("Lumped element \"{}\" {}", lumped.name, e))?;
+                // A recording plays at its own sample rate and ignores the frequency.
+                let sampled = matches!(signal_type, SignalType::Sampled { .. });
+                positive(resistance) && amplitude.is_finite() && (sampled || positive(frequency))
+            },
+        };
+        if !valid {
+            return Err(format!("Lumped element \"{}\" needs positive, finite values", lumped.name));
+        }
+    }
+    if let Some(name) = &params.comms.transmitter_feed {
+        if !matches!(params.transmitter_feed(), Some(LumpedElementDefinition { element: LumpedElement::VoltageSource { .. }, .. })) {
+            return Err(format!("Transmitter feed \"{}\" is not a lumped voltage source", name));
+        }
+    }
+    Ok(())
+}
+
+fn validate_s_parameters(definition: &SParameterDefinition, params: &SimulationParameters) -> Result<(), String> {
+    if params.polarization != Polarization::TMz {
+        return Err("Ports are only supported in TMz mode".to_string());
+    }
+    if definition.ports.is_empty() || definition.excited_port >= definition.ports.len() {
+        return Err(format!("The excited port {} must be one of the {} ports", definition.excited_port, definition.ports.len()));
+    }
+    if definition.frequencies.is_empty() || definition.frequencies.iter().any(|f| !f.is_finite() || *f <= 0.0) {
+        return Err("S-parameters need at least one positive frequency".to_string());
+    }
+    if !definition.frequency.is_finite() || definition.frequency <= 0.0 || !definition.amplitude.is_finite() {
+        return Err("The port pulse needs a positive frequency and a finite amplitude".to_string());
+    }
+    validate_waveform(&definition.signal_type).map_err(|e| format!("Port pulse {}", e))?;
+    for (i, port) in definition.ports.iter().enumerate() {
+        if port.name.is_empty() || definition.ports[..i].iter().any(|p| p.name == port.name) {
+            return Err(format!("Port names must be unique and non-empty (\"{}\")", port.name));
+        }
+        // Along the line the ends must stay on the grid; across it the pulse node one behind the
+        // line and the node past the reference plane must be interior nodes.
+        let (along, across, length, depth) = match port.direction {
+            PortDirection::PositiveX | PortDirection::NegativeX if port.x0 == port.x1 => {
+                ((port.y0, port.y1), port.x0, params.height, params.width)
+            },
+            PortDirection::PositiveY | PortDirection::NegativeY if port.y0 == port.y1 => {
+                ((port.x0, port.x1), port.y0, params.width, params.height)
+            },
+            _ => return Err(format!("Port \"{}\" must be a line across its direction", port.name)),
+        };
+        let behind = match port.direction {
+            PortDirection::PositiveX | PortDirection::PositiveY => across.checked_sub(1),
+            PortDirection::NegativeX | PortDirection::NegativeY => Some(across + 2),
+        };
+        if along.0.max(along.1) >= length || across == 0 || across + 2 >= depth || behind.is_none_or(|b| b == 0 || b + 1 >= depth) {
+            return Err(format!("Port \"{}\" and the node it launches from must lie inside the grid", port.name));
+        }
+    }
+    Ok(())
+}
+
+fn validate_materials(params: &SimulationParameters) -> Result<(), String> {
+    validate_material_definitions(&params.materials, params.time_step_seconds())?;
+    if params.polarization == Polarization::TEz {
+        if let Some(material) = params.materials.iter().find(|m| !m.poles.is_empty()) {
+            return Err(format!("Dispersive material \"{}\" is only supported in TMz mode", material.name));
+        }
+    }
+    for region in &params.regions {
+        validate_material_reference(&params.materials, &region.material)?;
+    }
+    Ok(())
+}
+
+/// Checks names, ranges and pole stability of user materials.
+/// `time_step_seconds` is `None` when the poles are already in per-step units.
+pub(crate) fn validate_material_definitions(materials: &[MaterialDefinition], time_step_seconds: Option<f64>) -> Result<(), String> {
+    use crate::materials::{PEC_NAME, VACUUM_NAME};
+
+    // Ids are stored as u8 and two are taken by the built-in materials.
+    if materials.len() > u8::MAX as usize - 1 {
+        return Err("Too many materials (at most 254 user materials are supported)".to_string());
+    }
+    for (i, material) in materials.iter().enumerate() {
+        if material.name.is_empty() || material.name == VACUUM_NAME || material.name == PEC_NAME {
+            return Err(format!("Material {} needs a name other than \"{}\" or \"{}\"", i, VACUUM_NAME, PEC_NAME));
+        }
+        if materials[..i].iter().any(|m| m.name == material.name) {
+            return Err(format!("Duplicate material name \"{}\"", material.name));
+        }
+        // Values below 1 would speed up propagation and break the CFL limit.
+        let at_least = |value: f64, min: f64| value.is_finite() && value >= min;
+        if !(at_least(material.relative_permittivity, 1.0) && at_least(material.relative_permeability, 1.0)) {
+            return Err(format!("Material \"{}\" must have finite relative permittivity and permeability >= 1", material.name));
+        }
+        if !at_least(material.conductivity, 0.0) {
+            return Err(format!("Material \"{}\" must have a finite, non-negative conductivity", material.name));
+        }
+        for pole in &material.poles {
+            validate_pole(time_step_seconds.unwrap_or(1.0), &material.name, pole)?;
+        }
+    }
+    Ok(())
+}
+
+/// Checks that a region refers to a built-in or user material.
+pub(crate) fn validate_material_reference(materials: &[MaterialDefinition], name: &str) -> Result<(), String> {
+    use crate::materials::{PEC_NAME, VACUUM_NAME};
+
+    let known = name == VACUUM_NAME || name == PEC_NAME || materials.iter().any(|m| m.name == name);
+    if known {
+        Ok(())
+    } else {
+        Err(format!("Region references unknown material \"{}\"", name))
+    }
+}
+
+fn validate_pole(dt: f64, name: &str, pole: &DispersivePole) -> Result<(), String> {
+    let valid = match pole.normalized(dt) {
+        DispersivePole::Drude { plasma_frequency, collision_rate } => plasma_frequency > 0.0 && collision_rate >= 0.0,
+        // The explicit Lorentz recursion is only stable for w0 * dt < 2.
+        DispersivePole::Lorentz { delta_epsilon, resonance_frequency, damping } => {
+            delta_epsilon >= 0.0 && resonance_frequency > 0.0 && damping >= 0.0
+                && 2.0 * std::f64::consts::PI * resonance_frequency < 2.0
+        },
+        DispersivePole::Debye { delta_epsilon, relaxation_time } => delta_epsilon >= 0.0 && relaxation_time > 0.0,
+    };
+    if valid {
+        Ok(())
+    } else {
+        Err(format!("Material \"{}\" has an invalid or unstable dispersive pole {:?}", name, pole))
+    }
+}
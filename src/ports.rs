@@ -0,0 +1,312 @@
+use crate::checkpoint::{Reader, Writer};
+use crate::engine::compute_source_signal;
+use crate::parameters::{PortDirection, SParameterDefinition, SignalType};
+use crate::precision::{widen, Real};
+use crate::state::SimulationState;
+use serde::Serialize;
+use std::f64::consts::PI;
+
+/// A waveguide port (see `PortDefinition`). It accumulates running DFTs of the modal voltage
+/// (Ez averaged onto the reference plane) and current (the tangential H on it), which the modal
+/// wave impedance of the grid splits into the waves travelling into and out of the device.
+#[derive(Debug, Clone)]
+pub struct Port {
+    pub name: String,
+    pub frequencies: Vec<f64>,
+    /// The two Ez nodes on either side of the reference plane and the H node on it, as for flux lines.
+    cells: Vec<(usize, usize, usize)>,
+    /// Mode profile along the line, normalised so that `sum(profile^2) = 1`.
+    profile: Vec<f64>,
+    /// Whether the plane is crossed along y, where Hx is the tangential component.
+    horizontal: bool,
+    /// Sign that makes `orientation * H = E / Z` for a wave travelling into the device.
+    orientation: f64,
+    /// Ez nodes the pulse is added to, with their weights, when this is the excited port.
+    launch: Vec<(usize, f64)>,
+    pulse: Option<(f64, f64, SignalType)>,
+    /// Modal wave impedance at each frequency, normalised to the free-space impedance.
+    impedance: Vec<(f64, f64)>,
+    voltage: (Vec<f64>, Vec<f64>),
+    current: (Vec<f64>, Vec<f64>),
+}
+
+impl Port {
+    /// Builds port `index` of `definition` on the rasterized grid of `state`, which sets its mode and impedance.
+    pub fn new(definition: &SParameterDefinition, index: usize, state: &SimulationState) -> Result<Self, String> {
+        let port = &definition.ports[index];
+        let w = state.width;
+        let horizontal = port.y0 == port.y1;
+        let node = |along: usize, across: usize| if horizontal { across * w + along } else { along * w + across };
+        let (lo, hi, across) = if horizontal {
+            (port.x0.min(port.x1), port.x0.max(port.x1), port.y0)
+        } else {
+            (port.y0.min(port.y1), port.y0.max(port.y1), port.x0)
+        };
+        let cells: Vec<_> = (lo..=hi).map(|i| (node(i, across), node(i, across + 1), node(i, across))).collect();
+
+        // The line must sit in one lossless, non-dispersive medium.
+        let material = state.materials[cells[0].0];
+        if cells.iter().any(|&(a, b, _)| state.materials[a] != material || state.materials[b] != material) || state.is_pec(cells[0].0) {
+            return Err(format!("Port \"{}\" must lie in a single medium", port.name));
+        }
+        let medium = &state.material_table[material as usize];
+        if medium.conductivity != 0.0 || !medium.poles.is_empty() {
+            return Err(format!("Port \"{}\" must lie in a lossless, non-dispersive medium", port.name));
+        }
+
+        let length = if horizontal { w } else { state.height };
+        let walled = |end: Option<usize>| end.filter(|&i| i < length)
+            .is_some_and(|i| state.is_pec(node(i, across)) && state.is_pec(node(i, across + 1)));
+        let n = cells.len();
+        let (profile, transverse): (Vec<f64>, f64) = match (walled(lo.checked_sub(1)), walled(Some(hi + 1))) {
+            // Ez vanishes on the walls one node beyond each end.
+            (true, true) => ((1..=n).map(|j| (PI * j as f64 / (n + 1) as f64).sin()).collect(), (PI / (2 * (n + 1)) as f64).sin().powi(2)),
+            (false, false) => (vec![1.0; n], 0.0),
+            _ => return Err(format!("Port \"{}\" must end on PEC walls at both ends or at neither", port.name)),
+        };
+        let norm = profile.iter().map(|p| p * p).sum::<f64>().sqrt();
+        let peak = profile.iter().cloned().fold(0.0, f64::max);
+
+        let (inward, behind) = match port.direction {
+            PortDirection::PositiveX | PortDirection::PositiveY => (true, across - 1),
+            PortDirection::NegativeX | PortDirection::NegativeY => (false, across + 2),
+        };
+        // Poynting flux along +x is -Ez Hy and along +y is Ez Hx.
+        let orientation = if horizontal { 1.0 } else { -1.0 } * if inward { 1.0 } else { -1.0 };
+        let excited = index == definition.excited_port;
+        let launch = if excited { (lo..=hi).zip(&profile).map(|(i, p)| (node(i, behind), p / peak)).collect() } else { Vec::new() };
+        let pulse = excited.then(|| (definition.amplitude, definition.frequency, definition.signal_type.clone()));
+
+        let (eps, mu, courant) = (medium.relative_permittivity, medium.relative_permeability, state.courant);
+        let impedance = definition.frequencies.iter().map(|&f| {
+            modal_impedance(f, eps, mu, courant, transverse)
+                .ok_or_else(|| format!("Port \"{}\" has no defined impedance at frequency {}: it is at the mode's cutoff or beyond the grid's band", port.name, f))
+        }).collect::<Result<_, _>>()?;
+        let spectrum = (vec![0.0; definition.frequencies.len()], vec![0.0; definition.frequencies.len()]);
+        Ok(Self {
+            name: port.name.clone(),
+            frequencies: definition.frequencies.clone(),
+            cells,
+            profile: profile.iter().map(|p| p / norm).collect(),
+            horizontal,
+            orientation,
+            launch,
+            pulse,
+            impedance,
+            voltage: spectrum.clone(),
+            current: spectrum,
+        })
+    }
+
+    /// Whether this port launches the pulse.
+    pub fn is_excited(&self) -> bool {
+        self.pulse.is_some()
+    }
+
+    /// Adds the pulse of the excited port to Ez. Runs with the other sources.
+    pub fn launch(&self, ez: &mut [Real], time_step: usize) {
+        if let Some((amplitude, frequency, signal_type)) = &self.pulse {
+            let value = compute_source_signal(time_step as f64, *frequency, *amplitude, signal_type);
+            for &(idx, weight) in &self.launch {
+                ez[idx] += (value * weight) as Real;
+            }
+        }
+    }
+
+    /// Adds the fields of the step just completed to the spectra. Runs at the end of `step`.
+    pub fn update(&mut self, state: &SimulationState) {
+        let h = if self.horizontal { &state.hx } else { &state.hy };
+        let (mut voltage, mut current) = (0.0, 0.0);
+        for (&(a, b, c), &p) in self.cells.iter().zip(&self.profile) {
+            voltage += p * 0.5 * (widen(state.ez[a]) + widen(state.ez[b]));
+            current += p * widen(h[c]);
+        }
+        current *= self.orientation;
+        // Ez sits on whole steps and H half a step behind, as in `FluxLine::update`.
+        let t_voltage = state.time_step as f64 + 1.0;
+        let t_current = t_voltage - 0.5;
+        for (k, &frequency) in self.frequencies.iter().enumerate() {
+            let (sin_v, cos_v) = (2.0 * PI * frequency * t_voltage).sin_cos();
+            let (sin_c, cos_c) = (2.0 * PI * frequency * t_current).sin_cos();
+            self.voltage.0[k] += voltage * cos_v;
+            self.voltage.1[k] -= voltage * sin_v;
+            self.current.0[k] += current * cos_c;
+            self.current.1[k] -= current * sin_c;
+        }
+    }
+
+    /// Power waves `(V + Z I) / (2 sqrt(Z))` into the device and `(V - Z I) / (2 sqrt(Z))` out of it.
+    pub fn waves(&self) -> Vec<((f64, f64), (f64, f64))> {
+        (0..self.frequencies.len()).map(|k| {
+            let z = self.impedance[k];
+            let v = (self.voltage.0[k], self.voltage.1[k]);
+            let zi = mul(z, (self.current.0[k], self.current.1[k]));
+            let scale = div((0.5, 0.0), sqrt(z));
+            (mul(scale, (v.0 + zi.0, v.1 + zi.1)), mul(scale, (v.0 - zi.0, v.1 - zi.1)))
+        }).collect()
+    }
+
+    /// Modal wave impedance `(re, im)` at each frequency, normalised to the free-space impedance.
+    pub fn impedance(&self) -> &[(f64, f64)] {
+        &self.impedance
+    }
+
+    pub fn reset(&mut self) {
+        for values in [&mut self.voltage.0, &mut self.voltage.1, &mut self.current.0, &mut self.current.1] {
+            values.fill(0.0);
+        }
+    }
+
+    pub fn save(&self, out: &mut Writer) {
+        for values in [&self.voltage.0, &self.voltage.1, &self.current.0, &self.current.1] {
+            out.f64s(values);
+        }
+    }
+
+    pub fn restore(&mut self, input: &mut Reader) -> Result<(), String> {
+        for values in [&mut self.voltage.0, &mut self.voltage.1, &mut self.current.0, &mut self.current.1] {
+            input.f64s_into(values, "port spectrum values")?;
+        }
+        Ok(())
+    }
+}
+
+/// Wave impedance `mu sin(w dt / 2) / (S tan(k / 2))` of the fundamental mode on the Yee grid, where
+/// `k` follows from the discrete dispersion relation `eps mu sin^2(w dt / 2) / S^2 = sin^2(k / 2) + transverse`.
+/// Below cutoff `k` is imaginary and so is the impedance. `None` at the cutoff itself, where the
+/// impedance is infinite, and from the grid's band edge (`k = pi`) on.
+fn modal_impedance(frequency: f64, eps: f64, mu: f64, courant: f64, transverse: f64) -> Option<(f64, f64)> {
+    let s = (PI * frequency).sin();
+    let q = eps * mu * s * s / (courant * courant) - transverse;
+    if q.abs() <= CUTOFF_TOLERANCE * transverse || q >= 1.0 {
+        return None;
+    }
+    let tan_squared = q / (1.0 - q);
+    // Choose the root of a wave that decays as it travels.
+    let tan = if tan_squared >= 0.0 { (tan_squared.sqrt(), 0.0) } else { (0.0, -(-tan_squared).sqrt()) };
+    Some(div((mu * s / courant, 0.0), tan))
+}
+
+/// Relative distance from cutoff, in `sin^2(k / 2)`, inside which a frequency is treated as at cutoff.
+const CUTOFF_TOLERANCE: f64 = 1e-6;
+
+fn mul((a, b): (f64, f64), (c, d): (f64, f64)) -> (f64, f64) {
+    (a * c - b * d, a * d + b * c)
+}
+
+fn div((a, b): (f64, f64), (c, d): (f64, f64)) -> (f64, f64) {
+    let norm = c * c + d * d;
+    ((a * c + b * d) / norm, (b * c - a * d) / norm)
+}
+
+/// Principal square root.
+fn sqrt((re, im): (f64, f64)) -> (f64, f64) {
+    let r = re.hypot(im);
+    let a = ((r + re) / 2.0).sqrt();
+    let b = ((r - re) / 2.0).sqrt();
+    (a, if im < 0.0 { -b } else { b })
+}
+
+/// One scattering parameter against frequency: the wave leaving port `to` over the wave entering port `from`.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct SParameter {
+    /// `S` followed by the one-based port numbers, e.g. `S21`.
+    pub name: String,
+    /// Zero-based port indices.
+    pub to: usize,
+    pub from: usize,
+    pub re: Vec<f64>,
+    pub im: Vec<f64>,
+}
+
+impl SParameter {
+    /// Magnitude in dB, floored at -200 dB.
+    pub fn magnitude_db(&self) -> Vec<f64> {
+        self.re.iter().zip(&self.im).map(|(re, im)| 20.0 * re.hypot(*im).max(1e-10).log10()).collect()
+    }
+
+    /// Phase in degrees.
+    pub fn phase_deg(&self) -> Vec<f64> {
+        self.re.iter().zip(&self.im).map(|(re, im)| im.atan2(*re).to_degrees()).collect()
+    }
+}
+
+/// S-parameters of a set of ports. A run measures the column of its excited port; `merge`
+/// collects the columns of runs that excite the other ports.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct SParameters {
+    /// Frequencies in cycles per time step.
+    pub frequencies: Vec<f64>,
+    pub ports: Vec<String>,
+    pub parameters: Vec<SParameter>,
+}
+
+impl SParameters {
+    /// The column of the excited port, from the spectra accumulated so far. Every wave is normalised
+    /// to its port's modal impedance, so `sum(|S_i1|^2) = 1` for a lossless device.
+    pub fn measure(ports: &[Port]) -> Self {
+        let waves: Vec<_> = ports.iter().map(Port::waves).collect();
+        let parameters = ports.iter().position(Port::is_excited).map(|from| {
+            (0..ports.len()).map(|to| {
+                let (re, im) = waves[to].iter().zip(&waves[from]).map(|(&(_, out), &(into, _))| div(out, into)).unzip();
+                SParameter { name: format!("S{}{}", to + 1, from + 1), to, from, re, im }
+            }).collect()
+        }).unwrap_or_default();
+        Self {
+            frequencies: ports.first().map(|p| p.frequencies.clone()).unwrap_or_default(),
+            ports: ports.iter().map(|p| p.name.clone()).collect(),
+            parameters,
+        }
+    }
+
+    /// The parameter from port `from` to port `to` (zero-based), if measured.
+    pub fn get(&self, to: usize, from: usize) -> Option<&SParameter> {
+        self.parameters.iter().find(|p| p.to == to && p.from == from)
+    }
+
+    /// Adds the parameters of another run on the same ports and frequencies.
+    pub fn merge(&mut self, other: SParameters) -> Result<(), String> {
+        if other.ports != self.ports || other.frequencies != self.frequencies {
+            return Err("S-parameters come from different ports or frequencies".to_string());
+        }
+        for parameter in other.parameters {
+            if self.get(parameter.to, parameter.from).is_none() {
+                self.parameters.push(parameter);
+            }
+        }
+        self.parameters.sort_by_key(|p| (p.from, p.to));
+        Ok(())
+    }
+
+    /// A Touchstone 1.1 two-port file (`.s2p`) in real/imaginary form. Frequencies are in hertz when
+    /// `time_step_seconds` is known, otherwise in cycles per time step. All four parameters are needed.
+    ///
+    /// The waves are normalised to each port's own modal impedance, which varies with frequency and
+    /// has no value in ohms on a 2D grid. The option line therefore declares a unit reference
+    /// (`R 1`, in units of the port impedance) rather than 50 ohms, and the data should not be
+    /// renormalised to a reference in ohms.
+    pub fn to_touchstone(&self, time_step_seconds: Option<f64>) -> Result<String, String> {
+        if self.ports.len() != 2 {
+            return Err(format!("A .s2p file needs 2 ports, not {}", self.ports.len()));
+        }
+        // Touchstone orders a two-port row as S11 S21 S12 S22.
+        let order = [(0, 0), (1, 0), (0, 1), (1, 1)];
+        let columns = order.iter().map(|&(to, from)| self.get(to, from)
+            .ok_or_else(|| format!("S{}{} has not been measured; excite port \"{}\" too", to + 1, from + 1, self.ports[from])))
+            .collect::<Result<Vec<_>, _>>()?;
+        let mut text = format!("! Ports: 1 = \"{}\", 2 = \"{}\"\n", self.ports[0], self.ports[1]);
+        text.push_str("! Waves are normalised to the modal wave impedance of each port: R 1 is that impedance, not 1 ohm\n");
+        if time_step_seconds.is_none() {
+            text.push_str("! Frequencies are in cycles per time step\n");
+        }
+        text.push_str("# HZ S RI R 1\n");
+        for (k, frequency) in self.frequencies.iter().enumerate() {
+            text.push_str(&(frequency / time_step_seconds.unwrap_or(1.0)).to_string());
+            for column in &columns {
+                text.push_str(&format!(" {} {}", column.re[k], column.im[k]));
+            }
+            text.push('\n');
+        }
+        Ok(text)
+    }
+}
@@ -0,0 +1,114 @@
+use crate::checkpoint::{Reader, Writer};
+use crate::precision::{widen, Real};
+use crate::parameters::{ProbeDefinition, ProbeShape};
+use crate::volume::parameters::FieldComponent;
+
+/// Records the average of one field component over a set of cells into a ring buffer.
+#[derive(Debug, Clone)]
+pub struct ProbeRecorder {
+    pub name: String,
+    pub component: FieldComponent,
+    /// Grid indices of the averaged cells.
+    cells: Vec<usize>,
+    buffer: Vec<f64>,
+    /// Total number of samples recorded since the last reset.
+    recorded: usize,
+}
+
+impl ProbeRecorder {
+    /// Builds the recorder for a validated probe. `default_capacity` applies when the probe sets none.
+    pub fn new(definition: &ProbeDefinition, width: usize, default_capacity: usize) -> Self {
+        let cells = match definition.shape {
+            ProbeShape::Point { x, y } => vec![y * width + x],
+            ProbeShape::Line { x0, y0, x1, y1 } => line_cells(x0, y0, x1, y1).map(|(x, y)| y * width + x).collect(),
+            ProbeShape::Area { x_min, y_min, x_max, y_max } => {
+                (y_min..=y_max).flat_map(|y| (x_min..=x_max).map(move |x| y * width + x)).collect()
+            },
+        };
+        Self {
+            name: definition.name.clone(),
+            component: definition.component,
+            cells,
+            buffer: vec![0.0; definition.capacity.unwrap_or(default_capacity).max(1)],
+            recorded: 0,
+        }
+    }
+
+    /// Appends the current average of `field`, overwriting the oldest sample when full.
+    pub fn record(&mut self, field: &[Real]) {
+        let sum: f64 = self.cells.iter().map(|&idx| widen(field[idx])).sum();
+        let slot = self.recorded % self.buffer.len();
+        self.buffer[slot] = sum / self.cells.len() as f64;
+        self.recorded += 1;
+    }
+
+    /// Number of samples currently held.
+    pub fn len(&self) -> usize {
+        self.recorded.min(self.buffer.len())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.recorded == 0
+    }
+
+    /// Total number of samples recorded, including the overwritten ones.
+    pub fn recorded(&self) -> usize {
+        self.recorded
+    }
+
+    pub fn latest(&self) -> Option<f64> {
+        (self.recorded > 0).then(|| self.buffer[(self.recorded - 1) % self.buffer.len()])
+    }
+
+    /// The held samples, oldest first.
+    pub fn samples(&self) -> Vec<f64> {
+        let capacity = self.buffer.len();
+        let start = self.recorded.saturating_sub(capacity);
+        (start..self.recorded).map(|n| self.buffer[n % capacity]).collect()
+    }
+
+    pub fn reset(&mut self) {
+        self.buffer.fill(0.0);
+        self.recorded = 0;
+    }
+
+    pub fn save(&self, out: &mut Writer) {
+        out.f64s(&self.buffer);
+        out.usize(self.recorded);
+    }
+
+    pub fn restore(&mut self, input: &mut Reader) -> Result<(), String> {
+        input.f64s_into(&mut self.buffer, "probe samples")?;
+        self.recorded = input.usize()?;
+        Ok(())
+    }
+}
+
+/// Cells of the digital straight line between two points, end points included (Bresenham).
+fn line_cells(x0: usize, y0: usize, x1: usize, y1: usize) -> impl Iterator<Item = (usize, usize)> {
+    let (x0, y0, x1, y1) = (x0 as i64, y0 as i64, x1 as i64, y1 as i64);
+    let (dx, dy) = ((x1 - x0).abs(), -(y1 - y0).abs());
+    let (sx, sy) = ((x1 - x0).signum(), (y1 - y0).signum());
+    let (mut x, mut y, mut error) = (x0, y0, dx + dy);
+    let mut done = false;
+    std::iter::from_fn(move || {
+        if done {
+            return None;
+        }
+        let cell = (x as usize, y as usize);
+        if x == x1 && y == y1 {
+            done = true;
+        } else {
+            let e2 = 2 * error;
+            if e2 >= dy {
+                error += dy;
+                x += sx;
+            }
+            if e2 <= dx {
+                error += dx;
+                y += sy;
+            }
+        }
+        Some(cell)
+    })
+}
@@ -59,8 +59,27 @@ pub fn rasterize_path(width: usize, height: usize, path: &str, grid: &mut [f64])
     }
 }
 
+/// Rasterizes a single SVG path string onto the material grid, reporting parse failures.
+/// The error message names the offending path so callers can surface it to the user.
+pub fn try_rasterize_path(width: usize, height: usize, path: &str, grid: &mut [f64]) -> Result<(), String> {
+    try_rasterize_path_with(width, height, path, grid, 1.0)
+}
+
+/// Like `try_rasterize_path`, but writes `value` (e.g. a material id) into every covered cell.
+pub fn try_rasterize_path_with<T: Copy>(width: usize, height: usize, path: &str, grid: &mut [T], value: T) -> Result<(), String> {
+    let commands = parse_svg_path(path)
+        .map_err(|e| format!("Invalid obstacle path \"{}\": {}", path, e))?;
+    fill_path_with(width, height, &commands, grid, value);
+    Ok(())
+}
+
 /// Fills the shape defined by the commands onto the grid.
 pub fn fill_path_on_grid(width: usize, height: usize, commands: &[PathCommand], grid: &mut [f64]) {
+    fill_path_with(width, height, commands, grid, 1.0);
+}
+
+/// Fills the shape defined by the commands with `value`.
+pub fn fill_path_with<T: Copy>(width: usize, height: usize, commands: &[PathCommand], grid: &mut [T], value: T) {
     // Simple Ray-Casting algorithm for point-in-polygon
     // 1. Convert commands to vertices
     let mut vertices = Vec::new();
@@ -104,7 +123,7 @@ pub fn fill_path_on_grid(width: usize, height: usize, commands: &[PathCommand],
             }
             
             if inside {
-                grid[y * width + x] = 1.0;
+                grid[y * width + x] = value;
             }
         }
     }
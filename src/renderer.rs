@@ -1,16 +1,87 @@
+use crate::dft::DftMonitor;
+use crate::materials::{PEC, VACUUM};
 use crate::state::SimulationState;
+use crate::volume::parameters::{Axis, FieldComponent};
+use crate::volume::state::VolumeState;
 
 /// Renders the current simulation state to an RGBA buffer.
+/// Shows Ez in TMz mode and Hz in TEz mode.
 pub fn render(state: &SimulationState) -> Vec<u8> {
-    let mut buffer = Vec::with_capacity(state.width * state.height * 4);
-    for (i, &val) in state.ez.iter().enumerate() {
-        let material_value = state.materials[i];
-        let color = map_value_to_color(val, material_value);
+    render_field(state.primary_field(), &state.materials)
+}
+
+/// Renders one component of a 3D simulation on the plane normal to `normal` at `position`.
+/// The image size is given by `VolumeState::slice_dimensions`.
+pub fn render_slice(state: &VolumeState, component: FieldComponent, normal: Axis, position: usize) -> Vec<u8> {
+    let (values, materials) = state.slice(state.field(component), normal, position);
+    render_field(&values, &materials)
+}
+
+/// Map of a DFT monitor drawn by `render_dft`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DftView {
+    /// Magnitude normalised to its maximum over the monitor.
+    Magnitude,
+    /// Phase as a colour wheel, dimmed where the magnitude is small.
+    Phase,
+}
+
+/// Renders a DFT monitor at frequency `index` to an RGBA buffer the size of its rectangle.
+pub fn render_dft(state: &SimulationState, monitor: &DftMonitor, index: usize, view: DftView) -> Vec<u8> {
+    let magnitude = monitor.magnitude(index);
+    let peak = magnitude.iter().fold(0.0f64, |a, &m| a.max(m));
+    let brightness: Vec<f64> = magnitude.iter().map(|m| if peak > 0.0 { m / peak } else { 0.0 }).collect();
+    let materials: Vec<u8> = (0..monitor.height)
+        .flat_map(|y| {
+            let row = (monitor.y_min + y) * state.width + monitor.x_min;
+            state.materials[row..row + monitor.width].iter().copied()
+        })
+        .collect();
+
+    match view {
+        DftView::Magnitude => render_field(&brightness, &materials),
+        DftView::Phase => {
+            let phase = monitor.phase(index);
+            let mut buffer = Vec::with_capacity(phase.len() * 4);
+            for ((&angle, &level), &material) in phase.iter().zip(&brightness).zip(&materials) {
+                let color = if material == PEC { [0, 255, 0, 255] } else { phase_to_color(angle, level) };
+                buffer.extend_from_slice(&color);
+            }
+            buffer
+        },
+    }
+}
+
+/// Maps a phase (radians) to a hue, at brightness `level` in 0..=1.
+pub fn phase_to_color(phase: f64, level: f64) -> [u8; 4] {
+    let hue = phase.rem_euclid(2.0 * std::f64::consts::PI) / (2.0 * std::f64::consts::PI) * 6.0;
+    let channel = |offset: f64| {
+        let d = (hue - offset).rem_euclid(6.0);
+        let weight = (2.0 - d.min(6.0 - d)).clamp(0.0, 1.0);
+        (weight * level.clamp(0.0, 1.0) * 255.0) as u8
+    };
+    [channel(0.0), channel(2.0), channel(4.0), 255]
+}
+
+/// Renders field values over their material ids to an RGBA buffer.
+pub fn render_field<T: Copy + Into<f64>>(values: &[T], materials: &[u8]) -> Vec<u8> {
+    let mut buffer = Vec::with_capacity(values.len() * 4);
+    for (&val, &material) in values.iter().zip(materials) {
+        let material_value = if material == PEC { 1.0 } else { 0.0 };
+        let mut color = map_value_to_color(val.into(), material_value);
+        if material != VACUUM && material != PEC {
+            color = tint_dielectric(color);
+        }
         buffer.extend_from_slice(&color);
     }
     buffer
 }
 
+/// Adds a faint green background so dielectric regions stay visible under the field.
+pub fn tint_dielectric(color: [u8; 4]) -> [u8; 4] {
+    [color[0], color[1].max(48), color[2], color[3]]
+}
+
 pub fn map_value_to_color(value: f64, material_value: f64) -> [u8; 4] {
     if material_value > 0.0 {
         // Render obstacles in green
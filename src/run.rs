@@ -0,0 +1,70 @@
+use crate::flux;
+use crate::state::SimulationState;
+use serde::{Deserialize, Serialize};
+
+/// Condition that ends a batch of steps before `duration_steps` is reached.
+/// It is checked after every step.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub enum StopCondition {
+    /// The receiver has decoded a packet with a valid CRC.
+    PacketDecoded,
+    /// The magnitude of a probe's latest sample reaches `threshold`.
+    ProbeThreshold { probe: String, threshold: f64 },
+    /// The electromagnetic energy stored in the grid rises to `energy`.
+    EnergyAbove { energy: f64 },
+    /// The electromagnetic energy stored in the grid decays to `energy`, e.g. after a pulse.
+    EnergyBelow { energy: f64 },
+}
+
+impl StopCondition {
+    /// Checks that the condition refers to parts that exist in `state`.
+    pub fn validate(&self, state: &SimulationState) -> Result<(), String> {
+        match self {
+            StopCondition::ProbeThreshold { probe, .. } if state.probe(probe).is_none() => {
+                Err(format!("Unknown probe \"{}\"", probe))
+            },
+            _ => Ok(()),
+        }
+    }
+
+    /// Whether the condition holds for `state`. `packet_decoded` tells whether the latest step
+    /// completed a valid packet.
+    pub fn is_met(&self, state: &SimulationState, packet_decoded: bool) -> bool {
+        match self {
+            StopCondition::PacketDecoded => packet_decoded,
+            StopCondition::ProbeThreshold { probe, threshold } => {
+                state.probe(probe).and_then(|p| p.latest()).is_some_and(|v| v.abs() >= *threshold)
+            },
+            StopCondition::EnergyAbove { energy } => flux::electromagnetic_energy(state, None) >= *energy,
+            StopCondition::EnergyBelow { energy } => flux::electromagnetic_energy(state, None) <= *energy,
+        }
+    }
+}
+
+/// Where a simulation stands after a batch of steps.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct RunStatus {
+    /// Steps taken by this batch.
+    pub steps: usize,
+    pub time_step: usize,
+    pub duration_steps: usize,
+    /// `time_step / duration_steps`, at most 1.
+    pub progress: f64,
+    /// Whether `duration_steps` has been reached.
+    pub finished: bool,
+    /// Whether the batch ended because its stop condition held.
+    pub condition_met: bool,
+}
+
+impl RunStatus {
+    pub fn new(steps: usize, time_step: usize, duration_steps: usize, condition_met: bool) -> Self {
+        Self {
+            steps,
+            time_step,
+            duration_steps,
+            progress: (time_step as f64 / duration_steps as f64).min(1.0),
+            finished: time_step >= duration_steps,
+            condition_met,
+        }
+    }
+}
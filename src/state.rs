@@ -1,21 +1,87 @@
+use crate::boundaries;
+use crate::checkpoint::{Reader, Writer};
+use crate::cpml::CpmlState;
+use crate::dft::DftMonitor;
+use crate::farfield::FarFieldMonitor;
+use crate::flux::FluxLine;
+use crate::lumped::LumpedElement;
+use crate::mur::MurState;
+use crate::ports::Port;
+use crate::probes::ProbeRecorder;
+use crate::volume::parameters::FieldComponent;
+use crate::dispersive::DispersiveState;
+use crate::materials::{self, UpdateCoefficients, PEC, VACUUM};
+use crate::precision::Real;
+use crate::parameters::{BoundaryDefinition, MaterialDefinition, Polarization, SimulationParameters, DEFAULT_COURANT_NUMBER};
+use crate::rasterizer;
+use crate::tfsf::PlaneWaveState;
+
 
 
 /// Represents the state of the simulation at a specific time step.
-/// Typically holds Ez (Electric field) and Hx, Hy (Magnetic fields).
-/// For 2D TMz mode.
+/// In TMz mode it holds Ez (Electric field) and Hx, Hy (Magnetic fields);
+/// in TEz mode Hz, Ex and Ey. The arrays of the inactive mode are empty.
+/// Fields are stored as `Real`, which is `f32` with the `f32` feature.
 pub struct SimulationState {
     pub width: usize,
     pub height: usize,
+    pub polarization: Polarization,
     
-    // Fields
-    pub ez: Vec<f64>,
-    pub hx: Vec<f64>,
-    pub hy: Vec<f64>,
+    // TMz fields
+    pub ez: Vec<Real>,
+    pub hx: Vec<Real>,
+    pub hy: Vec<Real>,
+
+    // TEz fields: Hz shares the nodes of Ez, Ex those of Hx and Ey those of Hy.
+    pub hz: Vec<Real>,
+    pub ex: Vec<Real>,
+    pub ey: Vec<Real>,
+
+    // Material id of every cell, indexing `material_table` and `coefficients`.
+    // 0 is free space and 1 is PEC (see `materials::VACUUM` / `materials::PEC`).
+    pub materials: Vec<u8>,
+    pub material_table: Vec<MaterialDefinition>,
+    // Ca/Cb (electric) and Da/Db (magnetic) update coefficients per material id.
+    pub coefficients: Vec<UpdateCoefficients>,
+
+    // Courant number c*dt/dx. Fields are normalised (H scaled by the free-space impedance),
+    // so in vacuum it is also the curl coefficient of both the E and H updates.
+    // Change it through `set_courant` so the coefficients follow.
+    pub courant: f64,
+
+    // Auxiliary fields of the convolutional PML, present when any edge uses one.
+    pub cpml: Option<CpmlState>,
+
+    // Previous edge values of the Mur boundaries, present when any edge uses one.
+    pub mur: Option<MurState>,
+
+    // Polarisation arrays of dispersive materials, present when any cell uses one.
+    pub dispersive: Option<DispersiveState>,
+
+    // Auxiliary 1D grid of the total-field / scattered-field plane wave, if configured.
+    pub plane_wave: Option<PlaneWaveState>,
+
+    // Imaginary part of the fields when an edge is Bloch-periodic. It shares the materials,
+    // is not driven by the sources and only couples to the real part through the edges.
+    pub bloch: Option<Box<SimulationState>>,
+
+    // Field recorders declared in the parameters, sampled at the end of every step.
+    pub probes: Vec<ProbeRecorder>,
+
+    // Running DFTs declared in the parameters, accumulated at the end of every step.
+    pub dft_monitors: Vec<DftMonitor>,
+
+    // Poynting flux lines declared in the parameters, integrated at the end of every step.
+    pub flux_lines: Vec<FluxLine>,
 
-    // Material properties (0.0 for free space, 1.0 for PEC, or intermediate for other materials)
-    // For this project: Boolean mask or coefficient.
-    // Let's use a coefficient array for flexibility (e.g., C_a, C_b update coefficients).
-    pub materials: Vec<f64>, 
+    // Near-to-far-field contours declared in the parameters, accumulated at the end of every step.
+    pub far_fields: Vec<FarFieldMonitor>,
+
+    // Lumped circuit elements on Ez nodes, applied at the end of the Ez update.
+    pub lumped_elements: Vec<LumpedElement>,
+
+    // Waveguide ports for S-parameters, accumulated at the end of every step.
+    pub ports: Vec<Port>,
 
     pub time_step: usize,
 }
@@ -23,24 +89,357 @@ pub struct SimulationState {
 impl SimulationState {
     pub fn new(width: usize, height: usize) -> Self {
         let size = width * height;
+        let material_table = materials::build_material_table(&[]);
         Self {
             width,
             height,
+            polarization: Polarization::TMz,
             ez: vec![0.0; size],
             hx: vec![0.0; size],
             hy: vec![0.0; size],
-            materials: vec![0.0; size],
+            hz: Vec::new(),
+            ex: Vec::new(),
+            ey: Vec::new(),
+            materials: vec![VACUUM; size],
+            coefficients: materials::compute_coefficients(&material_table, DEFAULT_COURANT_NUMBER),
+            material_table,
+            courant: DEFAULT_COURANT_NUMBER,
+            cpml: None,
+            mur: None,
+            dispersive: None,
+            plane_wave: None,
+            bloch: None,
+            probes: Vec::new(),
+            dft_monitors: Vec::new(),
+            flux_lines: Vec::new(),
+            far_fields: Vec::new(),
+            lumped_elements: Vec::new(),
+            ports: Vec::new(),
             time_step: 0,
         }
     }
 
+    /// Builds the state described by normalised parameters (see `SimulationParameters::resolve_units`):
+    /// Courant number, material table, absorbing layers and rasterized geometry.
+    pub fn from_parameters(params: &SimulationParameters) -> Result<Self, String> {
+        let mut state = SimulationState::new(params.width, params.height);
+        state.set_polarization(params.polarization);
+        state.set_courant(params.courant_number());
+        state.set_material_table(&params.materials);
+        state.configure_boundaries(&params.boundary);
+        state.rasterize_geometry(params)?;
+        state.plane_wave = params.plane_wave.as_ref().map(|p| PlaneWaveState::new(p, state.courant)).transpose()?;
+        state.probes = params.probes.iter().map(|p| ProbeRecorder::new(p, params.width, params.duration_steps)).collect();
+        state.dft_monitors = params.dft_monitors.iter().map(DftMonitor::new).collect();
+        state.flux_lines = params.flux_lines.iter().map(|l| FluxLine::new(l, params.width, params.polarization)).collect();
+        state.far_fields = params.far_fields.iter().map(|f| FarFieldMonitor::new(f, params.width, params.polarization)).collect();
+        state.lumped_elements = params.lumped_elements.iter().map(|e| LumpedElement::new(e, params.width)).collect();
+        if let Some(definition) = &params.s_parameters {
+            state.ports = (0..definition.ports.len()).map(|i| Port::new(definition, i, &state)).collect::<Result<_, _>>()?;
+        }
+        if params.boundary.is_complex() {
+            state.bloch = Some(Box::new(state.imaginary_part()));
+        }
+        Ok(state)
+    }
+
+    /// Switches the solved polarisation, allocating its fields and freeing the other ones.
+    pub fn set_polarization(&mut self, polarization: Polarization) {
+        let size = self.width * self.height;
+        self.polarization = polarization;
+        let (active, inactive) = match polarization {
+            Polarization::TMz => ([&mut self.ez, &mut self.hx, &mut self.hy], [&mut self.hz, &mut self.ex, &mut self.ey]),
+            Polarization::TEz => ([&mut self.hz, &mut self.ex, &mut self.ey], [&mut self.ez, &mut self.hx, &mut self.hy]),
+        };
+        for field in active {
+            *field = vec![0.0; size];
+        }
+        for field in inactive {
+            *field = Vec::new();
+        }
+        self.configure_dispersion();
+    }
+
+    /// The out-of-plane component driven by sources and shown by the renderer:
+    /// Ez in TMz mode, Hz in TEz mode.
+    pub fn primary_field(&self) -> &[Real] {
+        match self.polarization {
+            Polarization::TMz => &self.ez,
+            Polarization::TEz => &self.hz,
+        }
+    }
+
+    /// One field component. Components of the inactive polarisation are empty.
+    pub fn field(&self, component: FieldComponent) -> &[Real] {
+        match component {
+            FieldComponent::Ex => &self.ex,
+            FieldComponent::Ey => &self.ey,
+            FieldComponent::Ez => &self.ez,
+            FieldComponent::Hx => &self.hx,
+            FieldComponent::Hy => &self.hy,
+            FieldComponent::Hz => &self.hz,
+        }
+    }
+
+    /// The DFT monitor called `name`, if declared.
+    pub fn dft_monitor(&self, name: &str) -> Option<&DftMonitor> {
+        self.dft_monitors.iter().find(|m| m.name == name)
+    }
+
+    /// The flux line called `name`, if declared.
+    pub fn flux_line(&self, name: &str) -> Option<&FluxLine> {
+        self.flux_lines.iter().find(|l| l.name == name)
+    }
+
+    /// The far-field monitor called `name`, if declared.
+    pub fn far_field(&self, name: &str) -> Option<&FarFieldMonitor> {
+        self.far_fields.iter().find(|f| f.name == name)
+    }
+
+    /// The lumped element called `name`, if declared.
+    pub fn lumped_element(&self, name: &str) -> Option<&LumpedElement> {
+        self.lumped_elements.iter().find(|e| e.name == name)
+    }
+
+    /// The probe called `name`, if declared.
+    pub fn probe(&self, name: &str) -> Option<&ProbeRecorder> {
+        self.probes.iter().find(|p| p.name == name)
+    }
+
+    pub fn primary_field_mut(&mut self) -> &mut [Real] {
+        match self.polarization {
+            Polarization::TMz => &mut self.ez,
+            Polarization::TEz => &mut self.hz,
+        }
+    }
+
+    /// Changes the Courant number and recomputes the update coefficients.
+    pub fn set_courant(&mut self, courant: f64) {
+        self.courant = courant;
+        self.coefficients = materials::compute_coefficients(&self.material_table, courant);
+        self.configure_dispersion();
+        self.rebuild_bloch();
+    }
+
+    /// Replaces the user materials (ids 2..) and recomputes the update coefficients.
+    pub fn set_material_table(&mut self, definitions: &[MaterialDefinition]) {
+        self.material_table = materials::build_material_table(definitions);
+        self.coefficients = materials::compute_coefficients(&self.material_table, self.courant);
+        self.configure_dispersion();
+        self.rebuild_bloch();
+    }
+
+    /// Resets every cell to free space, then fills the material regions and the PEC obstacles.
+    pub fn rasterize_geometry(&mut self, params: &SimulationParameters) -> Result<(), String> {
+        self.materials.fill(VACUUM);
+        for region in &params.regions {
+            let id = materials::find_material(&self.material_table, &region.material)
+                .ok_or_else(|| format!("Unknown material \"{}\"", region.material))?;
+            rasterizer::try_rasterize_path_with(self.width, self.height, &region.path, &mut self.materials, id)?;
+        }
+        for path in &params.obstacles {
+            rasterizer::try_rasterize_path_with(self.width, self.height, path, &mut self.materials, PEC)?;
+        }
+        self.configure_dispersion();
+        self.sync_bloch_geometry();
+        Ok(())
+    }
+
+    /// Fills an extra PEC obstacle into the grid.
+    pub fn add_obstacle(&mut self, path: &str) -> Result<(), String> {
+        rasterizer::try_rasterize_path_with(self.width, self.height, path, &mut self.materials, PEC)?;
+        self.configure_dispersion();
+        self.sync_bloch_geometry();
+        Ok(())
+    }
+
+    /// Allocates polarisation arrays for the cells made of dispersive materials.
+    /// Must be called again whenever the material ids change.
+    pub fn configure_dispersion(&mut self) {
+        // The auxiliary equations act on Ez, so they only exist in TMz mode.
+        if self.polarization != Polarization::TMz {
+            self.dispersive = None;
+            return;
+        }
+        self.dispersive = DispersiveState::new(&self.material_table, &self.coefficients, &self.materials,
+                                               self.width, self.height, self.courant);
+    }
+
+    /// Whether the cell at `idx` is a perfect electric conductor.
+    pub fn is_pec(&self, idx: usize) -> bool {
+        self.materials[idx] == PEC
+    }
+
+    /// Allocates the absorbing-layer state required by the boundary definition.
+    pub fn configure_boundaries(&mut self, boundary: &BoundaryDefinition) {
+        self.cpml = CpmlState::new(self.width, self.height, boundary, self.courant);
+        if let Some(cpml) = self.cpml.as_mut() {
+            cpml.include_edge_nodes(boundaries::edge_nodes(boundary, self.polarization));
+        }
+        self.mur = MurState::new(self.width, self.height, boundary);
+    }
+
+    /// A zeroed copy of this state's configuration, to hold the imaginary part of the fields.
+    fn imaginary_part(&self) -> Self {
+        let mut imag = SimulationState::new(self.width, self.height);
+        imag.set_polarization(self.polarization);
+        imag.courant = self.courant;
+        imag.material_table = self.material_table.clone();
+        imag.coefficients = self.coefficients.clone();
+        imag.materials = self.materials.clone();
+        imag.cpml = self.cpml.clone();
+        imag.mur = self.mur.clone();
+        imag.lumped_elements = self.lumped_elements.iter().map(LumpedElement::passive).collect();
+        imag.reset();
+        imag.configure_dispersion();
+        imag
+    }
+
+    fn rebuild_bloch(&mut self) {
+        if self.bloch.is_some() {
+            self.bloch = Some(Box::new(self.imaginary_part()));
+        }
+    }
+
+    /// Keeps the imaginary part's material ids in step with edits to the geometry.
+    fn sync_bloch_geometry(&mut self) {
+        if let Some(imag) = self.bloch.as_mut() {
+            imag.materials.clone_from(&self.materials);
+            imag.configure_dispersion();
+        }
+    }
+
     pub fn reset(&mut self) {
         self.ez.fill(0.0);
         self.hx.fill(0.0);
         self.hy.fill(0.0);
+        self.hz.fill(0.0);
+        self.ex.fill(0.0);
+        self.ey.fill(0.0);
+        if let Some(cpml) = self.cpml.as_mut() {
+            cpml.reset();
+        }
+        if let Some(mur) = self.mur.as_mut() {
+            mur.reset();
+        }
+        if let Some(dispersive) = self.dispersive.as_mut() {
+            dispersive.reset();
+        }
+        if let Some(plane_wave) = self.plane_wave.as_mut() {
+            plane_wave.reset();
+        }
+        if let Some(imag) = self.bloch.as_mut() {
+            imag.reset();
+        }
+        for probe in &mut self.probes {
+            probe.reset();
+        }
+        for monitor in &mut self.dft_monitors {
+            monitor.reset();
+        }
+        for line in &mut self.flux_lines {
+            line.reset();
+        }
+        for far_field in &mut self.far_fields {
+            far_field.reset();
+        }
+        for element in &mut self.lumped_elements {
+            element.reset();
+        }
+        for port in &mut self.ports {
+            port.reset();
+        }
         // materials are static, usually don't reset them? Or should we?
         // Requirement says "Reset all fields to zero". 
         // Usually geometry persists. I will NOT reset materials unless requested.
         self.time_step = 0;
     }
+
+    /// Writes the evolving state: time step, material ids, fields, boundary and monitor state.
+    pub fn save(&self, out: &mut Writer) {
+        out.usize(self.width);
+        out.usize(self.height);
+        out.bool(self.polarization == Polarization::TEz);
+        out.usize(self.time_step);
+        out.bytes(&self.materials);
+        for field in [&self.ez, &self.hx, &self.hy, &self.hz, &self.ex, &self.ey] {
+            out.reals(field);
+        }
+        save_part(out, self.cpml.as_ref(), CpmlState::save);
+        save_part(out, self.mur.as_ref(), MurState::save);
+        save_part(out, self.dispersive.as_ref(), DispersiveState::save);
+        save_part(out, self.plane_wave.as_ref(), PlaneWaveState::save);
+        save_part(out, self.bloch.as_deref(), SimulationState::save);
+        out.usize(self.probes.len());
+        self.probes.iter().for_each(|p| p.save(out));
+        out.usize(self.dft_monitors.len());
+        self.dft_monitors.iter().for_each(|m| m.save(out));
+        out.usize(self.flux_lines.len());
+        self.flux_lines.iter().for_each(|l| l.save(out));
+        out.usize(self.far_fields.len());
+        self.far_fields.iter().for_each(|f| f.save(out));
+        out.usize(self.lumped_elements.len());
+        self.lumped_elements.iter().for_each(|e| e.save(out));
+        out.usize(self.ports.len());
+        self.ports.iter().for_each(|p| p.save(out));
+    }
+
+    /// Reads back what `save` wrote into a state built from the same parameters.
+    /// Fails if the grid or the configured parts differ; the state is then left partly restored.
+    pub fn restore(&mut self, input: &mut Reader) -> Result<(), String> {
+        let (width, height) = (input.usize()?, input.usize()?);
+        if (width, height) != (self.width, self.height) {
+            return Err(format!("Checkpoint grid is {}x{} but the simulation grid is {}x{}", width, height, self.width, self.height));
+        }
+        if input.bool()? != (self.polarization == Polarization::TEz) {
+            return Err("Checkpoint was saved with the other polarization".to_string());
+        }
+        self.time_step = input.usize()?;
+        input.bytes_into(&mut self.materials, "material cells")?;
+        if let Some(&id) = self.materials.iter().find(|&&id| id as usize >= self.material_table.len()) {
+            return Err(format!("Checkpoint refers to material {} but only {} are defined", id, self.material_table.len()));
+        }
+        // Obstacles added at run time change the dispersive cells, so rebuild them before reading.
+        self.configure_dispersion();
+        self.sync_bloch_geometry();
+        for field in [&mut self.ez, &mut self.hx, &mut self.hy, &mut self.hz, &mut self.ex, &mut self.ey] {
+            input.reals_into(field, "field values")?;
+        }
+        restore_part(input, self.cpml.as_mut(), "CPML layers", CpmlState::restore)?;
+        restore_part(input, self.mur.as_mut(), "Mur edges", MurState::restore)?;
+        restore_part(input, self.dispersive.as_mut(), "dispersive materials", DispersiveState::restore)?;
+        restore_part(input, self.plane_wave.as_mut(), "a plane wave", PlaneWaveState::restore)?;
+        restore_part(input, self.bloch.as_deref_mut(), "Bloch boundaries", SimulationState::restore)?;
+        input.len(self.probes.len(), "probes")?;
+        self.probes.iter_mut().try_for_each(|p| p.restore(input))?;
+        input.len(self.dft_monitors.len(), "DFT monitors")?;
+        self.dft_monitors.iter_mut().try_for_each(|m| m.restore(input))?;
+        input.len(self.flux_lines.len(), "flux lines")?;
+        self.flux_lines.iter_mut().try_for_each(|l| l.restore(input))?;
+        input.len(self.far_fields.len(), "far-field monitors")?;
+        self.far_fields.iter_mut().try_for_each(|f| f.restore(input))?;
+        input.len(self.lumped_elements.len(), "lumped elements")?;
+        self.lumped_elements.iter_mut().try_for_each(|e| e.restore(input))?;
+        input.len(self.ports.len(), "ports")?;
+        self.ports.iter_mut().try_for_each(|p| p.restore(input))
+    }
+}
+
+/// Writes whether an optional part exists, then its state.
+fn save_part<T>(out: &mut Writer, part: Option<&T>, save: fn(&T, &mut Writer)) {
+    out.bool(part.is_some());
+    if let Some(part) = part {
+        save(part, out);
+    }
+}
+
+/// Reads an optional part written by `save_part`, which must exist in both or neither.
+fn restore_part<T>(input: &mut Reader, part: Option<&mut T>, what: &str,
+                   restore: fn(&mut T, &mut Reader) -> Result<(), String>) -> Result<(), String> {
+    match (input.bool()?, part) {
+        (true, Some(part)) => restore(part, input),
+        (false, None) => Ok(()),
+        (true, None) => Err(format!("Checkpoint has {} but the simulation does not", what)),
+        (false, Some(_)) => Err(format!("Simulation has {} but the checkpoint does not", what)),
+    }
 }
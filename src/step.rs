@@ -1,33 +1,107 @@
-use crate::parameters::SimulationParameters;
+use crate::parameters::{SimulationParameters, EdgeBoundary, Polarization};
 use crate::state::SimulationState;
+use crate::boundaries::{update_edge_nodes, apply_periodic_copies};
 use crate::engine::{
-    update_hx, update_hy, update_e_fields, apply_source, apply_forced_source,
-    apply_boundary_left, apply_boundary_right, apply_boundary_top, apply_boundary_bottom
+    update_hx_hy, update_e_fields, update_cpml_h, update_cpml_e,
+    update_dispersive_pre, update_dispersive_post, update_ex_ey, update_hz,
+    update_cpml_tez_e, update_cpml_tez_h, update_plane_wave_h, update_plane_wave_e, apply_source, apply_forced_source, apply_port_pulse,
+    apply_boundary_left, apply_boundary_right, apply_boundary_top, apply_boundary_bottom, apply_mur_boundaries,
+    record_probes, update_dft_monitors, update_flux_lines, update_far_fields, update_ports
 };
 
 /// Executes a single simulation step.
 /// This orchestrates the field updates, source injection, and boundary conditions.
 pub fn step(params: &SimulationParameters, state: &mut SimulationState, forced_source: Option<f64>) {
-    // 1. Update Magnetic Fields (Hx, Hy)
-    update_hx(state);
-    update_hy(state);
+    let boundary = &params.boundary;
+    // A transmitter feed carries the forced value as the EMF of its voltage source.
+    let feed = params.comms.transmitter_feed.as_ref();
+    if let (Some(name), Some(val)) = (feed, forced_source) {
+        if let Some(element) = state.lumped_elements.iter_mut().find(|e| &e.name == name) {
+            element.drive(val);
+        }
+    }
+    let forced_source = forced_source.filter(|_| feed.is_none());
+    match state.polarization {
+        Polarization::TMz => {
+            // 1. Update Magnetic Fields (Hx, Hy)
+            for_each_part(state, |s| {
+                update_hx_hy(s);
+                update_cpml_h(s);
+            });
+            update_plane_wave_h(state);
+
+            // 2. Update Electric Fields (Ez), including the outer nodes of mirror and periodic edges
+            for_each_part(state, |s| {
+                update_dispersive_pre(s);
+                update_e_fields(s);
+            });
+            update_edge_nodes(state, boundary);
+            for_each_part(state, |s| {
+                update_cpml_e(s);
+                update_dispersive_post(s);
+            });
+            update_plane_wave_e(state);
+        },
+        Polarization::TEz => {
+            // 1. Update Electric Fields (Ex, Ey)
+            for_each_part(state, |s| {
+                update_ex_ey(s);
+                update_cpml_tez_e(s);
+            });
 
-    // 2. Update Electric Fields (Ez)
-    update_e_fields(state);
+            // 2. Update Magnetic Field (Hz), including the outer nodes of mirror and periodic edges
+            for_each_part(state, update_hz);
+            update_edge_nodes(state, boundary);
+            for_each_part(state, update_cpml_tez_h);
+        },
+    }
 
-    // 3. Apply Source
+    // 3. Apply Sources. A transmitter without its own position replaces the first source.
+    let replaces_first = forced_source.is_some() && params.comms.transmitter.is_none();
+    for (i, source) in params.sources.iter().enumerate() {
+        if !(replaces_first && i == 0) {
+            apply_source(state, source);
+        }
+    }
     if let Some(val) = forced_source {
-        apply_forced_source(state, params.source.x, params.source.y, val);
-    } else {
-        apply_source(state, &params.source);
+        let transmitter = params.transmitter();
+        apply_forced_source(state, transmitter.x, transmitter.y, val);
     }
+    apply_port_pulse(state);
 
-    // 4. Apply Boundary Conditions
-    apply_boundary_left(state);
-    apply_boundary_right(state);
-    apply_boundary_top(state);
-    apply_boundary_bottom(state);
+    // 4. Apply Boundary Conditions. PEC, PMC and CPML edges act inside the field updates;
+    // periodic edges copy their first node row or column onto the last one.
+    // Mur edges run last so they record the final values of this step.
+    apply_periodic_copies(state, boundary);
+    for_each_part(state, |s| {
+        if boundary.left == EdgeBoundary::Damping {
+            apply_boundary_left(s);
+        }
+        if boundary.right == EdgeBoundary::Damping {
+            apply_boundary_right(s);
+        }
+        if boundary.top == EdgeBoundary::Damping {
+            apply_boundary_top(s);
+        }
+        if boundary.bottom == EdgeBoundary::Damping {
+            apply_boundary_bottom(s);
+        }
+        apply_mur_boundaries(s);
+    });
 
-    // 5. Advance Time
+    // 5. Record Probes and DFT Monitors, then Advance Time
+    record_probes(state);
+    update_dft_monitors(state);
+    update_flux_lines(state);
+    update_far_fields(state);
+    update_ports(state);
     state.time_step += 1;
-}
\ No newline at end of file
+}
+
+/// Runs `update` on the fields and, with Bloch-periodic edges, on their imaginary part.
+fn for_each_part(state: &mut SimulationState, update: impl Fn(&mut SimulationState)) {
+    update(state);
+    if let Some(imag) = state.bloch.as_deref_mut() {
+        update(imag);
+    }
+}
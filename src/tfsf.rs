@@ -0,0 +1,192 @@
+use crate::checkpoint::{Reader, Writer};
+use crate::precision::Real;
+use crate::engine::compute_source_signal;
+use crate::materials::UpdateCoefficients;
+use crate::parameters::PlaneWaveDefinition;
+use std::f64::consts::PI;
+
+/// Cells between the hard source of the auxiliary grid and the first total-field node.
+const ORIGIN: usize = 5;
+/// Graded loss layer terminating the far end of the auxiliary grid.
+const LOSS_CELLS: usize = 40;
+const LOSS_MAX: f64 = 0.5;
+
+/// Wavenumber (radians per cell) of the 2D Yee grid at angular frequency `omega` (radians per step)
+/// for propagation at `angle` (radians), from its numerical dispersion relation.
+/// `None` from the grid's band edge on, where no real wavenumber solves it.
+pub fn numerical_wavenumber(omega: f64, angle: f64, courant: f64) -> Option<f64> {
+    let (c, s) = (angle.cos().abs(), angle.sin().abs());
+    let target = ((omega / 2.0).sin() / courant).powi(2);
+    let residual = |k: f64| (k * c / 2.0).sin().powi(2) + (k * s / 2.0).sin().powi(2) - target;
+    // The left side rises monotonically until the wave advances pi per cell along the faster axis.
+    let edge = PI / c.max(s);
+    if !(omega > 0.0 && omega < PI && residual(edge) > 0.0) {
+        return None;
+    }
+    let (mut low, mut high) = (0.0, edge);
+    for _ in 0..100 {
+        let mid = 0.5 * (low + high);
+        if residual(mid) < 0.0 { low = mid } else { high = mid }
+    }
+    Some(0.5 * (low + high))
+}
+
+/// Total-field / scattered-field plane-wave source for TMz.
+///
+/// The incident wave is computed on a 1D auxiliary grid along the propagation direction whose
+/// phase velocity matches the 2D grid at `angle` and the definition's `frequency`. Its values
+/// are projected onto the cells on both sides of the rectangle `x_min..=x_max`, `y_min..=y_max`
+/// (Ez nodes of the total-field region) to cancel the wave outside it.
+#[derive(Debug, Clone)]
+pub struct PlaneWaveState {
+    pub definition: PlaneWaveDefinition,
+    cos: f64,
+    sin: f64,
+    /// Corner of the total-field region the wave reaches first.
+    corner: (f64, f64),
+    /// Incident Ez at integer positions and H (along `(-sin, cos)`) at half-integer positions.
+    pub e_inc: Vec<f64>,
+    pub h_inc: Vec<f64>,
+    /// Update coefficients of the auxiliary grid, including the loss layer at its far end.
+    ce: Vec<(f64, f64)>,
+    ch: Vec<(f64, f64)>,
+}
+
+impl PlaneWaveState {
+    pub fn new(definition: &PlaneWaveDefinition, courant: f64) -> Result<Self, String> {
+        let angle = definition.angle.to_radians();
+        let (cos, sin) = (angle.cos(), angle.sin());
+        let corner = (
+            if cos >= 0.0 { definition.x_min } else { definition.x_max } as f64,
+            if sin >= 0.0 { definition.y_min } else { definition.y_max } as f64,
+        );
+        let span = (definition.x_max - definition.x_min) as f64 * cos.abs()
+            + (definition.y_max - definition.y_min) as f64 * sin.abs();
+        let len = ORIGIN + span.ceil() as usize + 4 + LOSS_CELLS;
+
+        // Slow the auxiliary grid down to the 2D numerical phase velocity at this angle.
+        let omega = 2.0 * PI * definition.frequency;
+        let k = numerical_wavenumber(omega, angle, courant)
+            .ok_or_else(|| format!("Plane-wave frequency {} is beyond the grid's band at {} degrees", definition.frequency, definition.angle))?;
+        let curl = (omega / 2.0).sin() / (k / 2.0).sin();
+
+        let loss_at = |pos: f64| {
+            let start = (len - LOSS_CELLS) as f64;
+            if pos > start { LOSS_MAX * ((pos - start) / LOSS_CELLS as f64).powi(3) } else { 0.0 }
+        };
+        let coefficients = |loss: f64| ((1.0 - loss) / (1.0 + loss), curl / (1.0 + loss));
+        let ce = (0..len).map(|m| coefficients(loss_at(m as f64))).collect();
+        let ch = (0..len).map(|m| coefficients(loss_at(m as f64 + 0.5))).collect();
+
+        let mut state = Self {
+            definition: definition.clone(),
+            cos,
+            sin,
+            corner,
+            e_inc: vec![0.0; len],
+            h_inc: vec![0.0; len],
+            ce,
+            ch,
+        };
+        state.reset();
+        Ok(state)
+    }
+
+    pub fn reset(&mut self) {
+        self.e_inc.fill(0.0);
+        self.h_inc.fill(0.0);
+        self.e_inc[0] = self.source_value(0);
+    }
+
+    pub fn save(&self, out: &mut Writer) {
+        out.f64s(&self.e_inc);
+        out.f64s(&self.h_inc);
+    }
+
+    pub fn restore(&mut self, input: &mut Reader) -> Result<(), String> {
+        input.f64s_into(&mut self.e_inc, "incident-wave values")?;
+        input.f64s_into(&mut self.h_inc, "incident-wave values")
+    }
+
+    fn source_value(&self, time_step: usize) -> f64 {
+        let d = &self.definition;
+        compute_source_signal(time_step as f64, d.frequency, d.amplitude, &d.signal_type)
+    }
+
+    /// Position on the auxiliary grid of the 2D point `(x, y)`.
+    fn position(&self, x: f64, y: f64) -> f64 {
+        ORIGIN as f64 + (x - self.corner.0) * self.cos + (y - self.corner.1) * self.sin
+    }
+
+    fn sample(values: &[f64], p: f64) -> f64 {
+        let i = p.floor() as usize;
+        let frac = p - i as f64;
+        values[i] + (values[i + 1] - values[i]) * frac
+    }
+
+    fn ez_at(&self, x: usize, y: usize) -> f64 {
+        Self::sample(&self.e_inc, self.position(x as f64, y as f64))
+    }
+
+    fn hx_at(&self, x: f64, y: f64) -> f64 {
+        -self.sin * Self::sample(&self.h_inc, self.position(x, y) - 0.5)
+    }
+
+    fn hy_at(&self, x: f64, y: f64) -> f64 {
+        self.cos * Self::sample(&self.h_inc, self.position(x, y) - 0.5)
+    }
+
+    /// Removes the incident Ez from the H updates that straddle the boundary.
+    /// Runs after the 2D H update, while the auxiliary grid still holds Ez at the same time level.
+    pub fn correct_h(&self, hx: &mut [Real], hy: &mut [Real], width: usize, materials: &[u8], coefficients: &[UpdateCoefficients]) {
+        let d = &self.definition;
+        let db = |idx: usize| coefficients[materials[idx] as usize].db;
+        for x in d.x_min..=d.x_max {
+            let below = (d.y_min - 1) * width + x;
+            hx[below] += (db(below) * self.ez_at(x, d.y_min)) as Real;
+            let above = d.y_max * width + x;
+            hx[above] -= (db(above) * self.ez_at(x, d.y_max)) as Real;
+        }
+        for y in d.y_min..=d.y_max {
+            let left = y * width + d.x_min - 1;
+            hy[left] -= (db(left) * self.ez_at(d.x_min, y)) as Real;
+            let right = y * width + d.x_max;
+            hy[right] += (db(right) * self.ez_at(d.x_max, y)) as Real;
+        }
+    }
+
+    /// Advances the incident magnetic field by half a step.
+    pub fn update_h(&mut self) {
+        let len = self.e_inc.len();
+        for m in 0..len - 1 {
+            let (a, b) = self.ch[m];
+            self.h_inc[m] = a * self.h_inc[m] + b * (self.e_inc[m + 1] - self.e_inc[m]);
+        }
+    }
+
+    /// Adds the incident H to the Ez updates that straddle the boundary.
+    pub fn correct_e(&self, ez: &mut [Real], width: usize, materials: &[u8], coefficients: &[UpdateCoefficients]) {
+        let d = &self.definition;
+        let cb = |idx: usize| coefficients[materials[idx] as usize].cb;
+        for y in d.y_min..=d.y_max {
+            let (yf, left, right) = (y as f64, y * width + d.x_min, y * width + d.x_max);
+            ez[left] -= (cb(left) * self.hy_at(d.x_min as f64 - 0.5, yf)) as Real;
+            ez[right] += (cb(right) * self.hy_at(d.x_max as f64 + 0.5, yf)) as Real;
+        }
+        for x in d.x_min..=d.x_max {
+            let (xf, bottom, top) = (x as f64, d.y_min * width + x, d.y_max * width + x);
+            ez[bottom] += (cb(bottom) * self.hx_at(xf, d.y_min as f64 - 0.5)) as Real;
+            ez[top] -= (cb(top) * self.hx_at(xf, d.y_max as f64 + 0.5)) as Real;
+        }
+    }
+
+    /// Advances the incident electric field to time step `time_step + 1` and drives its hard source.
+    pub fn update_e(&mut self, time_step: usize) {
+        let len = self.e_inc.len();
+        for m in 1..len - 1 {
+            let (a, b) = self.ce[m];
+            self.e_inc[m] = a * self.e_inc[m] + b * (self.h_inc[m] - self.h_inc[m - 1]);
+        }
+        self.e_inc[0] = self.source_value(time_step + 1);
+    }
+}
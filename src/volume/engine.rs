@@ -0,0 +1,116 @@
+use crate::engine::source_signal;
+use crate::volume::parameters::{Axis, FaceBoundary, VolumeBoundaryDefinition, VolumeParameters};
+use crate::volume::state::VolumeState;
+
+/// Updates Hx, Hy and Hz for one time step.
+pub fn update_h_fields(state: &mut VolumeState) {
+    let (w, h, d) = (state.width, state.height, state.depth);
+    let sy = w;
+    let sz = w * h;
+    for z in 0..d {
+        for y in 0..h {
+            for x in 0..w {
+                let idx = (z * h + y) * w + x;
+                let c = &state.coefficients[state.materials[idx] as usize];
+
+                // Hx(x, y+½, z+½) = curl of Ez along y and Ey along z
+                if y + 1 < h && z + 1 < d {
+                    let curl = (state.ez[idx + sy] - state.ez[idx]) - (state.ey[idx + sz] - state.ey[idx]);
+                    state.hx[idx] = c.da * state.hx[idx] - c.db * curl;
+                }
+                // Hy(x+½, y, z+½) = curl of Ex along z and Ez along x
+                if x + 1 < w && z + 1 < d {
+                    let curl = (state.ex[idx + sz] - state.ex[idx]) - (state.ez[idx + 1] - state.ez[idx]);
+                    state.hy[idx] = c.da * state.hy[idx] - c.db * curl;
+                }
+                // Hz(x+½, y+½, z) = curl of Ey along x and Ex along y
+                if x + 1 < w && y + 1 < h {
+                    let curl = (state.ey[idx + 1] - state.ey[idx]) - (state.ex[idx + sy] - state.ex[idx]);
+                    state.hz[idx] = c.da * state.hz[idx] - c.db * curl;
+                }
+            }
+        }
+    }
+}
+
+/// Updates Ex, Ey and Ez for one time step.
+/// Tangential components on the outer faces are never updated, which closes the volume with PEC.
+pub fn update_e_fields(state: &mut VolumeState) {
+    let (w, h, d) = (state.width, state.height, state.depth);
+    let sy = w;
+    let sz = w * h;
+    for z in 0..d {
+        for y in 0..h {
+            for x in 0..w {
+                let idx = (z * h + y) * w + x;
+                let c = &state.coefficients[state.materials[idx] as usize];
+                let (inner_x, inner_y, inner_z) = (x > 0 && x + 1 < w, y > 0 && y + 1 < h, z > 0 && z + 1 < d);
+
+                if x + 1 < w && inner_y && inner_z {
+                    let curl = (state.hz[idx] - state.hz[idx - sy]) - (state.hy[idx] - state.hy[idx - sz]);
+                    state.ex[idx] = c.ca * state.ex[idx] + c.cb * curl;
+                }
+                if y + 1 < h && inner_x && inner_z {
+                    let curl = (state.hx[idx] - state.hx[idx - sz]) - (state.hz[idx] - state.hz[idx - 1]);
+                    state.ey[idx] = c.ca * state.ey[idx] + c.cb * curl;
+                }
+                if z + 1 < d && inner_x && inner_y {
+                    let curl = (state.hy[idx] - state.hy[idx - 1]) - (state.hx[idx] - state.hx[idx - sy]);
+                    state.ez[idx] = c.ca * state.ez[idx] + c.cb * curl;
+                }
+            }
+        }
+    }
+}
+
+/// Adds the point-dipole source to the E component along its axis (soft source).
+pub fn apply_source(state: &mut VolumeState, params: &VolumeParameters) {
+    let source = &params.source;
+    let val = source_signal(source, state.time_step as f64);
+    let idx = state.index(source.x, source.y, params.source_z);
+    state.electric_field_mut(params.source_axis)[idx] += val;
+}
+
+/// Applies the damping layer to every face that requests one.
+/// Uses the same quadratic profile as the 2D damping boundary.
+pub fn apply_boundaries(state: &mut VolumeState, boundary: &VolumeBoundaryDefinition) {
+    let faces = [
+        (Axis::X, false, boundary.x_min), (Axis::X, true, boundary.x_max),
+        (Axis::Y, false, boundary.y_min), (Axis::Y, true, boundary.y_max),
+        (Axis::Z, false, boundary.z_min), (Axis::Z, true, boundary.z_max),
+    ];
+    for (axis, high, face) in faces {
+        if face == FaceBoundary::Damping {
+            damp_face(state, axis, high);
+        }
+    }
+}
+
+fn damp_face(state: &mut VolumeState, axis: Axis, high: bool) {
+    let (w, h, d) = (state.width, state.height, state.depth);
+    let len = match axis {
+        Axis::X => w,
+        Axis::Y => h,
+        Axis::Z => d,
+    };
+    let depth = 20.min(len / 2);
+    for i in 0..depth {
+        let factor = (i as f64 / depth as f64).powi(2); // Parabolic profile 0->1
+        let layer = if high { len - 1 - i } else { i };
+        let (xs, ys, zs) = match axis {
+            Axis::X => (layer..layer + 1, 0..h, 0..d),
+            Axis::Y => (0..w, layer..layer + 1, 0..d),
+            Axis::Z => (0..w, 0..h, layer..layer + 1),
+        };
+        for z in zs {
+            for y in ys.clone() {
+                for x in xs.clone() {
+                    let idx = (z * h + y) * w + x;
+                    for field in [&mut state.ex, &mut state.ey, &mut state.ez, &mut state.hx, &mut state.hy, &mut state.hz] {
+                        field[idx] *= factor;
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,7 @@
+//! Full 3D Yee-grid solver with all six field components.
+//! It runs alongside the 2D simulator and shares its sources, materials and renderer.
+
+pub mod parameters;
+pub mod state;
+pub mod engine;
+pub mod step;
@@ -0,0 +1,200 @@
+use serde::{Deserialize, Serialize};
+
+use crate::parameters::{
+    self, GridDefinition, MaterialDefinition, SourceDefinition, DEFAULT_COURANT_NUMBER,
+    FREE_SPACE_IMPEDANCE, MAX_COURANT_NUMBER_3D, SPEED_OF_LIGHT,
+};
+
+/// Configuration of a 3D simulation.
+/// `source.x` / `source.y` and `source_z` place a point dipole oriented along `source_axis`.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct VolumeParameters {
+    pub width: usize,
+    pub height: usize,
+    pub depth: usize,
+    pub source: SourceDefinition,
+    #[serde(default)]
+    pub source_z: usize,
+    #[serde(default)]
+    pub source_axis: Axis,
+    pub duration_steps: usize,
+    #[serde(default)]
+    pub boundary: VolumeBoundaryDefinition,
+    #[serde(default)]
+    pub grid: GridDefinition,
+    #[serde(default)]
+    pub materials: Vec<MaterialDefinition>,
+    #[serde(default)]
+    pub regions: Vec<VolumeRegion>,
+}
+
+impl Default for VolumeParameters {
+    fn default() -> Self {
+        Self {
+            width: 40,
+            height: 40,
+            depth: 40,
+            source: SourceDefinition { x: 20, y: 20, ..Default::default() },
+            source_z: 20,
+            source_axis: Axis::Z,
+            duration_steps: 1000,
+            boundary: VolumeBoundaryDefinition::default(),
+            grid: GridDefinition::default(),
+            materials: Vec::new(),
+            regions: Vec::new(),
+        }
+    }
+}
+
+/// A grid axis. Used for dipole orientation and as the normal of a rendered slice.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Default)]
+pub enum Axis {
+    X,
+    Y,
+    #[default]
+    Z,
+}
+
+impl Axis {
+    /// Parses `"x"`, `"y"` or `"z"` (case-insensitive).
+    pub fn parse(name: &str) -> Result<Axis, String> {
+        match name.to_ascii_lowercase().as_str() {
+            "x" => Ok(Axis::X),
+            "y" => Ok(Axis::Y),
+            "z" => Ok(Axis::Z),
+            _ => Err(format!("Unknown axis \"{}\" (expected x, y or z)", name)),
+        }
+    }
+}
+
+/// One of the six field components of the 3D grid. The 2D grid solves three of them, depending on the polarisation.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub enum FieldComponent {
+    Ex,
+    Ey,
+    Ez,
+    Hx,
+    Hy,
+    Hz,
+}
+
+impl FieldComponent {
+    /// Parses a component name such as `"Ez"` (case-insensitive).
+    pub fn parse(name: &str) -> Result<FieldComponent, String> {
+        match name.to_ascii_lowercase().as_str() {
+            "ex" => Ok(FieldComponent::Ex),
+            "ey" => Ok(FieldComponent::Ey),
+            "ez" => Ok(FieldComponent::Ez),
+            "hx" => Ok(FieldComponent::Hx),
+            "hy" => Ok(FieldComponent::Hy),
+            "hz" => Ok(FieldComponent::Hz),
+            _ => Err(format!("Unknown field component \"{}\"", name)),
+        }
+    }
+}
+
+/// Treatment of one face of the volume.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub enum FaceBoundary {
+    /// Tangential E stays zero on the outer nodes (a closed metal wall).
+    Pec,
+    /// Multiplicative damping layer, as the legacy 2D boundary.
+    Damping,
+}
+
+/// Boundary treatment for each face of the volume.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct VolumeBoundaryDefinition {
+    pub x_min: FaceBoundary,
+    pub x_max: FaceBoundary,
+    pub y_min: FaceBoundary,
+    pub y_max: FaceBoundary,
+    pub z_min: FaceBoundary,
+    pub z_max: FaceBoundary,
+}
+
+impl VolumeBoundaryDefinition {
+    /// Uses the same boundary on all six faces.
+    pub fn uniform(face: FaceBoundary) -> Self {
+        Self { x_min: face, x_max: face, y_min: face, y_max: face, z_min: face, z_max: face }
+    }
+}
+
+impl Default for VolumeBoundaryDefinition {
+    fn default() -> Self {
+        Self::uniform(FaceBoundary::Damping)
+    }
+}
+
+/// Fills an SVG path extruded over the layers `z_start..z_end` with a named material.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct VolumeRegion {
+    pub material: String,
+    pub path: String,
+    pub z_start: usize,
+    pub z_end: usize,
+}
+
+impl VolumeParameters {
+    /// Courant number `c * dt / dx` of the simulation.
+    pub fn courant_number(&self) -> f64 {
+        self.grid.courant.unwrap_or(DEFAULT_COURANT_NUMBER)
+    }
+
+    /// Time step in seconds, if the grid has a physical cell size.
+    pub fn time_step_seconds(&self) -> Option<f64> {
+        self.grid.cell_size.map(|dx| self.courant_number() * dx / SPEED_OF_LIGHT)
+    }
+
+    /// Returns a copy in normalised units (see `SimulationParameters::resolve_units`).
+    pub fn resolve_units(&self) -> VolumeParameters {
+        let mut resolved = self.clone();
+        if let (Some(dx), Some(dt)) = (self.grid.cell_size, self.time_step_seconds()) {
+            resolved.source.frequency *= dt;
+            resolved.source.start_time /= dt;
+            resolved.source.stop_time = self.source.stop_time.map(|t| t / dt);
+            resolved.source.ramp_time = self.source.ramp_time.map(|t| t / dt);
+            resolved.source.signal_type = self.source.signal_type.normalized(dt);
+            for material in &mut resolved.materials {
+                material.conductivity *= FREE_SPACE_IMPEDANCE * dx;
+            }
+        }
+        resolved.grid.cell_size = None;
+        resolved
+    }
+}
+
+/// Validates the dimensions, source placement, stability limit and materials of a 3D simulation.
+pub fn validate_volume_parameters(params: &VolumeParameters) -> Result<(), String> {
+    if params.width < 2 || params.height < 2 || params.depth < 2 {
+        return Err("Width, height and depth must be at least 2".to_string());
+    }
+    parameters::validate_source(&params.source, params.width, params.height)?;
+    if params.source_z >= params.depth {
+        return Err(format!("Source layer {} must be within 0..{}", params.source_z, params.depth - 1));
+    }
+    if let Some(dx) = params.grid.cell_size {
+        if dx <= 0.0 || !dx.is_finite() {
+            return Err("Cell size must be a positive number of metres".to_string());
+        }
+    }
+    let courant = params.courant_number();
+    if !(courant > 0.0 && courant <= MAX_COURANT_NUMBER_3D) {
+        return Err(format!("Courant number {} violates the 3D CFL limit (0 < S <= {:.6})",
+                           courant, MAX_COURANT_NUMBER_3D));
+    }
+    parameters::validate_material_definitions(&params.materials, params.time_step_seconds())?;
+    if let Some(material) = params.materials.iter().find(|m| !m.poles.is_empty()) {
+        return Err(format!("Dispersive material \"{}\" is not supported in 3D", material.name));
+    }
+    for region in &params.regions {
+        parameters::validate_material_reference(&params.materials, &region.material)?;
+        if region.z_start > region.z_end || region.z_end > params.depth {
+            return Err(format!("Region layers {}..{} must lie within 0..{}", region.z_start, region.z_end, params.depth));
+        }
+    }
+    if params.duration_steps == 0 {
+        return Err("Duration steps must be greater than 0".to_string());
+    }
+    Ok(())
+}
@@ -0,0 +1,141 @@
+use crate::materials::{self, UpdateCoefficients, VACUUM};
+use crate::parameters::{MaterialDefinition, DEFAULT_COURANT_NUMBER};
+use crate::rasterizer;
+use crate::volume::parameters::{Axis, FieldComponent, VolumeParameters};
+
+/// State of a 3D simulation. Every array is laid out as `(z * height + y) * width + x`.
+///
+/// Components follow the Yee cell of voxel `(x, y, z)`: Ex at `(x+½, y, z)`, Ey at `(x, y+½, z)`,
+/// Ez at `(x, y, z+½)`, Hx at `(x, y+½, z+½)`, Hy at `(x+½, y, z+½)` and Hz at `(x+½, y+½, z)`.
+pub struct VolumeState {
+    pub width: usize,
+    pub height: usize,
+    pub depth: usize,
+
+    pub ex: Vec<f64>,
+    pub ey: Vec<f64>,
+    pub ez: Vec<f64>,
+    pub hx: Vec<f64>,
+    pub hy: Vec<f64>,
+    pub hz: Vec<f64>,
+
+    // Material id of every voxel, with the same table layout as the 2D state.
+    pub materials: Vec<u8>,
+    pub material_table: Vec<MaterialDefinition>,
+    pub coefficients: Vec<UpdateCoefficients>,
+    pub courant: f64,
+
+    pub time_step: usize,
+}
+
+impl VolumeState {
+    pub fn new(width: usize, height: usize, depth: usize) -> Self {
+        let size = width * height * depth;
+        let material_table = materials::build_material_table(&[]);
+        Self {
+            width,
+            height,
+            depth,
+            ex: vec![0.0; size],
+            ey: vec![0.0; size],
+            ez: vec![0.0; size],
+            hx: vec![0.0; size],
+            hy: vec![0.0; size],
+            hz: vec![0.0; size],
+            materials: vec![VACUUM; size],
+            coefficients: materials::compute_coefficients(&material_table, DEFAULT_COURANT_NUMBER),
+            material_table,
+            courant: DEFAULT_COURANT_NUMBER,
+            time_step: 0,
+        }
+    }
+
+    /// Builds the state described by normalised parameters (see `VolumeParameters::resolve_units`).
+    pub fn from_parameters(params: &VolumeParameters) -> Result<Self, String> {
+        let mut state = VolumeState::new(params.width, params.height, params.depth);
+        state.courant = params.courant_number();
+        state.material_table = materials::build_material_table(&params.materials);
+        state.coefficients = materials::compute_coefficients(&state.material_table, state.courant);
+        state.rasterize_geometry(params)?;
+        Ok(state)
+    }
+
+    /// Resets every voxel to free space, then fills each region's path on its layers.
+    pub fn rasterize_geometry(&mut self, params: &VolumeParameters) -> Result<(), String> {
+        self.materials.fill(VACUUM);
+        let layer = self.width * self.height;
+        for region in &params.regions {
+            let id = materials::find_material(&self.material_table, &region.material)
+                .ok_or_else(|| format!("Unknown material \"{}\"", region.material))?;
+            let mut mask = vec![false; layer];
+            rasterizer::try_rasterize_path_with(self.width, self.height, &region.path, &mut mask, true)?;
+            for z in region.z_start..region.z_end {
+                let cells = &mut self.materials[z * layer..(z + 1) * layer];
+                for (cell, _) in cells.iter_mut().zip(&mask).filter(|(_, &inside)| inside) {
+                    *cell = id;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    pub fn index(&self, x: usize, y: usize, z: usize) -> usize {
+        (z * self.height + y) * self.width + x
+    }
+
+    /// Dimensions `(columns, rows)` of a slice normal to `normal`.
+    pub fn slice_dimensions(&self, normal: Axis) -> (usize, usize) {
+        match normal {
+            Axis::X => (self.height, self.depth),
+            Axis::Y => (self.width, self.depth),
+            Axis::Z => (self.width, self.height),
+        }
+    }
+
+    /// Copies a field and the material ids on the plane normal to `normal` at `position`.
+    /// Rows run along the second axis of `slice_dimensions`.
+    pub fn slice(&self, field: &[f64], normal: Axis, position: usize) -> (Vec<f64>, Vec<u8>) {
+        let (columns, rows) = self.slice_dimensions(normal);
+        let mut values = Vec::with_capacity(columns * rows);
+        let mut materials = Vec::with_capacity(columns * rows);
+        for row in 0..rows {
+            for column in 0..columns {
+                let idx = match normal {
+                    Axis::X => self.index(position, column, row),
+                    Axis::Y => self.index(column, position, row),
+                    Axis::Z => self.index(column, row, position),
+                };
+                values.push(field[idx]);
+                materials.push(self.materials[idx]);
+            }
+        }
+        (values, materials)
+    }
+
+    pub fn field(&self, component: FieldComponent) -> &[f64] {
+        match component {
+            FieldComponent::Ex => &self.ex,
+            FieldComponent::Ey => &self.ey,
+            FieldComponent::Ez => &self.ez,
+            FieldComponent::Hx => &self.hx,
+            FieldComponent::Hy => &self.hy,
+            FieldComponent::Hz => &self.hz,
+        }
+    }
+
+    /// The electric field component along `axis`, driven by dipole sources.
+    pub fn electric_field_mut(&mut self, axis: Axis) -> &mut [f64] {
+        match axis {
+            Axis::X => &mut self.ex,
+            Axis::Y => &mut self.ey,
+            Axis::Z => &mut self.ez,
+        }
+    }
+
+    pub fn reset(&mut self) {
+        for field in [&mut self.ex, &mut self.ey, &mut self.ez, &mut self.hx, &mut self.hy, &mut self.hz] {
+            field.fill(0.0);
+        }
+        self.time_step = 0;
+    }
+}
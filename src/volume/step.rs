@@ -0,0 +1,21 @@
+use crate::volume::engine::{apply_boundaries, apply_source, update_e_fields, update_h_fields};
+use crate::volume::parameters::VolumeParameters;
+use crate::volume::state::VolumeState;
+
+/// Executes a single 3D simulation step, in the same order as the 2D `step::step`.
+pub fn step(params: &VolumeParameters, state: &mut VolumeState) {
+    // 1. Update Magnetic Fields
+    update_h_fields(state);
+
+    // 2. Update Electric Fields
+    update_e_fields(state);
+
+    // 3. Apply Source
+    apply_source(state, params);
+
+    // 4. Apply Boundary Conditions
+    apply_boundaries(state, &params.boundary);
+
+    // 5. Advance Time
+    state.time_step += 1;
+}
@@ -0,0 +1,45 @@
+//! Reflection benchmark shared by the absorbing-boundary tests.
+
+use fdtd_wasm::engine::apply_forced_source;
+use fdtd_wasm::parameters::{BoundaryDefinition, SimulationParameters};
+use fdtd_wasm::precision::widen;
+use fdtd_wasm::state::SimulationState;
+use fdtd_wasm::step::step;
+
+/// Differentiated Gaussian: no DC content, so nothing lingers near the source.
+pub fn pulse(t: f64) -> f64 {
+    let t0 = 40.0;
+    let tau = 10.0;
+    let arg = (t - t0) / tau;
+    -2.0 * arg * (-arg * arg).exp()
+}
+
+/// Fires a pulse from the source cell and records Ez at the probe cell for `steps` steps.
+pub fn record(width: usize, height: usize, (sx, sy): (usize, usize), (px, py): (usize, usize),
+          boundary: BoundaryDefinition, steps: usize) -> Vec<f64> {
+    let params = SimulationParameters { width, height, boundary: boundary.clone(), ..Default::default() };
+    let mut state = SimulationState::new(width, height);
+    state.configure_boundaries(&boundary);
+
+    let mut trace = Vec::with_capacity(steps);
+    for _ in 0..steps {
+        let t = state.time_step as f64;
+        step(&params, &mut state, Some(0.0));
+        apply_forced_source(&mut state, sx, sy, pulse(t));
+        trace.push(widen(state.ez[py * width + px]));
+    }
+    trace
+}
+
+/// Reflected energy at a probe next to the left wall, in dB relative to the incident pulse.
+pub fn reflection_db(boundary: BoundaryDefinition) -> f64 {
+    let steps = 200;
+    // Small grid: the probe sits 10 cells from the inner edge of a 20-cell layer.
+    let small = record(120, 120, (40, 60), (30, 60), boundary.clone(), steps);
+    // Reference grid: same source/probe offsets but walls far enough not to be seen in time.
+    let reference = record(220, 220, (110, 110), (100, 110), boundary, steps);
+
+    let peak = reference.iter().fold(0.0f64, |a, v| a.max(v.abs()));
+    let error = small.iter().zip(&reference).fold(0.0f64, |a, (s, r)| a.max((s - r).abs()));
+    20.0 * (error / peak).log10()
+}
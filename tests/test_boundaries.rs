@@ -0,0 +1,151 @@
+use fdtd_wasm::parameters::{
+    validate_parameters, BoundaryDefinition, CpmlDefinition, EdgeBoundary, Polarization, SignalType,
+    SimulationParameters, SourceDefinition,
+};
+use fdtd_wasm::precision::{widen, Real};
+use fdtd_wasm::state::SimulationState;
+use fdtd_wasm::step::step;
+
+fn pulse(x: usize, y: usize, amplitude: f64) -> SourceDefinition {
+    SourceDefinition { x, y, amplitude, frequency: 0.05, signal_type: SignalType::PulseSine, ..Default::default() }
+}
+
+fn run(params: &SimulationParameters, steps: usize) -> SimulationState {
+    let mut state = SimulationState::from_parameters(params).unwrap();
+    for _ in 0..steps {
+        step(params, &mut state, None);
+    }
+    state
+}
+
+/// Whether fields from two different grids agree to `tolerance`. Single-precision fields round
+/// differently on each grid, so with the `f32` feature they only agree to 1e-6.
+fn close(a: Real, b: Real, tolerance: f64) -> bool {
+    let tolerance = if cfg!(feature = "f32") { 1e-6 } else { tolerance };
+    (widen(a) - widen(b)).abs() <= tolerance
+}
+
+/// Left/right edges as given, absorbing top and bottom.
+fn sides(left: EdgeBoundary, right: EdgeBoundary) -> BoundaryDefinition {
+    let cpml = EdgeBoundary::Cpml(CpmlDefinition { thickness: 10, ..Default::default() });
+    BoundaryDefinition { left, right, top: cpml.clone(), bottom: cpml }
+}
+
+#[test]
+fn test_mirror_walls_match_symmetric_grid() {
+    // A magnetic wall in TMz (an electric wall in TEz) is the symmetry plane of a mirrored source pair.
+    for (polarization, wall) in [(Polarization::TMz, EdgeBoundary::Pmc), (Polarization::TEz, EdgeBoundary::Pec)] {
+        let held = match polarization {
+            Polarization::TMz => EdgeBoundary::Pec,
+            Polarization::TEz => EdgeBoundary::Pmc,
+        };
+        let half = SimulationParameters {
+            width: 30, height: 40,
+            sources: vec![pulse(8, 20, 1.0)],
+            boundary: sides(wall, held.clone()),
+            polarization,
+            ..Default::default()
+        };
+        let full = SimulationParameters {
+            width: 59,
+            sources: vec![pulse(29 + 8, 20, 1.0), pulse(29 - 8, 20, 1.0)],
+            boundary: sides(held.clone(), held),
+            ..half.clone()
+        };
+        let (half_state, full_state) = (run(&half, 150), run(&full, 150));
+
+        assert!(half_state.primary_field()[20 * 30].abs() > 1e-3, "the wave should reach the wall");
+        for y in 0..40 {
+            for x in 0..30 {
+                let (half, full) = (half_state.primary_field()[y * 30 + x], full_state.primary_field()[y * 59 + 29 + x]);
+                assert!(close(half, full, 0.0), "({}, {}): {} vs {}", x, y, half, full);
+            }
+        }
+    }
+}
+
+#[test]
+fn test_periodic_edges_are_translation_invariant() {
+    let with = |x| SimulationParameters {
+        width: 41, height: 40,
+        sources: vec![pulse(x, 20, 1.0)],
+        boundary: sides(EdgeBoundary::Periodic, EdgeBoundary::Periodic),
+        ..Default::default()
+    };
+    // The period is 40 cells; column 40 repeats column 0.
+    let (a, b) = (run(&with(5), 200), run(&with(25), 200));
+    for y in 0..40 {
+        assert_eq!(a.ez[y * 41 + 40], a.ez[y * 41]);
+        for x in 0..40 {
+            assert!(close(a.ez[y * 41 + x], b.ez[y * 41 + (x + 20) % 40], 1e-12));
+        }
+    }
+    assert!(a.ez[20 * 41 + 25].abs() > 1e-3, "the wave should wrap around");
+}
+
+#[test]
+fn test_bloch_phase_matches_periodic_supercell() {
+    // With a phase of 2 pi / 3 the real field equals a periodic supercell of three periods
+    // whose copies of the source are weighted by cos(2 pi k / 3).
+    let phase = 2.0 * std::f64::consts::PI / 3.0;
+    let bloch = SimulationParameters {
+        width: 21, height: 40,
+        sources: vec![pulse(8, 20, 1.0)],
+        boundary: sides(EdgeBoundary::BlochPeriodic { phase }, EdgeBoundary::BlochPeriodic { phase }),
+        ..Default::default()
+    };
+    let supercell = SimulationParameters {
+        width: 61,
+        sources: (0..3).map(|k| pulse(8 + 20 * k, 20, (phase * k as f64).cos())).collect(),
+        boundary: sides(EdgeBoundary::Periodic, EdgeBoundary::Periodic),
+        ..bloch.clone()
+    };
+    let (cell, reference) = (run(&bloch, 250), run(&supercell, 250));
+
+    assert!(cell.bloch.is_some());
+    for y in 0..40 {
+        for x in 0..21 {
+            let (value, expected) = (cell.ez[y * 21 + x], reference.ez[y * 61 + x]);
+            assert!(close(value, expected, 1e-12), "({}, {}): {} vs {}", x, y, value, expected);
+        }
+    }
+    assert!(cell.ez[20 * 21].abs() > 1e-3);
+}
+
+#[test]
+fn test_periodic_edges_must_be_paired() {
+    let with = |left, right| SimulationParameters { boundary: sides(left, right), ..Default::default() };
+    assert!(validate_parameters(&with(EdgeBoundary::Periodic, EdgeBoundary::Periodic)).is_ok());
+    assert!(validate_parameters(&with(EdgeBoundary::Pec, EdgeBoundary::Pmc)).is_ok());
+    assert!(validate_parameters(&with(EdgeBoundary::Periodic, EdgeBoundary::Pec)).is_err());
+    assert!(validate_parameters(&with(
+        EdgeBoundary::BlochPeriodic { phase: 1.0 },
+        EdgeBoundary::BlochPeriodic { phase: 2.0 },
+    )).is_err());
+    assert!(validate_parameters(&with(
+        EdgeBoundary::BlochPeriodic { phase: f64::NAN },
+        EdgeBoundary::BlochPeriodic { phase: f64::NAN },
+    )).is_err());
+}
+
+#[test]
+fn test_cpml_parameters_must_be_finite() {
+    let with = |cpml: CpmlDefinition| SimulationParameters {
+        boundary: BoundaryDefinition::uniform(EdgeBoundary::Cpml(cpml)),
+        ..Default::default()
+    };
+    assert!(validate_parameters(&with(CpmlDefinition { sigma_max: Some(2.0), ..Default::default() })).is_ok());
+    for bad in [
+        CpmlDefinition { grading_order: f64::NAN, ..Default::default() },
+        CpmlDefinition { grading_order: f64::INFINITY, ..Default::default() },
+        CpmlDefinition { sigma_max: Some(-1.0), ..Default::default() },
+        CpmlDefinition { sigma_max: Some(f64::NAN), ..Default::default() },
+        CpmlDefinition { kappa_max: f64::NAN, ..Default::default() },
+        CpmlDefinition { kappa_max: 0.5, ..Default::default() },
+        CpmlDefinition { alpha_max: f64::NAN, ..Default::default() },
+        CpmlDefinition { alpha_grading_order: -1.0, ..Default::default() },
+        CpmlDefinition { alpha_grading_order: f64::NAN, ..Default::default() },
+    ] {
+        assert!(validate_parameters(&with(bad.clone())).is_err(), "{:?}", bad);
+    }
+}
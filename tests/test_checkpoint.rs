@@ -0,0 +1,183 @@
+use fdtd_wasm::checkpoint::{Reader, Writer, MAGIC};
+use fdtd_wasm::parameters::{
+    BoundaryDefinition, CpmlDefinition, DftMonitorDefinition, DispersivePole, EdgeBoundary, FarFieldDefinition,
+    FluxLineDefinition, LumpedElement, LumpedElementDefinition, MaterialDefinition, MaterialRegion, MurOrder, PlaneWaveDefinition, ProbeDefinition, ProbeShape,
+    SignalType, SimulationParameters, SourceDefinition,
+};
+use fdtd_wasm::precision::widen;
+use fdtd_wasm::state::SimulationState;
+use fdtd_wasm::volume::parameters::FieldComponent;
+use fdtd_wasm::FdtdSimulator;
+
+fn metal() -> MaterialDefinition {
+    MaterialDefinition {
+        name: "metal".to_string(),
+        poles: vec![DispersivePole::Drude { plasma_frequency: 0.015, collision_rate: 0.01 }],
+        ..Default::default()
+    }
+}
+
+/// Mur edges, a plane wave, a dispersive block, lumped elements, every kind of monitor and a comms link.
+fn link() -> SimulationParameters {
+    let mut params = SimulationParameters {
+        width: 80, height: 60,
+        sources: vec![SourceDefinition { x: 15, y: 30, amplitude: 10.0, ..Default::default() }],
+        boundary: BoundaryDefinition::uniform(EdgeBoundary::Mur(MurOrder::Second)),
+        plane_wave: Some(PlaneWaveDefinition {
+            x_min: 10, y_min: 10, x_max: 70, y_max: 50, angle: 30.0, amplitude: 0.2, frequency: 0.03,
+            signal_type: SignalType::PulseSine,
+        }),
+        materials: vec![metal()],
+        regions: vec![MaterialRegion { material: "metal".to_string(), path: "M 50 5 L 55 5 L 55 15 L 50 15 Z".to_string() }],
+        probes: vec![ProbeDefinition { name: "rx".to_string(), component: FieldComponent::Ez, shape: ProbeShape::Point { x: 35, y: 30 }, capacity: None }],
+        dft_monitors: vec![DftMonitorDefinition {
+            name: "map".to_string(), component: FieldComponent::Ez, x_min: 20, y_min: 20, x_max: 40, y_max: 40,
+            frequencies: vec![0.04, 0.06], start_time: 0.0,
+        }],
+        flux_lines: vec![FluxLineDefinition { name: "line".to_string(), x0: 30, y0: 20, x1: 30, y1: 40, frequencies: vec![0.05] }],
+        far_fields: vec![FarFieldDefinition {
+            name: "far".to_string(), x_min: 8, y_min: 8, x_max: 72, y_max: 52, frequencies: vec![0.05], angles: Some(36),
+        }],
+        lumped_elements: vec![
+            LumpedElementDefinition { name: "coil".to_string(), x: 25, y: 45, element: LumpedElement::Inductor { inductance: 2.0 } },
+            LumpedElementDefinition { name: "load".to_string(), x: 45, y: 45, element: LumpedElement::Resistor { resistance: 0.5 } },
+        ],
+        duration_steps: 3000,
+        ..Default::default()
+    };
+    params.comms.symbol_duration = 40;
+    params.comms.deviation = 0.025;
+    params.comms.receiver_probe = Some("rx".to_string());
+    params
+}
+
+/// CPML on the sides and Bloch-periodic top and bottom, so the imaginary fields are saved too.
+fn periodic() -> SimulationParameters {
+    let cpml = EdgeBoundary::Cpml(CpmlDefinition::default());
+    let bloch = EdgeBoundary::BlochPeriodic { phase: 1.0 };
+    SimulationParameters {
+        width: 60, height: 40,
+        sources: vec![SourceDefinition { x: 20, y: 20, signal_type: SignalType::PulseSine, ..Default::default() }],
+        boundary: BoundaryDefinition { left: cpml.clone(), right: cpml, top: bloch.clone(), bottom: bloch },
+        materials: vec![metal()],
+        regions: vec![MaterialRegion { material: "metal".to_string(), path: "M 35 15 L 40 15 L 40 25 L 35 25 Z".to_string() }],
+        ..Default::default()
+    }
+}
+
+fn assert_identical(a: &FdtdSimulator, b: &FdtdSimulator) {
+    let (a, b) = (a.state(), b.state());
+    assert_eq!(a.time_step, b.time_step);
+    assert_eq!(a.materials, b.materials);
+    for component in [FieldComponent::Ez, FieldComponent::Hx, FieldComponent::Hy] {
+        let bits = |state: &fdtd_wasm::state::SimulationState| -> Vec<u64> {
+            state.field(component).iter().map(|&v| widen(v).to_bits()).collect()
+        };
+        assert_eq!(bits(a), bits(b), "{:?}", component);
+    }
+    if let (Some(a), Some(b)) = (&a.bloch, &b.bloch) {
+        assert_eq!(a.ez, b.ez);
+    }
+}
+
+#[test]
+fn test_resumed_run_is_identical_to_uninterrupted_run() {
+    let mut reference = FdtdSimulator::from_parameters(link()).unwrap();
+    reference.send_message("Hi");
+    reference.add_obstacle("M 60 40 L 62 40 L 62 44 L 60 44 Z").unwrap();
+    for _ in 0..900 {
+        reference.step();
+    }
+    let checkpoint = reference.save_checkpoint();
+    assert_eq!(&checkpoint[..MAGIC.len()], MAGIC);
+
+    // A fresh simulator has neither the message nor the obstacle until the checkpoint is loaded.
+    let mut resumed = FdtdSimulator::from_parameters(link()).unwrap();
+    resumed.restore_checkpoint(&checkpoint).unwrap();
+    assert_identical(&reference, &resumed);
+
+    for _ in 0..1300 {
+        reference.step();
+        resumed.step();
+    }
+    assert_identical(&reference, &resumed);
+    assert_eq!(reference.get_received_text(), "Hi");
+    assert_eq!(resumed.get_received_text(), "Hi");
+    assert_eq!(resumed.get_received_bits(), reference.get_received_bits());
+    assert_eq!(resumed.probe_samples("rx").unwrap(), reference.probe_samples("rx").unwrap());
+    assert_eq!(resumed.dft_monitor("map", 1).unwrap().magnitude(1), reference.dft_monitor("map", 1).unwrap().magnitude(1));
+    let (a, b) = (resumed.flux_line("line").unwrap(), reference.flux_line("line").unwrap());
+    assert_eq!((a.energy(), a.spectrum()), (b.energy(), b.spectrum()));
+    let (a, b) = (resumed.far_field_pattern("far", 0).unwrap(), reference.far_field_pattern("far", 0).unwrap());
+    assert_eq!(a.directivity, b.directivity);
+    assert_eq!(resumed.save_checkpoint(), reference.save_checkpoint());
+}
+
+#[test]
+fn test_checkpoint_rewinds_a_simulation() {
+    let mut simulator = FdtdSimulator::from_parameters(periodic()).unwrap();
+    for _ in 0..150 {
+        simulator.step();
+    }
+    let checkpoint = simulator.save_checkpoint();
+    for _ in 0..200 {
+        simulator.step();
+    }
+    let first = simulator.save_checkpoint();
+
+    simulator.restore_checkpoint(&checkpoint).unwrap();
+    assert_eq!(simulator.get_current_step(), 150);
+    for _ in 0..200 {
+        simulator.step();
+    }
+    assert!(simulator.state().bloch.is_some());
+    assert_eq!(simulator.save_checkpoint(), first);
+}
+
+#[test]
+fn test_rejected_checkpoint_leaves_simulation_unchanged() {
+    let mut simulator = FdtdSimulator::from_parameters(periodic()).unwrap();
+    for _ in 0..50 {
+        simulator.step();
+    }
+    let checkpoint = simulator.save_checkpoint();
+    let before = checkpoint.clone();
+
+    let mut bad_magic = checkpoint.clone();
+    bad_magic[0] = b'X';
+    let mut bad_version = checkpoint.clone();
+    bad_version[MAGIC.len()] += 1;
+    let mut bad_precision = checkpoint.clone();
+    bad_precision[MAGIC.len() + 4] = 2;
+    let mut trailing = checkpoint.clone();
+    trailing.push(0);
+    let truncated = &checkpoint[..checkpoint.len() - 1];
+    for bytes in [&bad_magic[..], &bad_version, &bad_precision, &trailing, truncated, &[]] {
+        assert!(simulator.restore_checkpoint(bytes).is_err());
+    }
+    assert!(Reader::new(&bad_version).err().unwrap().contains("version"));
+
+    // A checkpoint of another configuration does not fit.
+    let other = FdtdSimulator::from_parameters(link()).unwrap().save_checkpoint();
+    assert!(simulator.restore_checkpoint(&other).is_err());
+    let smaller = FdtdSimulator::from_parameters(SimulationParameters { width: 50, ..periodic() }).unwrap().save_checkpoint();
+    assert!(simulator.restore_checkpoint(&smaller).unwrap_err().contains("grid"));
+
+    assert_eq!(simulator.save_checkpoint(), before);
+    assert_eq!(simulator.get_current_step(), 50);
+}
+
+#[test]
+fn test_checkpoint_with_unknown_material_is_rejected() {
+    let state = SimulationState::from_parameters(&periodic()).unwrap();
+    let mut out = Writer::new();
+    state.save(&mut out);
+    let mut bytes = out.finish();
+    // The first material id follows the header, the grid size, the polarization, the step and the id count.
+    let first_material = MAGIC.len() + 5 + 8 + 8 + 1 + 8 + 8;
+    bytes[first_material] = state.material_table.len() as u8;
+
+    let mut restored = SimulationState::from_parameters(&periodic()).unwrap();
+    let error = restored.restore(&mut Reader::new(&bytes).unwrap()).unwrap_err();
+    assert!(error.contains("material"), "{}", error);
+}
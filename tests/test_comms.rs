@@ -69,7 +69,7 @@ fn test_demodulator_perfect_signal_fsk() {
     // Simulate bit '1' (F1)
     for t_step in 0..samples_per_sym {
         let val = (2.0 * std::f64::consts::PI * f1 * (t_step as f64 + samples_per_sym as f64)).sin();
-        let bit = dem.process_sample(val, (t_step as f64 + samples_per_sym as f64));
+        let bit = dem.process_sample(val, t_step as f64 + samples_per_sym as f64);
         if t_step == samples_per_sym - 1 {
             assert_eq!(bit, Some(1));
         } else {
@@ -99,7 +99,7 @@ fn test_demodulator_perfect_signal_ask() {
     // Simulate bit '0' (Carrier OFF / Silence)
     for t_step in 0..samples_per_sym {
         let val = 0.0; // No signal
-        let bit = dem.process_sample(val, (t_step as f64 + samples_per_sym as f64));
+        let bit = dem.process_sample(val, t_step as f64 + samples_per_sym as f64);
         if t_step == samples_per_sym - 1 {
             assert_eq!(bit, Some(0));
         } else {
@@ -0,0 +1,48 @@
+mod common;
+
+use common::reflection_db;
+use fdtd_wasm::cpml::{coefficients, CpmlState};
+use fdtd_wasm::parameters::{BoundaryDefinition, CpmlDefinition, EdgeBoundary};
+
+#[test]
+fn test_cpml_reflection_below_threshold() {
+    let db = reflection_db(BoundaryDefinition::default());
+    assert!(db < -60.0, "CPML reflection too high: {:.1} dB", db);
+}
+
+#[test]
+fn test_cpml_outperforms_damping_layer() {
+    let cpml_db = reflection_db(BoundaryDefinition::default());
+    let damping_db = reflection_db(BoundaryDefinition::uniform(EdgeBoundary::Damping));
+    assert!(cpml_db < damping_db - 10.0, "CPML {:.1} dB vs damping {:.1} dB", cpml_db, damping_db);
+}
+
+#[test]
+fn test_cpml_coefficients_vanish_at_interface() {
+    let def = CpmlDefinition::default();
+    let (b, c, kappa) = coefficients(0.0, &def, 0.5);
+    assert_eq!(b, 1.0);
+    assert_eq!(c, 0.0);
+    assert_eq!(kappa, 1.0);
+
+    let (b_wall, c_wall, _) = coefficients(1.0, &def, 0.5);
+    assert!(b_wall < 1.0);
+    assert!(c_wall < 0.0);
+}
+
+#[test]
+fn test_cpml_state_only_covers_requested_edges() {
+    let boundary = BoundaryDefinition {
+        left: EdgeBoundary::Cpml(CpmlDefinition { thickness: 8, ..Default::default() }),
+        right: EdgeBoundary::Damping,
+        top: EdgeBoundary::Damping,
+        bottom: EdgeBoundary::Damping,
+    };
+    let cpml = CpmlState::new(50, 40, &boundary, 0.5).unwrap();
+    assert_eq!(cpml.psi_ez_x.len(), cpml.ez_x.len() * 40);
+    assert!(cpml.ez_x.positions.iter().all(|&x| x < 8));
+    assert!(cpml.ez_y.is_empty());
+    assert!(cpml.hx_y.is_empty());
+
+    assert!(CpmlState::new(50, 40, &BoundaryDefinition::uniform(EdgeBoundary::Damping), 0.5).is_none());
+}
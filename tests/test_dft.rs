@@ -0,0 +1,101 @@
+use fdtd_wasm::dft::DftMonitor;
+use fdtd_wasm::parameters::{validate_parameters, DftMonitorDefinition, GridDefinition, SimulationParameters, SourceDefinition};
+use fdtd_wasm::renderer::{render_dft, DftView};
+use fdtd_wasm::state::SimulationState;
+use fdtd_wasm::step::step;
+use fdtd_wasm::volume::parameters::FieldComponent;
+use std::f64::consts::PI;
+use fdtd_wasm::precision::{widen, Real};
+
+fn monitor(frequencies: Vec<f64>, start_time: f64) -> DftMonitorDefinition {
+    DftMonitorDefinition {
+        name: "near".to_string(),
+        component: FieldComponent::Ez,
+        x_min: 25, y_min: 30, x_max: 75, y_max: 70,
+        frequencies,
+        start_time,
+    }
+}
+
+#[test]
+fn test_dft_recovers_amplitude_and_phase() {
+    let definition = DftMonitorDefinition { x_min: 0, y_min: 0, x_max: 1, y_max: 0, ..monitor(vec![0.05, 0.1], 0.0) };
+    let mut dft = DftMonitor::new(&definition);
+    // Two cells with different amplitudes and phases, over ten periods of 0.05.
+    for n in 0..200 {
+        let t = n as f64;
+        let field = [2.0 * (2.0 * PI * 0.05 * t + 0.7).cos(), 0.5 * (2.0 * PI * 0.05 * t - 2.0).cos()].map(|v| v as Real);
+        dft.accumulate(&field, 2, t);
+    }
+    assert_eq!(dft.samples(), 200);
+    let (magnitude, phase) = (dft.magnitude(0), dft.phase(0));
+    // Single-precision samples (the `f32` feature) are only exact to about 1e-7.
+    let tolerance = if cfg!(feature = "f32") { 1e-6 } else { 1e-12 };
+    assert!((magnitude[0] - 2.0).abs() < tolerance && (magnitude[1] - 0.5).abs() < tolerance);
+    assert!((phase[0] - 0.7).abs() < tolerance && (phase[1] + 2.0).abs() < tolerance);
+    assert!(dft.magnitude(1).iter().all(|m| m.abs() < tolerance));
+
+    dft.reset();
+    assert_eq!(dft.samples(), 0);
+    assert!(dft.magnitude(0).iter().all(|&m| m == 0.0));
+}
+
+#[test]
+fn test_dft_monitor_shows_steady_state_pattern() {
+    let params = SimulationParameters {
+        sources: vec![SourceDefinition { x: 50, y: 50, ..Default::default() }],
+        dft_monitors: vec![monitor(vec![0.05, 0.03], 301.0)],
+        ..Default::default()
+    };
+    let mut state = SimulationState::from_parameters(&params).unwrap();
+    let at = |x: usize, y: usize| (y - 30) * 51 + (x - 25);
+    let mut peak = 0.0f64;
+    for n in 0..700 {
+        step(&params, &mut state, None);
+        if n >= 680 {
+            peak = peak.max(widen(state.ez[50 * 100 + 62]).abs());
+        }
+    }
+
+    let dft = state.dft_monitor("near").unwrap();
+    assert_eq!(dft.samples(), 400);
+    let carrier = dft.magnitude(0);
+    assert!((carrier[at(62, 50)] - peak).abs() < 0.05 * peak, "DFT {} vs peak {}", carrier[at(62, 50)], peak);
+    // The pattern is symmetric about the source, up to the half-cell offset of the absorbing layers.
+    assert!((carrier[at(62, 50)] - carrier[at(38, 50)]).abs() < 1e-4 * carrier[at(62, 50)]);
+    let tolerance = if cfg!(feature = "f32") { 1e-6 } else { 1e-12 };
+    assert!((carrier[at(62, 50)] - carrier[at(50, 62)]).abs() < tolerance);
+    assert!(dft.magnitude(1)[at(62, 50)] < 0.05 * carrier[at(62, 50)]);
+
+    // The phase falls by k per cell away from the source.
+    let omega = 2.0 * PI * 0.05;
+    let k = 2.0 * ((omega / 2.0).sin() / 0.5).asin();
+    let phase = dft.phase(0);
+    let delta = (phase[at(70, 50)] - phase[at(65, 50)] + 5.0 * k).rem_euclid(2.0 * PI);
+    assert!(delta.min(2.0 * PI - delta) < 0.1, "phase error {}", delta);
+
+    for view in [DftView::Magnitude, DftView::Phase] {
+        assert_eq!(render_dft(&state, dft, 0, view).len(), 51 * 41 * 4);
+    }
+}
+
+#[test]
+fn test_dft_monitor_validation_and_units() {
+    let with = |definition| SimulationParameters { dft_monitors: vec![definition], ..Default::default() };
+    assert!(validate_parameters(&with(monitor(vec![0.05], 0.0))).is_ok());
+    assert!(validate_parameters(&with(monitor(vec![], 0.0))).is_err());
+    assert!(validate_parameters(&with(monitor(vec![-0.05], 0.0))).is_err());
+    assert!(validate_parameters(&with(monitor(vec![0.05], -1.0))).is_err());
+    assert!(validate_parameters(&with(DftMonitorDefinition { x_max: 100, ..monitor(vec![0.05], 0.0) })).is_err());
+    assert!(validate_parameters(&with(DftMonitorDefinition { component: FieldComponent::Hz, ..monitor(vec![0.05], 0.0) })).is_err());
+
+    let physical = SimulationParameters {
+        grid: GridDefinition { cell_size: Some(1e-3), courant: None },
+        sources: vec![SourceDefinition { x: 50, y: 50, frequency: 1e10, ..Default::default() }],
+        ..with(monitor(vec![1e10, 2e10], 1e-9))
+    };
+    let dt = physical.time_step_seconds().unwrap();
+    let resolved = physical.resolve_units();
+    assert!((resolved.dft_monitors[0].frequencies[1] - 2e10 * dt).abs() < 1e-12);
+    assert!((resolved.dft_monitors[0].start_time - 1e-9 / dt).abs() < 1e-9);
+}
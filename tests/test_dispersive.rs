@@ -0,0 +1,161 @@
+use fdtd_wasm::dispersive::{permittivity_at, PoleUpdate};
+use fdtd_wasm::engine::apply_forced_source;
+use fdtd_wasm::parameters::{
+    validate_parameters, BoundaryDefinition, CpmlDefinition, DispersivePole, EdgeBoundary, MaterialDefinition,
+    MaterialRegion, SignalType, SimulationParameters, SourceDefinition,
+};
+use fdtd_wasm::state::SimulationState;
+use fdtd_wasm::step::step;
+use std::f64::consts::PI;
+use fdtd_wasm::precision::widen;
+
+const WIDTH: usize = 1000;
+const SOURCE_X: usize = 100;
+const PROBE_X: usize = 300;
+const INTERFACE_X: usize = 500;
+const STEPS: usize = 2400;
+
+fn gaussian(t: f64) -> f64 {
+    let arg = (t - 60.0) / 10.0;
+    (-arg * arg).exp()
+}
+
+/// Runs a normally incident plane wave on a 3-row grid whose outer rows mirror the middle one,
+/// which makes the 2D solver behave as a 1D line. Returns Ez at the probe.
+fn run_line(material: Option<MaterialDefinition>) -> Vec<f64> {
+    let side = EdgeBoundary::Cpml(CpmlDefinition::default());
+    let none = EdgeBoundary::Cpml(CpmlDefinition { thickness: 0, ..Default::default() });
+    let mut params = SimulationParameters {
+        width: WIDTH,
+        height: 3,
+        sources: vec![SourceDefinition { x: SOURCE_X, y: 1, amplitude: 0.0, frequency: 0.01, signal_type: SignalType::ContinuousSine, ..Default::default() }],
+        boundary: BoundaryDefinition { left: side.clone(), right: side, top: none.clone(), bottom: none },
+        ..Default::default()
+    };
+    if let Some(m) = material {
+        params.regions = vec![MaterialRegion {
+            material: m.name.clone(),
+            path: format!("M {} -1 L {} -1 L {} 4 L {} 4 Z", INTERFACE_X, WIDTH, WIDTH, INTERFACE_X),
+        }];
+        params.materials = vec![m];
+    }
+    assert!(validate_parameters(&params).is_ok());
+    let mut state = SimulationState::from_parameters(&params).unwrap();
+
+    let mut trace = Vec::with_capacity(STEPS);
+    for n in 0..STEPS {
+        step(&params, &mut state, Some(0.0));
+        apply_forced_source(&mut state, SOURCE_X, 1, gaussian(n as f64));
+        let (top, rest) = state.ez.split_at_mut(WIDTH);
+        let (middle, bottom) = rest.split_at_mut(WIDTH);
+        top.copy_from_slice(middle);
+        bottom.copy_from_slice(middle);
+        trace.push(widen(state.ez[WIDTH + PROBE_X]));
+    }
+    trace
+}
+
+fn dft(signal: &[f64], frequency: f64) -> (f64, f64) {
+    signal.iter().enumerate().fold((0.0, 0.0), |(re, im), (n, v)| {
+        let phase = 2.0 * PI * frequency * n as f64;
+        (re + v * phase.cos(), im - v * phase.sin())
+    })
+}
+
+/// |(1 - n) / (1 + n)| with n = sqrt(eps).
+fn analytic_reflection(eps: (f64, f64)) -> f64 {
+    let modulus = (eps.0 * eps.0 + eps.1 * eps.1).sqrt();
+    let n_re = ((modulus + eps.0) / 2.0).sqrt();
+    let n_im = ((modulus - eps.0) / 2.0).sqrt().copysign(eps.1);
+    let num = ((1.0 - n_re).powi(2) + n_im * n_im).sqrt();
+    let den = ((1.0 + n_re).powi(2) + n_im * n_im).sqrt();
+    num / den
+}
+
+/// Compares the simulated and analytical reflection magnitude at several frequencies.
+fn check_half_space(material: MaterialDefinition, frequencies: &[f64], tolerance: f64) {
+    let incident = run_line(None);
+    let total = run_line(Some(material.clone()));
+    let reflected: Vec<f64> = total.iter().zip(&incident).map(|(t, i)| t - i).collect();
+
+    for &f in frequencies {
+        let (ir, ii) = dft(&incident, f);
+        let (rr, ri) = dft(&reflected, f);
+        let simulated = ((rr * rr + ri * ri) / (ir * ir + ii * ii)).sqrt();
+        let expected = analytic_reflection(permittivity_at(&material, f));
+        assert!((simulated - expected).abs() < tolerance,
+                "{}: f = {} simulated |R| = {:.4}, analytic |R| = {:.4}", material.name, f, simulated, expected);
+    }
+}
+
+#[test]
+fn test_drude_half_space_reflection() {
+    let plasma = MaterialDefinition {
+        name: "plasma".to_string(),
+        poles: vec![DispersivePole::Drude { plasma_frequency: 0.015, collision_rate: 0.01 }],
+        ..Default::default()
+    };
+    check_half_space(plasma, &[0.008, 0.012, 0.02, 0.025], 0.02);
+}
+
+#[test]
+fn test_lorentz_half_space_reflection() {
+    let resonant = MaterialDefinition {
+        name: "resonant".to_string(),
+        relative_permittivity: 1.5,
+        poles: vec![DispersivePole::Lorentz { delta_epsilon: 2.0, resonance_frequency: 0.015, damping: 0.005 }],
+        ..Default::default()
+    };
+    check_half_space(resonant, &[0.008, 0.012, 0.02, 0.025], 0.02);
+}
+
+#[test]
+fn test_debye_half_space_reflection() {
+    let water = MaterialDefinition {
+        name: "water".to_string(),
+        relative_permittivity: 2.0,
+        poles: vec![DispersivePole::Debye { delta_epsilon: 6.0, relaxation_time: 15.0 }],
+        ..Default::default()
+    };
+    check_half_space(water, &[0.005, 0.01, 0.02], 0.02);
+}
+
+#[test]
+fn test_permittivity_limits() {
+    let debye = MaterialDefinition {
+        name: "d".to_string(),
+        relative_permittivity: 2.0,
+        poles: vec![DispersivePole::Debye { delta_epsilon: 3.0, relaxation_time: 10.0 }],
+        ..Default::default()
+    };
+    // Static limit eps_inf + delta_epsilon
+    let (re, im) = permittivity_at(&debye, 0.0);
+    assert!((re - 5.0).abs() < 1e-12);
+    assert_eq!(im, 0.0);
+    // Lossy (positive imaginary part) in between
+    assert!(permittivity_at(&debye, 0.01).1 > 0.0);
+}
+
+#[test]
+fn test_pole_update_coefficients() {
+    match PoleUpdate::new(&DispersivePole::Debye { delta_epsilon: 2.0, relaxation_time: 0.5 }) {
+        PoleUpdate::Relaxation { a, b } => {
+            assert_eq!(a, 0.0);
+            assert_eq!(b, 1.0);
+        },
+        other => panic!("unexpected update {:?}", other),
+    }
+}
+
+#[test]
+fn test_validate_rejects_unstable_lorentz_pole() {
+    let params = SimulationParameters {
+        materials: vec![MaterialDefinition {
+            name: "fast".to_string(),
+            poles: vec![DispersivePole::Lorentz { delta_epsilon: 1.0, resonance_frequency: 0.4, damping: 0.0 }],
+            ..Default::default()
+        }],
+        ..Default::default()
+    };
+    assert!(validate_parameters(&params).is_err());
+}
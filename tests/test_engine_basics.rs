@@ -1,6 +1,5 @@
 use fdtd_wasm::engine::compute_source_signal;
 use fdtd_wasm::parameters::SignalType;
-use std::f64::consts::PI;
 
 #[test]
 fn test_compute_source_signal_zero_time() {
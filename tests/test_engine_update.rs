@@ -18,7 +18,7 @@ fn test_update_fields_no_source_remains_zero() {
 #[test]
 fn test_apply_source_injects_value() {
     let mut state = SimulationState::new(10, 10);
-    let source = SourceDefinition { x: 5, y: 5, amplitude: 1.0, frequency: 1.0, signal_type: SignalType::ContinuousSine };
+    let _source = SourceDefinition { x: 5, y: 5, amplitude: 1.0, frequency: 1.0, signal_type: SignalType::ContinuousSine, ..Default::default() };
     
     // We need to advance time to t=0.25 (peak) to see injection, because sin(0)=0.
     // apply_source uses state.time_step.
@@ -36,7 +36,7 @@ fn test_apply_source_injects_value() {
     // Let's use frequency 0.25 -> period = 4. 
     // t=1 -> sin(2pi * 0.25 * 1) = sin(pi/2) = 1.
     
-    let source_visible = SourceDefinition { x: 5, y: 5, amplitude: 1.0, frequency: 0.25, signal_type: SignalType::ContinuousSine };
+    let source_visible = SourceDefinition { x: 5, y: 5, amplitude: 1.0, frequency: 0.25, signal_type: SignalType::ContinuousSine, ..Default::default() };
     state.time_step = 1;
     
     apply_source(&mut state, &source_visible);
@@ -97,4 +97,12 @@ fn test_apply_forced_source() {
     // Apply again to verify accumulation (it's +=)
     apply_forced_source(&mut state, 5, 5, 5.0);
     assert_eq!(state.ez[55], 15.0);
-}
\ No newline at end of file
+}
+#[test]
+fn test_update_uses_state_courant_number() {
+    let mut state = SimulationState::new(10, 10);
+    state.set_courant(0.25);
+    state.ez[55] = 1.0;
+    update_hy(&mut state);
+    assert_eq!(state.hy[55], -0.25);
+}
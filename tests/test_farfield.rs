@@ -0,0 +1,95 @@
+use fdtd_wasm::farfield::FarFieldMonitor;
+use fdtd_wasm::parameters::{validate_parameters, FarFieldDefinition, Polarization, SimulationParameters, SourceDefinition};
+use fdtd_wasm::state::SimulationState;
+use fdtd_wasm::step::step;
+
+// At the default Courant number of 0.5 a frequency of 0.025 has a 20-cell wavelength.
+fn source(x: usize, y: usize) -> SourceDefinition {
+    SourceDefinition { x, y, frequency: 0.025, ramp_time: Some(80.0), ..Default::default() }
+}
+
+fn contour(name: &str) -> FarFieldDefinition {
+    FarFieldDefinition {
+        name: name.to_string(),
+        x_min: 35, y_min: 35, x_max: 85, y_max: 85,
+        frequencies: vec![0.025],
+        angles: Some(72),
+    }
+}
+
+fn run(sources: Vec<SourceDefinition>, polarization: Polarization) -> SimulationState {
+    let params = SimulationParameters {
+        width: 121, height: 121,
+        sources,
+        far_fields: vec![contour("pattern")],
+        polarization,
+        ..Default::default()
+    };
+    let mut state = SimulationState::from_parameters(&params).unwrap();
+    for _ in 0..600 {
+        step(&params, &mut state, None);
+    }
+    state
+}
+
+#[test]
+fn test_line_source_is_omnidirectional() {
+    for polarization in [Polarization::TMz, Polarization::TEz] {
+        let state = run(vec![source(60, 60)], polarization);
+        let pattern = state.far_field("pattern").unwrap().pattern(0, state.courant);
+        assert_eq!(pattern.angles.len(), 72);
+        assert_eq!(pattern.angles[18], 90.0);
+        for (angle, d) in pattern.angles.iter().zip(&pattern.directivity) {
+            assert!((d - 1.0).abs() < 0.02, "{:?} at {} degrees: {}", polarization, angle, d);
+        }
+    }
+}
+
+#[test]
+fn test_two_element_array_pattern() {
+    // Two in-phase sources half a wavelength apart along x: the array factor cos(pi/2 cos(phi))
+    // nulls the x axis and gives a broadside directivity of 2 / (1 - J0(pi)) = 2.87.
+    for polarization in [Polarization::TMz, Polarization::TEz] {
+        let state = run(vec![source(55, 60), source(65, 60)], polarization);
+        let pattern = state.far_field("pattern").unwrap().pattern(0, state.courant);
+        let at = |angle: f64| pattern.directivity[pattern.angles.iter().position(|&a| a == angle).unwrap()];
+
+        for broadside in [90.0, 270.0] {
+            assert!((at(broadside) - 2.87).abs() < 0.15, "{:?}: broadside {}", polarization, at(broadside));
+        }
+        for endfire in [0.0, 180.0] {
+            assert!(at(endfire) < 0.05, "{:?}: endfire {}", polarization, at(endfire));
+        }
+        assert!((at(45.0) - at(135.0)).abs() < 0.02 * at(45.0));
+        let (angle, peak) = pattern.peak();
+        assert!((angle == 90.0 || angle == 270.0) && peak >= at(90.0));
+        assert!((pattern.directivity_db()[18] - 10.0 * at(90.0).log10()).abs() < 1e-12);
+    }
+}
+
+#[test]
+fn test_far_field_pattern_export_and_reset() {
+    let mut state = run(vec![source(60, 60)], Polarization::TMz);
+    let pattern = state.far_field("pattern").unwrap().pattern(0, state.courant);
+    let csv = pattern.to_csv();
+    assert!(csv.starts_with("angle_deg,directivity,directivity_db\n"));
+    assert_eq!(csv.lines().count(), 73);
+
+    state.reset();
+    let far_field: &FarFieldMonitor = state.far_field("pattern").unwrap();
+    assert!(far_field.pattern(0, state.courant).directivity.iter().all(|&d| d == 0.0));
+}
+
+#[test]
+fn test_far_field_validation() {
+    let with = |definition: FarFieldDefinition| SimulationParameters { far_fields: vec![definition], ..Default::default() };
+    assert!(validate_parameters(&with(contour("ok"))).is_ok());
+    assert!(validate_parameters(&with(FarFieldDefinition { x_min: 0, ..contour("edge") })).is_err());
+    assert!(validate_parameters(&with(FarFieldDefinition { x_max: 99, ..contour("far edge") })).is_err());
+    assert!(validate_parameters(&with(FarFieldDefinition { x_min: 90, ..contour("inverted") })).is_err());
+    assert!(validate_parameters(&with(FarFieldDefinition { frequencies: vec![], ..contour("none") })).is_err());
+    assert!(validate_parameters(&with(FarFieldDefinition { angles: Some(0), ..contour("no angles") })).is_err());
+    let mut twice = with(contour("same"));
+    twice.far_fields.push(contour("same"));
+    assert!(validate_parameters(&twice).is_err());
+}
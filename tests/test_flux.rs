@@ -0,0 +1,140 @@
+use fdtd_wasm::flux::electromagnetic_energy;
+use fdtd_wasm::parameters::{
+    validate_parameters, BoundaryDefinition, EdgeBoundary, FluxLineDefinition, Polarization, SignalType,
+    SimulationParameters, SourceDefinition,
+};
+use fdtd_wasm::state::SimulationState;
+use fdtd_wasm::step::step;
+
+fn pulse(x: usize, y: usize) -> SourceDefinition {
+    SourceDefinition { x, y, frequency: 0.05, signal_type: SignalType::PulseSine, ..Default::default() }
+}
+
+fn line(name: &str, x0: usize, y0: usize, x1: usize, y1: usize) -> FluxLineDefinition {
+    FluxLineDefinition { name: name.to_string(), x0, y0, x1, y1, frequencies: vec![0.03, 0.05] }
+}
+
+#[test]
+fn test_energy_is_conserved_in_closed_box() {
+    // Walls that hold the primary field at zero: electric in TMz, magnetic in TEz.
+    for (polarization, wall) in [(Polarization::TMz, EdgeBoundary::Pec), (Polarization::TEz, EdgeBoundary::Pmc)] {
+        let params = SimulationParameters {
+            width: 60, height: 50,
+            sources: vec![pulse(20, 22)],
+            boundary: BoundaryDefinition { left: wall.clone(), right: wall.clone(), top: wall.clone(), bottom: wall },
+            polarization,
+            ..Default::default()
+        };
+        let mut state = SimulationState::from_parameters(&params).unwrap();
+        for _ in 0..40 {
+            step(&params, &mut state, None);
+        }
+        let reference = electromagnetic_energy(&state, None);
+        assert!(reference > 0.0);
+        // Single-precision fields (the `f32` feature) add rounding noise every step.
+        let tolerance = if cfg!(feature = "f32") { 1e-5 } else { 1e-9 };
+        for _ in 0..1000 {
+            step(&params, &mut state, None);
+            let energy = electromagnetic_energy(&state, None);
+            assert!((energy - reference).abs() < tolerance * reference, "{:?}: {} vs {}", polarization, energy, reference);
+        }
+    }
+}
+
+#[test]
+fn test_energy_leaving_region_matches_flux() {
+    // Lines around the cells 30..=70 x 30..=70, the source inside.
+    let params = SimulationParameters {
+        width: 101, height: 101,
+        sources: vec![SourceDefinition { frequency: 0.025, ..pulse(45, 55) }],
+        flux_lines: vec![
+            line("left", 29, 30, 29, 70),
+            line("right", 70, 30, 70, 70),
+            line("top", 30, 29, 70, 29),
+            line("bottom", 30, 70, 70, 70),
+        ],
+        ..Default::default()
+    };
+    let mut state = SimulationState::from_parameters(&params).unwrap();
+    let region = Some((30, 30, 70, 70));
+    for _ in 0..45 {
+        step(&params, &mut state, None);
+    }
+    let before = electromagnetic_energy(&state, region);
+    let flux_before: Vec<f64> = state.flux_lines.iter().map(|l| l.energy()).collect();
+    for _ in 0..150 {
+        step(&params, &mut state, None);
+    }
+    let after = electromagnetic_energy(&state, region);
+    let out: f64 = ["right", "bottom"].iter().zip(["left", "top"])
+        .map(|(outward, inward)| {
+            let energy = |name: &str| {
+                let i = state.flux_lines.iter().position(|l| l.name == name).unwrap();
+                state.flux_lines[i].energy() - flux_before[i]
+            };
+            energy(outward) - energy(inward)
+        })
+        .sum();
+
+    assert!(after < 0.2 * before, "most of the pulse should have left: {} of {}", after, before);
+    assert!((before - after - out).abs() < 0.01 * before, "energy lost {} vs flux out {}", before - after, out);
+}
+
+#[test]
+fn test_symmetric_lines_see_opposite_flux() {
+    for polarization in [Polarization::TMz, Polarization::TEz] {
+        let params = SimulationParameters {
+            width: 101, height: 101,
+            sources: vec![SourceDefinition { x: 50, y: 50, frequency: 0.05, ..Default::default() }],
+            flux_lines: vec![line("east", 70, 20, 70, 80), line("west", 29, 20, 29, 80), line("south", 20, 70, 80, 70)],
+            polarization,
+            ..Default::default()
+        };
+        let mut state = SimulationState::from_parameters(&params).unwrap();
+        // The wall at x + 1/2 sits 20.5 cells from the source on both sides.
+        for _ in 0..400 {
+            step(&params, &mut state, None);
+            let (east, west) = (state.flux_line("east").unwrap(), state.flux_line("west").unwrap());
+            assert!((east.power() + west.power()).abs() < 1e-3 * east.power().abs().max(1e-12));
+        }
+        let (east, west, south) = (
+            state.flux_line("east").unwrap(),
+            state.flux_line("west").unwrap(),
+            state.flux_line("south").unwrap(),
+        );
+        assert!(east.energy() > 0.0);
+        assert!((east.energy() + west.energy()).abs() < 1e-3 * east.energy());
+        assert!((east.energy() - south.energy()).abs() < 1e-6 * east.energy());
+
+        let (east_spectrum, west_spectrum) = (east.spectrum(), west.spectrum());
+        assert!(east_spectrum[1] > 0.0 && east_spectrum[1] > 10.0 * east_spectrum[0].abs());
+        assert!((east_spectrum[1] + west_spectrum[1]).abs() < 1e-3 * east_spectrum[1]);
+    }
+}
+
+#[test]
+fn test_flux_line_reset() {
+    let params = SimulationParameters { flux_lines: vec![line("east", 70, 20, 70, 80)], ..Default::default() };
+    let mut state = SimulationState::from_parameters(&params).unwrap();
+    for _ in 0..200 {
+        step(&params, &mut state, None);
+    }
+    assert!(state.flux_line("east").unwrap().energy() != 0.0);
+    state.reset();
+    let east = state.flux_line("east").unwrap();
+    assert_eq!((east.power(), east.energy()), (0.0, 0.0));
+    assert!(east.spectrum().iter().all(|&s| s == 0.0));
+}
+
+#[test]
+fn test_flux_line_validation() {
+    let with = |definition: FluxLineDefinition| SimulationParameters { flux_lines: vec![definition], ..Default::default() };
+    assert!(validate_parameters(&with(line("ok", 70, 20, 70, 80))).is_ok());
+    assert!(validate_parameters(&with(line("diagonal", 20, 20, 30, 30))).is_err());
+    assert!(validate_parameters(&with(line("edge", 99, 20, 99, 80))).is_err());
+    assert!(validate_parameters(&with(line("outside", 70, 20, 70, 100))).is_err());
+    assert!(validate_parameters(&with(FluxLineDefinition { frequencies: vec![-0.1], ..line("negative", 70, 20, 70, 80) })).is_err());
+    let mut twice = with(line("same", 70, 20, 70, 80));
+    twice.flux_lines.push(line("same", 20, 20, 20, 80));
+    assert!(validate_parameters(&twice).is_err());
+}
@@ -0,0 +1,112 @@
+use fdtd_wasm::engine::{update_e_fields, update_ex, update_ex_ey, update_ey, update_hx, update_hx_hy, update_hy, update_hz};
+use fdtd_wasm::parameters::{MaterialDefinition, Polarization};
+use fdtd_wasm::precision::{widen, Real};
+use fdtd_wasm::state::SimulationState;
+
+/// A state with pseudo-random fields and a block of lossy dielectric, so rows are
+/// uniform vacuum, uniform dielectric or mixed.
+fn state(polarization: Polarization) -> SimulationState {
+    let (w, h) = (37, 29);
+    let mut state = SimulationState::new(w, h);
+    state.set_polarization(polarization);
+    state.set_material_table(&[MaterialDefinition { name: "glass".to_string(), relative_permittivity: 3.0, conductivity: 0.02, ..Default::default() }]);
+    let mut seed = 12345u64;
+    let mut next = || {
+        seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+        (seed >> 11) as f64 / (1u64 << 53) as f64 - 0.5
+    };
+    for field in [&mut state.ez, &mut state.hx, &mut state.hy, &mut state.hz, &mut state.ex, &mut state.ey] {
+        field.iter_mut().for_each(|v| *v = next() as Real);
+    }
+    for y in 5..20 {
+        for x in if y < 12 { 0..w } else { 10..25 } {
+            state.materials[y * w + x] = 2;
+        }
+    }
+    state
+}
+
+/// Per-cell reference of `out = keep * out + sign * gain * (f[idx + step] - f[idx])`, where `step`
+/// is 1 (along x) or the width (along y).
+fn reference_difference(state: &SimulationState, out: &[Real], source: &[Real], step: usize, sign: f64, electric: bool) -> Vec<Real> {
+    let (w, h) = (state.width, state.height);
+    let (rows, columns) = if step == 1 { (0..h, 0..w - 1) } else { (0..h - 1, 0..w) };
+    let mut out = out.to_vec();
+    for y in rows {
+        for x in columns.clone() {
+            let idx = y * state.width + x;
+            let c = &state.coefficients[state.materials[idx] as usize];
+            let (keep, gain) = if electric { (c.ca, c.cb) } else { (c.da, c.db) };
+            let (keep, gain) = (keep as Real, gain as Real);
+            let d = source[idx + step] - source[idx];
+            out[idx] = if sign > 0.0 { keep * out[idx] + gain * d } else { keep * out[idx] - gain * d };
+        }
+    }
+    out
+}
+
+fn bits(field: &[Real]) -> Vec<u64> {
+    field.iter().map(|&v| widen(v).to_bits()).collect()
+}
+
+#[test]
+fn test_row_kernels_match_per_cell_updates() {
+    let tm = state(Polarization::TMz);
+    let (w, h) = (tm.width, tm.height);
+
+    let mut s = state(Polarization::TMz);
+    update_hx(&mut s);
+    assert_eq!(bits(&s.hx), bits(&reference_difference(&tm, &tm.hx, &tm.ez, w, -1.0, false)));
+    let mut s = state(Polarization::TMz);
+    update_hy(&mut s);
+    assert_eq!(bits(&s.hy), bits(&reference_difference(&tm, &tm.hy, &tm.ez, 1, 1.0, false)));
+
+    let mut s = state(Polarization::TMz);
+    update_e_fields(&mut s);
+    let mut expected = tm.ez.clone();
+    for y in 1..h - 1 {
+        for x in 1..w - 1 {
+            let idx = y * w + x;
+            let c = &tm.coefficients[tm.materials[idx] as usize];
+            let (dhy, dhx) = (tm.hy[idx] - tm.hy[idx - 1], tm.hx[idx] - tm.hx[idx - w]);
+            expected[idx] = c.ca as Real * expected[idx] + c.cb as Real * (dhy - dhx);
+        }
+    }
+    assert_eq!(bits(&s.ez), bits(&expected));
+
+    let te = state(Polarization::TEz);
+    let mut s = state(Polarization::TEz);
+    update_ex(&mut s);
+    assert_eq!(bits(&s.ex), bits(&reference_difference(&te, &te.ex, &te.hz, w, 1.0, true)));
+    let mut s = state(Polarization::TEz);
+    update_ey(&mut s);
+    assert_eq!(bits(&s.ey), bits(&reference_difference(&te, &te.ey, &te.hz, 1, -1.0, true)));
+
+    let mut s = state(Polarization::TEz);
+    update_hz(&mut s);
+    let mut expected = te.hz.clone();
+    for y in 1..h - 1 {
+        for x in 1..w - 1 {
+            let idx = y * w + x;
+            let c = &te.coefficients[te.materials[idx] as usize];
+            let (dex, dey) = (te.ex[idx] - te.ex[idx - w], te.ey[idx] - te.ey[idx - 1]);
+            expected[idx] = c.da as Real * expected[idx] + c.db as Real * (dex - dey);
+        }
+    }
+    assert_eq!(bits(&s.hz), bits(&expected));
+}
+
+#[test]
+fn test_fused_sweeps_match_separate_updates() {
+    let (mut fused, mut separate) = (state(Polarization::TMz), state(Polarization::TMz));
+    update_hx_hy(&mut fused);
+    update_hx(&mut separate);
+    update_hy(&mut separate);
+    assert_eq!((bits(&fused.hx), bits(&fused.hy)), (bits(&separate.hx), bits(&separate.hy)));
+
+    let (mut fused, mut separate) = (state(Polarization::TEz), state(Polarization::TEz));
+    update_ex_ey(&mut fused);
+    update_ex(&mut separate);
+    update_ey(&mut separate);
+    assert_eq!((bits(&fused.ex), bits(&fused.ey)), (bits(&separate.ex), bits(&separate.ey)));
+}
@@ -0,0 +1,225 @@
+use fdtd_wasm::flux::electromagnetic_energy;
+use fdtd_wasm::parameters::{
+    validate_parameters, BoundaryDefinition, EdgeBoundary, GridDefinition, Interpolation, LumpedElement, LumpedElementDefinition,
+    MaterialDefinition, Polarization, ProbeDefinition, ProbeShape, SignalType, SimulationParameters, SourceDefinition,
+    FREE_SPACE_IMPEDANCE, SPEED_OF_LIGHT,
+};
+use fdtd_wasm::precision::widen;
+use fdtd_wasm::state::SimulationState;
+use fdtd_wasm::step::step;
+use fdtd_wasm::volume::parameters::FieldComponent;
+use fdtd_wasm::FdtdSimulator;
+
+fn element(name: &str, x: usize, y: usize, element: LumpedElement) -> LumpedElementDefinition {
+    LumpedElementDefinition { name: name.to_string(), x, y, element }
+}
+
+fn gaussian_source(resistance: f64) -> LumpedElement {
+    LumpedElement::VoltageSource {
+        resistance,
+        amplitude: 1.0,
+        frequency: 0.05,
+        signal_type: SignalType::Gaussian { width: 10.0, delay: None },
+    }
+}
+
+fn params(lumped_elements: Vec<LumpedElementDefinition>) -> SimulationParameters {
+    SimulationParameters {
+        width: 100, height: 80,
+        sources: vec![SourceDefinition { x: 35, y: 40, signal_type: SignalType::PulseSine, ..Default::default() }],
+        lumped_elements,
+        ..Default::default()
+    }
+}
+
+/// Runs `params` next to a copy without lumped elements whose cell (60, 40) is made of `material`.
+fn assert_matches_material(lumped: LumpedElement, material: MaterialDefinition) {
+    let with_element = params(vec![element("e", 60, 40, lumped)]);
+    let with_material = SimulationParameters { materials: vec![material], ..params(Vec::new()) };
+    let mut a = SimulationState::from_parameters(&with_element).unwrap();
+    let mut b = SimulationState::from_parameters(&with_material).unwrap();
+    b.materials[40 * 100 + 60] = 2;
+    let mut peak = 0.0f64;
+    for _ in 0..300 {
+        step(&with_element, &mut a, None);
+        step(&with_material, &mut b, None);
+        for (&x, &y) in a.ez.iter().zip(&b.ez) {
+            peak = peak.max(widen(y).abs());
+            assert!((widen(x) - widen(y)).abs() <= 1e-12 + 1e-6 * peak * f64::from(cfg!(feature = "f32")));
+        }
+    }
+    assert!(peak > 0.01);
+}
+
+#[test]
+fn test_resistor_matches_conductive_cell() {
+    // A resistor r across a dx cube is a conductivity 1 / r in normalised units.
+    let material = MaterialDefinition { name: "lossy".to_string(), conductivity: 1.0 / 0.4, ..Default::default() };
+    assert_matches_material(LumpedElement::Resistor { resistance: 0.4 }, material);
+}
+
+#[test]
+fn test_capacitor_matches_dielectric_cell() {
+    // A capacitance c (in eps0 * dx) across a dx cube adds c to the relative permittivity.
+    let material = MaterialDefinition { name: "dielectric".to_string(), relative_permittivity: 3.5, ..Default::default() };
+    assert_matches_material(LumpedElement::Capacitor { capacitance: 2.5 }, material);
+}
+
+#[test]
+fn test_port_power_balances_stored_energy() {
+    // In a closed PEC box every change of the field energy is the power taken by the elements,
+    // `S * v * i` per step, with v and i at the same half step.
+    let pec = EdgeBoundary::Pec;
+    let params = SimulationParameters {
+        sources: Vec::new(),
+        boundary: BoundaryDefinition { left: pec.clone(), right: pec.clone(), top: pec.clone(), bottom: pec },
+        ..params(vec![
+            element("feed", 20, 25, gaussian_source(0.5)),
+            element("load", 40, 25, LumpedElement::Resistor { resistance: 0.2 }),
+            element("cap", 30, 15, LumpedElement::Capacitor { capacitance: 4.0 }),
+            element("coil", 30, 35, LumpedElement::Inductor { inductance: 0.5 }),
+        ])
+    };
+    let mut state = SimulationState::from_parameters(&params).unwrap();
+    let mut delivered = 0.0;
+    for _ in 0..600 {
+        step(&params, &mut state, None);
+        delivered -= state.lumped_elements.iter().map(|e| state.courant * e.voltage() * e.current()).sum::<f64>();
+        let energy = electromagnetic_energy(&state, None);
+        let tolerance = if cfg!(feature = "f32") { 1e-4 } else { 1e-9 };
+        assert!((energy - delivered).abs() <= tolerance * delivered.abs().max(1e-6), "{} vs {}", energy, delivered);
+    }
+    assert!(delivered > 1e-3);
+}
+
+#[test]
+fn test_small_inductors_stay_bounded() {
+    // Values that made the old explicit update diverge within a hundred steps, coupled to the
+    // node's four H links.
+    for (courant, inductance) in [(0.5, 0.07), (0.5, 1e-3), (0.7, 0.2)] {
+        let params = SimulationParameters {
+            grid: GridDefinition { cell_size: None, courant: Some(courant) },
+            ..params(vec![element("l", 60, 40, LumpedElement::Inductor { inductance })])
+        };
+        let mut state = SimulationState::from_parameters(&params).unwrap();
+        let mut peak = 0.0f64;
+        for n in 0..4000 {
+            step(&params, &mut state, None);
+            let largest = state.ez.iter().fold(0.0f64, |m, &v| m.max(widen(v).abs()));
+            assert!(largest < 10.0, "S = {}, L = {}: |Ez| = {} at step {}", courant, inductance, largest, n);
+            peak = peak.max(largest);
+        }
+        assert!(peak > 0.01);
+    }
+}
+
+#[test]
+fn test_low_impedance_source_holds_its_voltage() {
+    let params = params(vec![element("feed", 30, 25, LumpedElement::VoltageSource {
+        resistance: 1e-3, amplitude: 2.0, frequency: 0.02, signal_type: SignalType::ContinuousSine,
+    })]);
+    let mut simulator = FdtdSimulator::from_parameters(SimulationParameters { sources: Vec::new(), ..params }).unwrap();
+    for n in 0..200 {
+        simulator.step();
+        let emf = 2.0 * (2.0 * std::f64::consts::PI * 0.02 * (n as f64 + 0.5)).sin();
+        let (voltage, current) = simulator.lumped_port("feed").unwrap();
+        assert!((voltage - emf).abs() < 0.05, "step {}: {} vs {}", n, voltage, emf);
+        assert!((voltage - emf - 1e-3 * current).abs() < 1e-9);
+    }
+}
+
+#[test]
+fn test_feed_carries_transmission() {
+    let mut params = params(vec![element("feed", 35, 40, LumpedElement::VoltageSource {
+        resistance: 1.0, amplitude: 10.0, frequency: 0.05, signal_type: SignalType::ContinuousSine,
+    })]);
+    params.sources = Vec::new();
+    params.probes = vec![ProbeDefinition { name: "rx".to_string(), component: FieldComponent::Ez, shape: ProbeShape::Point { x: 55, y: 40 }, capacity: None }];
+    params.comms.symbol_duration = 40;
+    params.comms.deviation = 0.025;
+    params.comms.receiver_probe = Some("rx".to_string());
+    params.comms.transmitter_feed = Some("feed".to_string());
+    assert_eq!((params.transmitter().x, params.transmitter().amplitude), (35, 10.0));
+
+    let mut simulator = FdtdSimulator::from_parameters(params).unwrap();
+    simulator.send_message("Hi");
+    for _ in 0..2500 {
+        simulator.step();
+    }
+    assert_eq!(simulator.get_received_text(), "Hi");
+}
+
+#[test]
+fn test_physical_units() {
+    let dx = 1e-3;
+    let grid = GridDefinition { cell_size: Some(dx), courant: None };
+    let dt = 0.5 * dx / SPEED_OF_LIGHT;
+    let physical = SimulationParameters {
+        grid,
+        ..params(vec![
+            element("r", 10, 10, LumpedElement::Resistor { resistance: 50.0 }),
+            element("c", 11, 10, LumpedElement::Capacitor { capacitance: 1e-12 }),
+            element("l", 12, 10, LumpedElement::Inductor { inductance: 1e-9 }),
+            element("v", 13, 10, LumpedElement::VoltageSource {
+                resistance: 50.0, amplitude: 1.0, frequency: 1e9, signal_type: SignalType::ContinuousSine,
+            }),
+        ])
+    };
+    let resolved = physical.resolve_units();
+    let eps0 = 1.0 / (FREE_SPACE_IMPEDANCE * SPEED_OF_LIGHT);
+    let mu0 = FREE_SPACE_IMPEDANCE / SPEED_OF_LIGHT;
+    let close = |a: f64, b: f64| (a - b).abs() < 1e-12 * b.abs();
+    match resolved.lumped_elements.iter().map(|e| e.element.clone()).collect::<Vec<_>>()[..] {
+        [LumpedElement::Resistor { resistance: r }, LumpedElement::Capacitor { capacitance: c }, LumpedElement::Inductor { inductance: l },
+         LumpedElement::VoltageSource { resistance, amplitude, frequency, .. }] => {
+            assert!(close(r, 50.0 / FREE_SPACE_IMPEDANCE) && close(resistance, r));
+            assert!(close(c, 1e-12 / (eps0 * dx)));
+            assert!(close(l, 1e-9 / (mu0 * dx)));
+            assert!(close(amplitude, 1.0 / dx) && close(frequency, 1e9 * dt));
+        },
+        _ => panic!("unexpected elements"),
+    }
+
+    // Ports read in volts and amperes: the resistor obeys Ohm's law in ohms.
+    let mut simulator = FdtdSimulator::from_parameters(physical).unwrap();
+    for _ in 0..100 {
+        simulator.step();
+    }
+    let (voltage, current) = simulator.lumped_port("r").unwrap();
+    assert!(voltage.abs() > 0.0);
+    assert!((voltage - 50.0 * current).abs() < 1e-9 * voltage.abs());
+    assert!(simulator.lumped_port("missing").is_err());
+}
+
+#[test]
+fn test_lumped_element_validation() {
+    let resistor = |x, y| element("r", x, y, LumpedElement::Resistor { resistance: 1.0 });
+    assert!(validate_parameters(&params(vec![resistor(10, 10)])).is_ok());
+    assert!(validate_parameters(&params(vec![resistor(0, 10)])).is_err());
+    assert!(validate_parameters(&params(vec![resistor(99, 10)])).is_err());
+    assert!(validate_parameters(&params(vec![resistor(10, 10), element("s", 10, 10, LumpedElement::Resistor { resistance: 1.0 })])).is_err());
+    assert!(validate_parameters(&params(vec![resistor(10, 10), resistor(11, 10)])).is_err());
+    assert!(validate_parameters(&params(vec![element("r", 10, 10, LumpedElement::Resistor { resistance: 0.0 })])).is_err());
+    assert!(validate_parameters(&params(vec![element("c", 10, 10, LumpedElement::Capacitor { capacitance: f64::NAN })])).is_err());
+    // The implicit inductor update has no lower limit on the inductance.
+    assert!(validate_parameters(&params(vec![element("l", 10, 10, LumpedElement::Inductor { inductance: 1e-3 })])).is_ok());
+    assert!(validate_parameters(&params(vec![element("l", 10, 10, LumpedElement::Inductor { inductance: 0.0 })])).is_err());
+
+    let te = SimulationParameters { polarization: Polarization::TEz, ..params(vec![resistor(10, 10)]) };
+    assert!(validate_parameters(&te).is_err());
+
+    let mut feed = params(vec![resistor(10, 10)]);
+    feed.comms.transmitter_feed = Some("r".to_string());
+    assert!(validate_parameters(&feed).is_err());
+    feed.lumped_elements.push(element("v", 12, 10, gaussian_source(1.0)));
+    feed.comms.transmitter_feed = Some("v".to_string());
+    assert!(validate_parameters(&feed).is_ok());
+
+    // A recorded feed plays at its own sample rate, so its frequency is not needed.
+    let voltage = |frequency, signal_type| element("v", 10, 10, LumpedElement::VoltageSource {
+        resistance: 1.0, amplitude: 1.0, frequency, signal_type,
+    });
+    let recording = SignalType::Sampled { samples: vec![0.0, 1.0, -1.0], interval: 4.0, interpolation: Interpolation::Linear };
+    assert!(FdtdSimulator::from_parameters(params(vec![voltage(0.0, recording)])).is_ok());
+    assert!(validate_parameters(&params(vec![voltage(0.0, SignalType::ContinuousSine)])).is_err());
+}
@@ -0,0 +1,144 @@
+use fdtd_wasm::engine::apply_forced_source;
+use fdtd_wasm::materials::{build_material_table, compute_coefficients, find_material, UpdateCoefficients, PEC, VACUUM};
+use fdtd_wasm::parameters::{validate_parameters, MaterialDefinition, MaterialRegion, SimulationParameters};
+use fdtd_wasm::state::SimulationState;
+use fdtd_wasm::step::step;
+use fdtd_wasm::precision::widen;
+
+fn glass() -> MaterialDefinition {
+    MaterialDefinition { name: "glass".to_string(), relative_permittivity: 4.0, ..Default::default() }
+}
+
+#[test]
+fn test_vacuum_coefficients_match_courant() {
+    let c = UpdateCoefficients::for_material(&MaterialDefinition::default(), 0.5);
+    assert_eq!(c, UpdateCoefficients { ca: 1.0, cb: 0.5, da: 1.0, db: 0.5 });
+}
+
+#[test]
+fn test_dielectric_and_lossy_coefficients() {
+    let c = UpdateCoefficients::for_material(&glass(), 0.5);
+    assert_eq!(c.ca, 1.0);
+    assert_eq!(c.cb, 0.125);
+
+    let lossy = MaterialDefinition { name: "ground".to_string(), conductivity: 0.4, ..Default::default() };
+    let c = UpdateCoefficients::for_material(&lossy, 0.5);
+    // loss term = sigma * S / (2 * eps_r) = 0.1
+    assert!((c.ca - 0.9 / 1.1).abs() < 1e-12);
+    assert!((c.cb - 0.5 / 1.1).abs() < 1e-12);
+}
+
+#[test]
+fn test_material_table_has_builtins_first() {
+    let table = build_material_table(&[glass()]);
+    assert_eq!(find_material(&table, "vacuum"), Some(VACUUM));
+    assert_eq!(find_material(&table, "pec"), Some(PEC));
+    assert_eq!(find_material(&table, "glass"), Some(2));
+    assert_eq!(find_material(&table, "water"), None);
+
+    let coefficients = compute_coefficients(&table, 0.5);
+    assert_eq!(coefficients[PEC as usize].ca, 0.0);
+    assert_eq!(coefficients[PEC as usize].cb, 0.0);
+}
+
+#[test]
+fn test_state_rasterizes_regions_and_obstacles() {
+    let params = SimulationParameters {
+        width: 20,
+        height: 20,
+        materials: vec![glass()],
+        regions: vec![MaterialRegion { material: "glass".to_string(), path: "M 2 2 L 12 2 L 12 12 L 2 12 Z".to_string() }],
+        obstacles: vec!["M 10 10 L 15 10 L 15 15 L 10 15 Z".to_string()],
+        ..Default::default()
+    };
+    let state = SimulationState::from_parameters(&params).unwrap();
+    assert_eq!(state.materials[5 * 20 + 5], 2);
+    // Obstacles are drawn on top of regions
+    assert_eq!(state.materials[11 * 20 + 11], PEC);
+    assert!(state.is_pec(11 * 20 + 11));
+    assert_eq!(state.materials[18 * 20 + 18], VACUUM);
+}
+
+#[test]
+fn test_validate_rejects_bad_materials() {
+    let unknown = SimulationParameters {
+        regions: vec![MaterialRegion { material: "water".to_string(), path: "M 0 0 L 1 0 L 1 1 Z".to_string() }],
+        ..Default::default()
+    };
+    assert!(validate_parameters(&unknown).is_err());
+
+    let reserved = SimulationParameters {
+        materials: vec![MaterialDefinition { name: "pec".to_string(), ..Default::default() }],
+        ..Default::default()
+    };
+    assert!(validate_parameters(&reserved).is_err());
+
+    let faster_than_light = SimulationParameters {
+        materials: vec![MaterialDefinition { name: "x".to_string(), relative_permittivity: 0.5, ..Default::default() }],
+        ..Default::default()
+    };
+    assert!(validate_parameters(&faster_than_light).is_err());
+
+    for bad in [
+        MaterialDefinition { name: "x".to_string(), relative_permittivity: f64::NAN, ..Default::default() },
+        MaterialDefinition { name: "x".to_string(), relative_permeability: f64::NAN, ..Default::default() },
+        MaterialDefinition { name: "x".to_string(), relative_permittivity: f64::INFINITY, ..Default::default() },
+        MaterialDefinition { name: "x".to_string(), conductivity: f64::NAN, ..Default::default() },
+        MaterialDefinition { name: "x".to_string(), conductivity: f64::INFINITY, ..Default::default() },
+    ] {
+        let params = SimulationParameters { materials: vec![bad], ..Default::default() };
+        assert!(validate_parameters(&params).is_err());
+    }
+
+    let duplicate = SimulationParameters { materials: vec![glass(), glass()], ..Default::default() };
+    assert!(validate_parameters(&duplicate).is_err());
+}
+
+/// Steps until Ez at `probe` first exceeds `threshold`, starting from an impulse at the centre.
+fn arrival_step(material: Option<MaterialDefinition>, probe: usize) -> usize {
+    let size = 81;
+    let mut params = SimulationParameters { width: size, height: size, ..Default::default() };
+    if let Some(m) = material {
+        params.regions = vec![MaterialRegion { material: m.name.clone(), path: "M 0 0 L 81 0 L 81 81 L 0 81 Z".to_string() }];
+        params.materials = vec![m];
+    }
+    let mut state = SimulationState::from_parameters(&params).unwrap();
+    apply_forced_source(&mut state, 40, 40, 1.0);
+    for n in 0..400 {
+        step(&params, &mut state, Some(0.0));
+        if state.ez[40 * size + 40 + probe].abs() > 1e-3 {
+            return n;
+        }
+    }
+    panic!("pulse never arrived");
+}
+
+#[test]
+fn test_dielectric_halves_wave_speed() {
+    let vacuum = arrival_step(None, 20);
+    let glass = arrival_step(Some(glass()), 20);
+    // n = sqrt(4) = 2
+    let ratio = glass as f64 / vacuum as f64;
+    assert!((ratio - 2.0).abs() < 0.2, "arrival ratio {}", ratio);
+}
+
+#[test]
+fn test_conductor_attenuates_field() {
+    let run = |conductivity: f64| {
+        let lossy = MaterialDefinition { name: "ground".to_string(), conductivity, ..Default::default() };
+        let params = SimulationParameters {
+            width: 60,
+            height: 60,
+            materials: vec![lossy],
+            regions: vec![MaterialRegion { material: "ground".to_string(), path: "M 0 0 L 60 0 L 60 60 L 0 60 Z".to_string() }],
+            ..Default::default()
+        };
+        let mut state = SimulationState::from_parameters(&params).unwrap();
+        apply_forced_source(&mut state, 30, 30, 1.0);
+        for _ in 0..40 {
+            step(&params, &mut state, Some(0.0));
+        }
+        state.ez.iter().map(|&v| widen(v) * widen(v)).sum::<f64>()
+    };
+    assert!(run(0.5) < 0.1 * run(0.0));
+}
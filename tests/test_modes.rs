@@ -0,0 +1,125 @@
+use fdtd_wasm::modes::{find_modes, ModeSearch};
+use fdtd_wasm::parameters::{
+    BoundaryDefinition, EdgeBoundary, MaterialDefinition, MaterialRegion, ProbeDefinition, ProbeShape, SignalType,
+    SimulationParameters, SourceDefinition,
+};
+use fdtd_wasm::volume::parameters::FieldComponent;
+use fdtd_wasm::FdtdSimulator;
+use std::f64::consts::PI;
+
+#[test]
+fn test_finds_decaying_sinusoids() {
+    // (frequency, decay rate, amplitude, phase)
+    let modes = [(0.031, 0.002, 1.0, 0.3), (0.052, 0.0005, 0.4, -1.0), (0.09, 0.004, 0.7, 2.0)];
+    let interval = 1e-9;
+    let samples: Vec<f64> = (0..3000).map(|n| {
+        let t = n as f64;
+        modes.iter().map(|&(f, a, amplitude, phase)| amplitude * (-a * t).exp() * (2.0 * PI * f * t + phase).cos()).sum()
+    }).collect();
+
+    let found = find_modes(&samples, interval, &ModeSearch::default()).unwrap();
+    assert_eq!(found.len(), 3);
+    for (mode, &(f, a, amplitude, phase)) in found.iter().zip(&modes) {
+        assert!((mode.frequency * interval - f).abs() < 1e-9);
+        assert!((mode.decay_rate * interval - a).abs() < 1e-9);
+        assert!((mode.q - PI * f / a).abs() < 1e-6 * mode.q);
+        assert!((mode.amplitude - amplitude).abs() < 1e-9);
+        assert!((mode.phase - phase).abs() < 1e-9);
+    }
+
+    // Skipped samples move the time origin; the band keeps only the modes inside it.
+    let search = ModeSearch { skip: 100, min_frequency: Some(0.04 / interval), ..Default::default() };
+    let found = find_modes(&samples, interval, &search).unwrap();
+    assert_eq!(found.len(), 2);
+    assert!((found[0].amplitude - 0.4 * (-0.0005f64 * 100.0).exp()).abs() < 1e-9);
+}
+
+#[test]
+fn test_finds_modes_in_noise() {
+    // A deterministic pseudo-random noise floor far below the signal.
+    let mut seed = 12345u64;
+    let samples: Vec<f64> = (0..2000).map(|n| {
+        seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+        let noise = ((seed >> 11) as f64 / (1u64 << 53) as f64 - 0.5) * 1e-3;
+        (-0.001 * n as f64).exp() * (2.0 * PI * 0.043 * n as f64).cos() + noise
+    }).collect();
+    let search = ModeSearch { tolerance: 1e-2, ..Default::default() };
+    let found = find_modes(&samples, 1.0, &search).unwrap();
+    let strongest = found.iter().max_by(|a, b| a.amplitude.total_cmp(&b.amplitude)).unwrap();
+    assert!((strongest.frequency - 0.043).abs() < 1e-5);
+    assert!((strongest.decay_rate - 0.001).abs() < 2e-5);
+    assert!((strongest.amplitude - 1.0).abs() < 1e-2);
+}
+
+#[test]
+fn test_rejects_bad_searches() {
+    let samples = vec![1.0; 100];
+    assert!(find_modes(&samples, 0.0, &ModeSearch::default()).is_err());
+    assert!(find_modes(&samples, 1.0, &ModeSearch { stride: 0, ..Default::default() }).is_err());
+    assert!(find_modes(&samples, 1.0, &ModeSearch { stride: 20, ..Default::default() }).is_err());
+    assert!(find_modes(&samples, 1.0, &ModeSearch { max_modes: 0, ..Default::default() }).is_err());
+    assert!(find_modes(&samples, 1.0, &ModeSearch { tolerance: 0.0, ..Default::default() }).is_err());
+    assert!(find_modes(&samples, 1.0, &ModeSearch { skip: 98, ..Default::default() }).is_err());
+    assert!(find_modes(&[0.0; 50], 1.0, &ModeSearch::default()).unwrap().is_empty());
+}
+
+/// A closed PEC box whose Ez nodes span x = 0..=60 and y = 0..=40, rung by a long Gaussian pulse
+/// so that only its lowest modes are excited.
+fn cavity() -> SimulationParameters {
+    SimulationParameters {
+        width: 61, height: 41,
+        sources: vec![SourceDefinition {
+            x: 13, y: 9, amplitude: 1.0,
+            signal_type: SignalType::DifferentiatedGaussian { width: 40.0, delay: None },
+            ..Default::default()
+        }],
+        boundary: BoundaryDefinition::uniform(EdgeBoundary::Pec),
+        probes: vec![ProbeDefinition { name: "p".to_string(), component: FieldComponent::Ez, shape: ProbeShape::Point { x: 41, y: 27 }, capacity: None }],
+        duration_steps: 4000,
+        ..Default::default()
+    }
+}
+
+/// Frequency of the (m, n) mode of the box on the grid, from the discrete dispersion relation.
+fn cavity_frequency(m: usize, n: usize) -> f64 {
+    let k = (m as f64 * PI / 120.0).sin().powi(2) + (n as f64 * PI / 80.0).sin().powi(2);
+    (0.5 * k.sqrt()).asin() / PI
+}
+
+#[test]
+fn test_finds_cavity_resonances() {
+    let mut simulator = FdtdSimulator::from_parameters(cavity()).unwrap();
+    simulator.advance(usize::MAX, None);
+    // One sample in eight still resolves everything the pulse excites, over a longer pencil.
+    let search = ModeSearch { skip: 400, stride: 8, ..Default::default() };
+    let modes = simulator.probe_modes("p", &search).unwrap();
+    let strongest = modes.iter().map(|m| m.amplitude).fold(0.0, f64::max);
+    let significant: Vec<_> = modes.iter().filter(|m| m.amplitude > 1e-3 * strongest).collect();
+    assert!(significant.len() >= 3);
+    for mode in &significant {
+        let nearest = (1..8).flat_map(|m| (1..8).map(move |n| cavity_frequency(m, n)))
+            .min_by(|a, b| (a - mode.frequency).abs().total_cmp(&(b - mode.frequency).abs())).unwrap();
+        assert!((mode.frequency - nearest).abs() < 1e-6, "{:?} vs {}", mode, nearest);
+        // A lossless box rings forever.
+        assert!(mode.q > 1e5, "{:?}", mode);
+    }
+    assert!(significant.iter().any(|m| (m.frequency - cavity_frequency(1, 1)).abs() < 1e-6));
+    assert!(simulator.probe_modes("missing", &search).is_err());
+}
+
+#[test]
+fn test_cavity_q_follows_conductivity() {
+    // A uniform conductivity sigma (normalised) damps every mode at sigma * S / 2 per step.
+    let sigma = 0.002;
+    let mut params = cavity();
+    params.materials = vec![MaterialDefinition { name: "lossy".to_string(), conductivity: sigma, ..Default::default() }];
+    params.regions = vec![MaterialRegion { material: "lossy".to_string(), path: "M 0 0 L 61 0 L 61 41 L 0 41 Z".to_string() }];
+    let mut simulator = FdtdSimulator::from_parameters(params).unwrap();
+    simulator.advance(usize::MAX, None);
+    let modes = simulator.probe_modes("p", &ModeSearch { skip: 400, stride: 8, ..Default::default() }).unwrap();
+    let fundamental = modes.iter().find(|m| (m.frequency - cavity_frequency(1, 1)).abs() < 1e-4).unwrap();
+    let rate = sigma * 0.5 / 2.0;
+    assert!((fundamental.decay_rate - rate).abs() < 0.01 * rate, "{:?}", fundamental);
+    assert!((fundamental.q - PI * fundamental.frequency / rate).abs() < 0.01 * fundamental.q);
+}
+
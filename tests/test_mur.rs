@@ -0,0 +1,48 @@
+mod common;
+
+use common::reflection_db;
+use fdtd_wasm::parameters::{BoundaryDefinition, EdgeBoundary, MurOrder, Polarization, SignalType, SimulationParameters, SourceDefinition};
+use fdtd_wasm::state::SimulationState;
+use fdtd_wasm::step::step;
+
+fn mur(order: MurOrder) -> BoundaryDefinition {
+    BoundaryDefinition::uniform(EdgeBoundary::Mur(order))
+}
+
+#[test]
+fn test_reflection_benchmark() {
+    let first = reflection_db(mur(MurOrder::First));
+    let second = reflection_db(mur(MurOrder::Second));
+    let damping = reflection_db(BoundaryDefinition::uniform(EdgeBoundary::Damping));
+    let cpml = reflection_db(BoundaryDefinition::default());
+    assert!(first < -40.0, "Mur 1st order reflection too high: {:.1} dB", first);
+    assert!(first < damping - 10.0, "Mur {:.1} dB vs damping {:.1} dB", first, damping);
+    assert!(second < first, "2nd order {:.1} dB vs 1st order {:.1} dB", second, first);
+    assert!(cpml < second - 40.0, "CPML {:.1} dB vs Mur {:.1} dB", cpml, second);
+}
+
+#[test]
+fn test_mur_acts_on_primary_field_in_both_polarizations() {
+    let params = |polarization| SimulationParameters {
+        width: 60, height: 50,
+        sources: vec![SourceDefinition { x: 15, y: 20, signal_type: SignalType::PulseSine, ..Default::default() }],
+        boundary: mur(MurOrder::Second),
+        polarization,
+        ..Default::default()
+    };
+    let (tm, te) = (params(Polarization::TMz), params(Polarization::TEz));
+    let mut tm_state = SimulationState::from_parameters(&tm).unwrap();
+    let mut te_state = SimulationState::from_parameters(&te).unwrap();
+    for _ in 0..120 {
+        step(&tm, &mut tm_state, None);
+        step(&te, &mut te_state, None);
+    }
+    assert!(tm_state.ez[20 * 60].abs() > 0.0, "the edge node should be updated");
+    assert_eq!(tm_state.ez, te_state.hz);
+
+    tm_state.reset();
+    for _ in 0..120 {
+        step(&tm, &mut tm_state, None);
+    }
+    assert_eq!(tm_state.ez, te_state.hz, "reset should clear the stored edge values");
+}
@@ -0,0 +1,49 @@
+use fdtd_wasm::parallel::set_threads;
+use fdtd_wasm::parameters::{
+    BoundaryDefinition, CpmlDefinition, EdgeBoundary, MaterialDefinition, MaterialRegion, MurOrder, Polarization,
+    SimulationParameters, SourceDefinition,
+};
+use fdtd_wasm::state::SimulationState;
+use fdtd_wasm::step::step;
+
+fn run(params: &SimulationParameters, threads: usize) -> SimulationState {
+    set_threads(threads);
+    let mut state = SimulationState::from_parameters(params).unwrap();
+    for _ in 0..150 {
+        step(params, &mut state, None);
+    }
+    set_threads(0);
+    state
+}
+
+// One test only: the thread count is global to the process.
+#[test]
+fn test_threaded_updates_match_serial_bit_for_bit() {
+    let cpml = EdgeBoundary::Cpml(CpmlDefinition { thickness: 12, ..Default::default() });
+    for polarization in [Polarization::TMz, Polarization::TEz] {
+        let params = SimulationParameters {
+            width: 150, height: 230,
+            sources: vec![SourceDefinition { x: 60, y: 100, frequency: 0.05, ..Default::default() }],
+            boundary: BoundaryDefinition { left: cpml.clone(), right: EdgeBoundary::Damping, top: cpml.clone(), bottom: EdgeBoundary::Mur(MurOrder::Second) },
+            materials: vec![MaterialDefinition { name: "glass".to_string(), relative_permittivity: 4.0, conductivity: 0.01, ..Default::default() }],
+            regions: vec![MaterialRegion { material: "glass".to_string(), path: "M 80 60 L 120 60 L 120 160 L 80 160 Z".to_string() }],
+            obstacles: vec!["M 30 150 L 50 150 L 50 170 L 30 170 Z".to_string()],
+            polarization,
+            ..Default::default()
+        };
+        let serial = run(&params, 1);
+        for threads in [2, 3, 7] {
+            let threaded = run(&params, threads);
+            for component in ["ez", "hx", "hy", "hz", "ex", "ey"] {
+                let field = |s: &SimulationState| match component {
+                    "ez" => s.ez.clone(), "hx" => s.hx.clone(), "hy" => s.hy.clone(),
+                    "hz" => s.hz.clone(), "ex" => s.ex.clone(), _ => s.ey.clone(),
+                };
+                let (a, b) = (field(&serial), field(&threaded));
+                assert!(a.iter().zip(&b).all(|(a, b)| a.to_bits() == b.to_bits()),
+                        "{:?} {} differs with {} threads", polarization, component, threads);
+            }
+        }
+        assert!(serial.primary_field().iter().any(|v| v.abs() > 1e-3));
+    }
+}
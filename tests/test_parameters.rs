@@ -1,13 +1,15 @@
-use fdtd_wasm::parameters::{SimulationParameters, SourceDefinition, validate_parameters, SignalType};
+use fdtd_wasm::parameters::{SimulationParameters, SourceDefinition, GridDefinition, validate_parameters, SignalType,
+    SPEED_OF_LIGHT, DEFAULT_COURANT_NUMBER, MAX_COURANT_NUMBER_2D};
 
 #[test]
 fn test_validate_parameters_valid() {
     let params = SimulationParameters {
         width: 100,
         height: 100,
-        source: SourceDefinition { x: 50, y: 50, amplitude: 1.0, frequency: 1.0, signal_type: SignalType::ContinuousSine },
+        sources: vec![SourceDefinition { x: 50, y: 50, amplitude: 1.0, frequency: 1.0, signal_type: SignalType::ContinuousSine, ..Default::default() }],
         obstacles: vec![],
         duration_steps: 100,
+        ..Default::default()
     };
     assert!(validate_parameters(&params).is_ok());
 }
@@ -17,9 +19,10 @@ fn test_validate_parameters_invalid_dimensions() {
     let params = SimulationParameters {
         width: 0,
         height: 100,
-        source: SourceDefinition { x: 0, y: 0, amplitude: 1.0, frequency: 1.0, signal_type: SignalType::ContinuousSine },
+        sources: vec![SourceDefinition { x: 0, y: 0, amplitude: 1.0, frequency: 1.0, signal_type: SignalType::ContinuousSine, ..Default::default() }],
         obstacles: vec![],
         duration_steps: 100,
+        ..Default::default()
     };
     assert!(validate_parameters(&params).is_err());
 }
@@ -29,9 +32,10 @@ fn test_validate_parameters_source_out_of_bounds() {
     let params = SimulationParameters {
         width: 50,
         height: 50,
-        source: SourceDefinition { x: 100, y: 100, amplitude: 1.0, frequency: 1.0, signal_type: SignalType::ContinuousSine },
+        sources: vec![SourceDefinition { x: 100, y: 100, amplitude: 1.0, frequency: 1.0, signal_type: SignalType::ContinuousSine, ..Default::default() }],
         obstacles: vec![],
         duration_steps: 100,
+        ..Default::default()
     };
     assert!(validate_parameters(&params).is_err());
 }
@@ -41,9 +45,10 @@ fn test_validate_parameters_source_on_boundary() {
     let params = SimulationParameters {
         width: 100,
         height: 100,
-        source: SourceDefinition { x: 100, y: 50, amplitude: 1.0, frequency: 1.0, signal_type: SignalType::ContinuousSine }, // x = width is invalid
+        sources: vec![SourceDefinition { x: 100, y: 50, amplitude: 1.0, frequency: 1.0, signal_type: SignalType::ContinuousSine, ..Default::default() }], // x = width is invalid
         obstacles: vec![],
         duration_steps: 100,
+        ..Default::default()
     };
     assert!(validate_parameters(&params).is_err());
 }
@@ -53,9 +58,10 @@ fn test_validate_parameters_negative_frequency() {
     let params = SimulationParameters {
         width: 100,
         height: 100,
-        source: SourceDefinition { x: 50, y: 50, amplitude: 1.0, frequency: -5.0, signal_type: SignalType::ContinuousSine },
+        sources: vec![SourceDefinition { x: 50, y: 50, amplitude: 1.0, frequency: -5.0, signal_type: SignalType::ContinuousSine, ..Default::default() }],
         obstacles: vec![],
         duration_steps: 100,
+        ..Default::default()
     };
     assert!(validate_parameters(&params).is_err());
 }
@@ -65,9 +71,69 @@ fn test_validate_parameters_zero_duration() {
     let params = SimulationParameters {
         width: 100,
         height: 100,
-        source: SourceDefinition { x: 50, y: 50, amplitude: 1.0, frequency: 1.0, signal_type: SignalType::ContinuousSine },
+        sources: vec![SourceDefinition { x: 50, y: 50, amplitude: 1.0, frequency: 1.0, signal_type: SignalType::ContinuousSine, ..Default::default() }],
         obstacles: vec![],
         duration_steps: 0,
+        ..Default::default()
     };
     assert!(validate_parameters(&params).is_err());
 }
+
+#[test]
+fn test_validate_parameters_rejects_cfl_violation() {
+    let mut params = SimulationParameters {
+        grid: GridDefinition { cell_size: Some(0.01), courant: Some(0.8) },
+        ..Default::default()
+    };
+    assert!(validate_parameters(&params).is_err());
+
+    params.grid.courant = Some(MAX_COURANT_NUMBER_2D);
+    assert!(validate_parameters(&params).is_ok());
+
+    params.grid.courant = Some(0.0);
+    assert!(validate_parameters(&params).is_err());
+
+    params.grid.courant = Some(f64::NAN);
+    assert!(validate_parameters(&params).is_err());
+}
+
+#[test]
+fn test_validate_parameters_rejects_bad_cell_size() {
+    let params = SimulationParameters {
+        grid: GridDefinition { cell_size: Some(-1.0), courant: None },
+        ..Default::default()
+    };
+    assert!(validate_parameters(&params).is_err());
+}
+
+#[test]
+fn test_resolve_units_normalizes_physical_frequencies() {
+    let mut params = SimulationParameters {
+        grid: GridDefinition { cell_size: Some(1.0), courant: Some(0.5) },
+        ..Default::default()
+    };
+    params.sources[0].frequency = 10e6;
+    params.comms.carrier_frequency = 10e6;
+    params.comms.deviation = 1e6;
+    params.comms.symbol_rate = Some(100e3);
+
+    // dt = S * dx / c
+    let dt = 0.5 * 1.0 / SPEED_OF_LIGHT;
+    assert!((params.time_step_seconds().unwrap() - dt).abs() < 1e-18);
+
+    let resolved = params.resolve_units();
+    assert!((resolved.sources[0].frequency - 10e6 * dt).abs() < 1e-12);
+    assert!((resolved.comms.carrier_frequency - 10e6 * dt).abs() < 1e-12);
+    assert!((resolved.comms.deviation - 1e6 * dt).abs() < 1e-12);
+    assert_eq!(resolved.comms.symbol_duration, (1.0 / (100e3 * dt)).round() as usize);
+    assert!(resolved.time_step_seconds().is_none());
+}
+
+#[test]
+fn test_resolve_units_keeps_normalized_values() {
+    let params = SimulationParameters::default();
+    let resolved = params.resolve_units();
+    assert_eq!(resolved.sources[0].frequency, params.sources[0].frequency);
+    assert_eq!(resolved.comms.symbol_duration, params.comms.symbol_duration);
+    assert_eq!(params.courant_number(), DEFAULT_COURANT_NUMBER);
+}
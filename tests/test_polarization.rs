@@ -0,0 +1,87 @@
+use fdtd_wasm::parameters::{
+    validate_parameters, DispersivePole, MaterialDefinition, MaterialRegion, Polarization, SignalType,
+    SimulationParameters, SourceDefinition,
+};
+use fdtd_wasm::state::SimulationState;
+use fdtd_wasm::step::step;
+use fdtd_wasm::precision::widen;
+
+fn params(polarization: Polarization) -> SimulationParameters {
+    SimulationParameters {
+        width: 60, height: 50,
+        sources: vec![SourceDefinition { x: 25, y: 20, amplitude: 1.0, frequency: 0.05, signal_type: SignalType::PulseSine, ..Default::default() }],
+        materials: vec![MaterialDefinition { name: "glass".to_string(), relative_permittivity: 4.0, ..Default::default() }],
+        regions: vec![MaterialRegion { material: "glass".to_string(), path: "M 35 10 L 45 10 L 45 40 L 35 40 Z".to_string() }],
+        obstacles: vec!["M 10 30 L 15 30 L 15 35 L 10 35 Z".to_string()],
+        polarization,
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_set_polarization_allocates_active_fields() {
+    let mut state = SimulationState::new(8, 6);
+    assert_eq!(state.ez.len(), 48);
+    assert!(state.hz.is_empty());
+
+    state.set_polarization(Polarization::TEz);
+    assert_eq!(state.hz.len(), 48);
+    assert_eq!(state.ex.len(), 48);
+    assert_eq!(state.ey.len(), 48);
+    assert!(state.ez.is_empty() && state.hx.is_empty() && state.hy.is_empty());
+    assert_eq!(state.primary_field().len(), 48);
+}
+
+#[test]
+fn test_tez_is_dual_of_tmz_in_vacuum() {
+    // Swapping E and H (Ez -> Hz, Hx -> -Ex, Hy -> -Ey) maps the two modes onto each other
+    // when eps = mu, which holds for vacuum and the CPML.
+    let vacuum = |polarization| SimulationParameters {
+        materials: vec![], regions: vec![], obstacles: vec![],
+        ..params(polarization)
+    };
+    let tm = vacuum(Polarization::TMz);
+    let te = vacuum(Polarization::TEz);
+    let mut tm_state = SimulationState::from_parameters(&tm).unwrap();
+    let mut te_state = SimulationState::from_parameters(&te).unwrap();
+
+    for _ in 0..150 {
+        step(&tm, &mut tm_state, None);
+        step(&te, &mut te_state, None);
+    }
+
+    assert!(tm_state.ez.iter().any(|&v| v.abs() > 1e-3));
+    assert_eq!(tm_state.ez, te_state.hz);
+    for i in 0..tm_state.hx.len() {
+        assert_eq!(tm_state.hx[i], -te_state.ex[i]);
+        assert_eq!(tm_state.hy[i], -te_state.ey[i]);
+    }
+}
+
+#[test]
+fn test_tez_pec_and_dielectric() {
+    let te = params(Polarization::TEz);
+    let mut state = SimulationState::from_parameters(&te).unwrap();
+    for _ in 0..200 {
+        step(&te, &mut state, None);
+    }
+
+    let idx = 32 * te.width + 12;
+    assert!(state.is_pec(idx));
+    assert_eq!(state.ex[idx], 0.0);
+    assert_eq!(state.ey[idx], 0.0);
+
+    assert!(state.hz.iter().all(|v| v.is_finite()));
+    let glass = (20..30).map(|y| widen(state.hz[y * te.width + 40]).abs()).fold(0.0, f64::max);
+    assert!(glass > 0.0, "the wave should reach the dielectric");
+}
+
+#[test]
+fn test_dispersive_materials_require_tmz() {
+    let mut te = params(Polarization::TEz);
+    te.materials[0].poles = vec![DispersivePole::Debye { delta_epsilon: 1.0, relaxation_time: 5.0 }];
+    assert!(validate_parameters(&te).is_err());
+
+    let tm = SimulationParameters { polarization: Polarization::TMz, ..te };
+    assert!(validate_parameters(&tm).is_ok());
+}
@@ -0,0 +1,177 @@
+use fdtd_wasm::parameters::{
+    validate_parameters, BoundaryDefinition, CpmlDefinition, EdgeBoundary, GridDefinition, MaterialDefinition, MaterialRegion,
+    Polarization, PortDefinition, PortDirection, SParameterDefinition, SignalType, SimulationParameters, SPEED_OF_LIGHT,
+};
+use fdtd_wasm::FdtdSimulator;
+use std::f64::consts::PI;
+
+fn port(name: &str, x: usize, direction: PortDirection) -> PortDefinition {
+    PortDefinition { name: name.to_string(), x0: x, y0: 10, x1: x, y1: 29, direction }
+}
+
+/// A PEC parallel-plate guide along x whose walls leave the Ez nodes y = 10..=29 open, with a port
+/// at each end. Its TE1 cutoff is about 0.012 and its TE3 cutoff about 0.036 cycles per step.
+fn guide(obstacles: &[&str]) -> SimulationParameters {
+    let walls = ["M 0 0 L 200 0 L 200 10 L 0 10 Z", "M 0 30 L 200 30 L 200 40 L 0 40 Z"];
+    SimulationParameters {
+        width: 200, height: 40,
+        sources: Vec::new(),
+        obstacles: walls.iter().chain(obstacles).map(|p| p.to_string()).collect(),
+        boundary: BoundaryDefinition {
+            top: EdgeBoundary::Pec,
+            bottom: EdgeBoundary::Pec,
+            ..BoundaryDefinition::uniform(EdgeBoundary::Cpml(CpmlDefinition::default()))
+        },
+        duration_steps: 3000,
+        s_parameters: Some(SParameterDefinition {
+            ports: vec![port("in", 40, PortDirection::PositiveX), port("out", 160, PortDirection::NegativeX)],
+            frequencies: vec![0.02, 0.025, 0.03, 0.034],
+            excited_port: 0,
+            amplitude: 1.0,
+            frequency: 0.027,
+            signal_type: SignalType::ModulatedGaussian { bandwidth: 0.02, delay: None },
+        }),
+        ..Default::default()
+    }
+}
+
+/// A symmetric PEC iris halfway along the guide.
+const IRIS: [&str; 2] = ["M 99 10 L 101 10 L 101 17 L 99 17 Z", "M 99 23 L 101 23 L 101 30 L 99 30 Z"];
+
+fn magnitude(re: f64, im: f64) -> f64 {
+    re.hypot(im)
+}
+
+#[test]
+fn test_empty_guide_is_matched() {
+    let mut simulator = FdtdSimulator::from_parameters(guide(&[])).unwrap();
+    simulator.advance(usize::MAX, None);
+    let s = simulator.s_parameters().unwrap();
+    assert_eq!(s.ports, ["in", "out"]);
+    assert_eq!(s.parameters.iter().map(|p| p.name.as_str()).collect::<Vec<_>>(), ["S11", "S21"]);
+    let (s11, s21) = (s.get(0, 0).unwrap(), s.get(1, 0).unwrap());
+    assert!(s11.magnitude_db().iter().all(|&db| db < -35.0), "{:?}", s11.magnitude_db());
+    assert!(s21.magnitude_db().iter().all(|&db| db.abs() < 0.1), "{:?}", s21.magnitude_db());
+
+    // The reference planes are 120 cells apart, so S21 = exp(-i k 120) with k from the grid's
+    // dispersion relation for the half-sine mode of 20 nodes.
+    for (k, &f) in s.frequencies.iter().enumerate() {
+        let sin_half_k = ((PI * f).sin().powi(2) / 0.25 - (PI / 42.0).sin().powi(2)).sqrt();
+        let expected = -2.0 * sin_half_k.asin() * 120.0;
+        let error = (s21.im[k].atan2(s21.re[k]) - expected).rem_euclid(2.0 * PI);
+        assert!(error.min(2.0 * PI - error) < 0.02, "phase error {} at {}", error, f);
+    }
+}
+
+#[test]
+fn test_iris_conserves_power_and_is_reciprocal() {
+    let simulator = FdtdSimulator::from_parameters(guide(&IRIS)).unwrap();
+    let s = simulator.s_parameter_sweep().unwrap();
+    assert_eq!(s.parameters.iter().map(|p| p.name.as_str()).collect::<Vec<_>>(), ["S11", "S21", "S12", "S22"]);
+    // The sweep runs its own simulations.
+    assert_eq!(simulator.get_current_step(), 0);
+    for k in 0..s.frequencies.len() {
+        let m = |to, from| {
+            let p = s.get(to, from).unwrap();
+            magnitude(p.re[k], p.im[k])
+        };
+        // Only the fundamental mode propagates past the symmetric iris below the TE3 cutoff.
+        assert!((m(0, 0).powi(2) + m(1, 0).powi(2) - 1.0).abs() < 0.03, "{} {}", m(0, 0), m(1, 0));
+        assert!((m(1, 1).powi(2) + m(0, 1).powi(2) - 1.0).abs() < 0.03);
+        assert!(m(0, 0) > 0.3);
+        let (s21, s12) = (s.get(1, 0).unwrap(), s.get(0, 1).unwrap());
+        assert!(magnitude(s21.re[k] - s12.re[k], s21.im[k] - s12.im[k]) < 1e-2);
+        assert!((m(0, 0) - m(1, 1)).abs() < 1e-2);
+    }
+}
+
+#[test]
+fn test_touchstone_output() {
+    let simulator = FdtdSimulator::from_parameters(guide(&IRIS)).unwrap();
+    let s = simulator.s_parameter_sweep().unwrap();
+    let text = s.to_touchstone(None).unwrap();
+    let lines: Vec<&str> = text.lines().filter(|l| !l.starts_with('!')).collect();
+    assert_eq!(lines[0], "# HZ S RI R 1");
+    assert_eq!(lines.len(), 1 + s.frequencies.len());
+    for (line, &f) in lines[1..].iter().zip(&s.frequencies) {
+        let values: Vec<f64> = line.split_whitespace().map(|v| v.parse().unwrap()).collect();
+        assert_eq!(values.len(), 9);
+        assert_eq!(values[0], f);
+        let s21 = s.get(1, 0).unwrap();
+        let k = s.frequencies.iter().position(|&g| g == f).unwrap();
+        assert_eq!((values[3], values[4]), (s21.re[k], s21.im[k]));
+    }
+
+    // On a physical grid the frequencies are written in hertz.
+    let dt = 0.5 * 1e-3 / SPEED_OF_LIGHT;
+    let hz = s.to_touchstone(Some(dt)).unwrap();
+    assert!(!hz.contains("cycles per time step"));
+    let first: f64 = hz.lines().find(|l| !l.starts_with(['!', '#'])).unwrap().split_whitespace().next().unwrap().parse().unwrap();
+    assert!((first - 0.02 / dt).abs() < 1e-6 * first);
+
+    // A single run only measures the column of its excited port.
+    let mut single = FdtdSimulator::from_parameters(guide(&IRIS)).unwrap();
+    single.advance(100, None);
+    assert!(single.s_parameters().unwrap().to_touchstone(None).is_err());
+}
+
+#[test]
+fn test_dielectric_guide_impedance() {
+    // A guide filled with eps_r = 2 and ports inside it: still matched, with the slower phase.
+    let mut params = guide(&[]);
+    params.materials = vec![MaterialDefinition { name: "fill".to_string(), relative_permittivity: 2.0, ..Default::default() }];
+    params.regions = vec![MaterialRegion { material: "fill".to_string(), path: "M 0 10 L 200 10 L 200 30 L 0 30 Z".to_string() }];
+    let mut simulator = FdtdSimulator::from_parameters(params).unwrap();
+    simulator.advance(usize::MAX, None);
+    let s = simulator.s_parameters().unwrap();
+    let (s11, s21) = (s.get(0, 0).unwrap(), s.get(1, 0).unwrap());
+    assert!(s11.magnitude_db().iter().all(|&db| db < -30.0), "{:?}", s11.magnitude_db());
+    assert!(s21.magnitude_db().iter().all(|&db| db.abs() < 0.1), "{:?}", s21.magnitude_db());
+    for (k, &f) in s.frequencies.iter().enumerate() {
+        let sin_half_k = (2.0 * (PI * f).sin().powi(2) / 0.25 - (PI / 42.0).sin().powi(2)).sqrt();
+        let error = (s21.im[k].atan2(s21.re[k]) + 2.0 * sin_half_k.asin() * 120.0).rem_euclid(2.0 * PI);
+        assert!(error.min(2.0 * PI - error) < 0.03, "phase error {} at {}", error, f);
+    }
+}
+
+#[test]
+fn test_port_validation() {
+    assert!(validate_parameters(&guide(&[])).is_ok());
+    let with = |edit: &dyn Fn(&mut SParameterDefinition)| {
+        let mut params = guide(&[]);
+        edit(params.s_parameters.as_mut().unwrap());
+        params
+    };
+    assert!(validate_parameters(&with(&|s| s.excited_port = 2)).is_err());
+    assert!(validate_parameters(&with(&|s| s.frequencies.clear())).is_err());
+    assert!(validate_parameters(&with(&|s| s.frequency = f64::NAN)).is_err());
+    assert!(validate_parameters(&with(&|s| s.ports[1].name = "in".to_string())).is_err());
+    assert!(validate_parameters(&with(&|s| s.ports[0].direction = PortDirection::PositiveY)).is_err());
+    assert!(validate_parameters(&with(&|s| s.ports[0].x1 = 41)).is_err());
+    // The pulse is launched one node behind the line, which must stay off the edge.
+    assert!(validate_parameters(&with(&|s| { s.ports[0].x0 = 1; s.ports[0].x1 = 1; })).is_err());
+    assert!(validate_parameters(&with(&|s| { s.ports[1].x0 = 197; s.ports[1].x1 = 197; })).is_err());
+    let te = SimulationParameters { polarization: Polarization::TEz, ..guide(&[]) };
+    assert!(validate_parameters(&te).is_err());
+
+    // The mode needs walls at both ends of the line or at neither, and a single medium.
+    let one_wall = with(&|s| s.ports[0].y1 = 25);
+    assert!(FdtdSimulator::from_parameters(one_wall).err().unwrap().contains("PEC walls"));
+    let on_iris = with(&|s| { s.ports[0].x0 = 100; s.ports[0].x1 = 100; });
+    assert!(FdtdSimulator::from_parameters(SimulationParameters { obstacles: guide(&IRIS).obstacles, ..on_iris }).is_err());
+
+    // The impedance is infinite at the half-sine mode's cutoff.
+    let cutoff = (0.5 * (PI / 42.0).sin()).asin() / PI;
+    let at_cutoff = with(&|s| s.frequencies = vec![0.02, cutoff]);
+    assert!(FdtdSimulator::from_parameters(at_cutoff).err().unwrap().contains("cutoff"));
+    assert!(FdtdSimulator::from_parameters(with(&|s| s.frequencies = vec![cutoff * 0.9])).is_ok());
+
+    // Frequencies follow the grid units.
+    let physical = SimulationParameters { grid: GridDefinition { cell_size: Some(1e-3), courant: None }, ..with(&|s| {
+        s.frequencies = vec![3e9];
+        s.frequency = 3e9;
+    }) };
+    let resolved = physical.resolve_units();
+    let dt = 0.5 * 1e-3 / SPEED_OF_LIGHT;
+    assert!((resolved.s_parameters.unwrap().frequencies[0] - 3e9 * dt).abs() < 1e-12);
+}
@@ -0,0 +1,56 @@
+use fdtd_wasm::parameters::{
+    BoundaryDefinition, EdgeBoundary, MaterialDefinition, MaterialRegion, SimulationParameters, SourceDefinition,
+};
+use fdtd_wasm::precision::widen;
+use fdtd_wasm::state::SimulationState;
+use fdtd_wasm::step::step;
+use fdtd_wasm::volume::parameters::{FaceBoundary, VolumeBoundaryDefinition, VolumeParameters, VolumeRegion};
+use fdtd_wasm::volume::state::VolumeState;
+use fdtd_wasm::volume::step::step as step_volume;
+
+/// Largest difference between the 2D fields and an f64 reference over a long run, relative to the
+/// peak of the reference. With the `f32` feature it stays below this; in f64 the runs are identical.
+const F32_TOLERANCE: f64 = 1e-5;
+
+// The 3D solver always runs in f64, and a single Ez layer between PEC faces is the 2D TMz problem,
+// so it serves as the double-precision reference for whichever precision the 2D fields use.
+#[test]
+fn test_fields_agree_with_double_precision_over_long_run() {
+    let (width, height, steps) = (100, 80, 3000);
+    let source = SourceDefinition { x: 30, y: 40, frequency: 0.04, ..Default::default() };
+    let glass = MaterialDefinition { name: "glass".to_string(), relative_permittivity: 4.0, conductivity: 0.005, ..Default::default() };
+    let block = "M 55 25 L 70 25 L 70 55 L 55 55 Z";
+    let flat = SimulationParameters {
+        width, height,
+        sources: vec![source.clone()],
+        boundary: BoundaryDefinition::uniform(EdgeBoundary::Damping),
+        materials: vec![glass.clone()],
+        regions: vec![MaterialRegion { material: "glass".to_string(), path: block.to_string() }],
+        ..Default::default()
+    };
+    let volume = VolumeParameters {
+        width, height, depth: 2,
+        source,
+        source_z: 0,
+        boundary: VolumeBoundaryDefinition { z_min: FaceBoundary::Pec, z_max: FaceBoundary::Pec, ..Default::default() },
+        materials: vec![glass],
+        regions: vec![VolumeRegion { material: "glass".to_string(), path: block.to_string(), z_start: 0, z_end: 2 }],
+        ..Default::default()
+    };
+    let mut state = SimulationState::from_parameters(&flat).unwrap();
+    let mut reference = VolumeState::from_parameters(&volume).unwrap();
+
+    let (mut peak, mut error) = (0.0f64, 0.0f64);
+    for _ in 0..steps {
+        step(&flat, &mut state, None);
+        step_volume(&volume, &mut reference);
+        for (&value, &expected) in state.ez.iter().zip(&reference.ez[..width * height]) {
+            peak = peak.max(expected.abs());
+            error = error.max((widen(value) - expected).abs());
+        }
+    }
+
+    assert!(peak > 0.1, "peak {}", peak);
+    let tolerance = if cfg!(feature = "f32") { F32_TOLERANCE } else { 0.0 };
+    assert!(error <= tolerance * peak, "error {:e} of peak {}", error / peak, peak);
+}
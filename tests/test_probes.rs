@@ -0,0 +1,159 @@
+use fdtd_wasm::comms::noise::receiver_noise;
+use fdtd_wasm::parameters::{
+    validate_parameters, Polarization, ProbeDefinition, ProbeShape, SignalType, SimulationParameters, SourceDefinition,
+};
+use fdtd_wasm::state::SimulationState;
+use fdtd_wasm::step::step;
+use fdtd_wasm::volume::parameters::FieldComponent;
+use fdtd_wasm::FdtdSimulator;
+use fdtd_wasm::precision::widen;
+
+fn probe(name: &str, component: FieldComponent, shape: ProbeShape) -> ProbeDefinition {
+    ProbeDefinition { name: name.to_string(), component, shape, capacity: None }
+}
+
+fn params(probes: Vec<ProbeDefinition>) -> SimulationParameters {
+    SimulationParameters {
+        width: 60, height: 50,
+        sources: vec![SourceDefinition { x: 20, y: 25, signal_type: SignalType::PulseSine, ..Default::default() }],
+        probes,
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_probes_average_their_cells_every_step() {
+    let params = params(vec![
+        probe("point", FieldComponent::Ez, ProbeShape::Point { x: 30, y: 25 }),
+        probe("line", FieldComponent::Ez, ProbeShape::Line { x0: 30, y0: 20, x1: 33, y1: 22 }),
+        probe("area", FieldComponent::Hy, ProbeShape::Area { x_min: 28, y_min: 23, x_max: 31, y_max: 27 }),
+    ]);
+    let mut state = SimulationState::from_parameters(&params).unwrap();
+    let w = params.width;
+    let mut expected = (Vec::new(), Vec::new(), Vec::new());
+    for _ in 0..80 {
+        step(&params, &mut state, None);
+        expected.0.push(widen(state.ez[25 * w + 30]));
+        let line = [(30, 20), (31, 21), (32, 21), (33, 22)];
+        expected.1.push(line.iter().map(|&(x, y)| widen(state.ez[y * w + x])).sum::<f64>() / 4.0);
+        let area: f64 = (23..=27).flat_map(|y| (28..=31).map(move |x| (x, y))).map(|(x, y)| widen(state.hy[y * w + x])).sum();
+        expected.2.push(area / 20.0);
+    }
+
+    assert!(expected.0.iter().any(|v| v.abs() > 1e-3));
+    assert_eq!(state.probe("point").unwrap().samples(), expected.0);
+    assert_eq!(state.probe("line").unwrap().samples(), expected.1);
+    let area = state.probe("area").unwrap().samples();
+    for (a, b) in area.iter().zip(&expected.2) {
+        assert!((a - b).abs() < 1e-15);
+    }
+
+    state.reset();
+    assert!(state.probe("point").unwrap().is_empty());
+    assert_eq!(state.probe("point").unwrap().latest(), None);
+}
+
+#[test]
+fn test_probe_ring_buffer_keeps_latest_samples() {
+    let mut point = probe("point", FieldComponent::Ez, ProbeShape::Point { x: 30, y: 25 });
+    point.capacity = Some(16);
+    let params = params(vec![point]);
+    let mut state = SimulationState::from_parameters(&params).unwrap();
+    let mut history = Vec::new();
+    for _ in 0..50 {
+        step(&params, &mut state, None);
+        history.push(widen(state.ez[25 * params.width + 30]));
+    }
+    let recorder = state.probe("point").unwrap();
+    assert_eq!(recorder.len(), 16);
+    assert_eq!(recorder.recorded(), 50);
+    assert_eq!(recorder.samples(), history[34..]);
+    assert_eq!(recorder.latest(), Some(history[49]));
+}
+
+#[test]
+fn test_receiver_probe_feeds_demodulator() {
+    let base = SimulationParameters {
+        sources: vec![SourceDefinition { x: 15, y: 25, amplitude: 10.0, ..Default::default() }],
+        ..params(vec![probe("rx", FieldComponent::Ez, ProbeShape::Point { x: 40, y: 25 })])
+    };
+    let mut automatic = base.clone();
+    automatic.comms.receiver_probe = Some("rx".to_string());
+
+    let mut manual = FdtdSimulator::from_parameters(base).unwrap();
+    let mut built_in = FdtdSimulator::from_parameters(automatic).unwrap();
+    manual.send_message("Hi");
+    built_in.send_message("Hi");
+    for _ in 0..3000 {
+        manual.step();
+        let val = manual.get_field_at(40, 25);
+        manual.process_receiver_signal(val);
+        built_in.step();
+    }
+    assert_eq!(built_in.probe_samples("rx").unwrap().len(), 1000);
+    assert!(!manual.get_received_bits().is_empty());
+    assert_eq!(built_in.get_received_bits(), manual.get_received_bits());
+    assert_eq!(built_in.get_demodulator_status(), manual.get_demodulator_status());
+    assert!(built_in.probe_samples("tx").is_err());
+}
+
+#[test]
+fn test_receiver_noise_reaches_demodulator() {
+    let mut params = SimulationParameters {
+        sources: vec![SourceDefinition { x: 15, y: 25, amplitude: 10.0, ..Default::default() }],
+        ..params(vec![probe("rx", FieldComponent::Ez, ProbeShape::Point { x: 40, y: 25 })])
+    };
+    params.comms.receiver_noise = 0.05;
+    params.duration_steps = 1500;
+    let mut manual = FdtdSimulator::from_parameters(params.clone()).unwrap();
+    params.comms.receiver_probe = Some("rx".to_string());
+    let mut built_in = FdtdSimulator::from_parameters(params).unwrap();
+    manual.send_message("Hi");
+    built_in.send_message("Hi");
+    for _ in 0..1500 {
+        manual.step();
+        let noise = receiver_noise(manual.get_current_step(), 0.05);
+        assert!(noise.abs() <= 0.05);
+        manual.process_receiver_signal(manual.get_field_at(40, 25) + noise);
+    }
+    assert_eq!(built_in.advance(1500, None).steps, 1500);
+    assert!(!manual.get_received_bits().is_empty());
+    assert_eq!(built_in.get_received_bits(), manual.get_received_bits());
+
+    // The noisy samples are the clean ones plus the noise of their own step.
+    let clean = built_in.probe_samples("rx").unwrap();
+    let noisy = built_in.receiver_samples().unwrap();
+    assert_eq!(noisy.len(), 1500);
+    let first = built_in.get_current_step() + 1 - clean.len();
+    for (i, (c, n)) in clean.iter().zip(&noisy).enumerate() {
+        assert_eq!(*n, c + receiver_noise(first + i, 0.05));
+    }
+    assert!(noisy.iter().zip(&clean).any(|(n, c)| n != c));
+    assert!(manual.receiver_samples().is_err());
+}
+
+#[test]
+fn test_probe_validation() {
+    let point = |name: &str, component, x| probe(name, component, ProbeShape::Point { x, y: 10 });
+    assert!(validate_parameters(&params(vec![point("a", FieldComponent::Ez, 10)])).is_ok());
+    assert!(validate_parameters(&params(vec![point("a", FieldComponent::Ez, 60)])).is_err());
+    assert!(validate_parameters(&params(vec![point("", FieldComponent::Ez, 10)])).is_err());
+    assert!(validate_parameters(&params(vec![point("a", FieldComponent::Ez, 10), point("a", FieldComponent::Hx, 11)])).is_err());
+    assert!(validate_parameters(&params(vec![point("a", FieldComponent::Hz, 10)])).is_err());
+
+    let te = SimulationParameters { polarization: Polarization::TEz, ..params(vec![point("a", FieldComponent::Hz, 10)]) };
+    assert!(validate_parameters(&te).is_ok());
+
+    let backwards = probe("a", FieldComponent::Ez, ProbeShape::Area { x_min: 5, y_min: 5, x_max: 4, y_max: 8 });
+    assert!(validate_parameters(&params(vec![backwards])).is_err());
+
+    let mut unknown = params(vec![point("a", FieldComponent::Ez, 10)]);
+    unknown.comms.receiver_probe = Some("b".to_string());
+    assert!(validate_parameters(&unknown).is_err());
+
+    let mut noisy = params(vec![point("a", FieldComponent::Ez, 10)]);
+    noisy.comms.receiver_noise = -0.1;
+    assert!(validate_parameters(&noisy).is_err());
+    noisy.comms.receiver_noise = f64::NAN;
+    assert!(validate_parameters(&noisy).is_err());
+}
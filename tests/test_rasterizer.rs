@@ -1,4 +1,4 @@
-use fdtd_wasm::rasterizer::{parse_svg_path, fill_path_on_grid, rasterize_path, rasterize_obstacles, PathCommand};
+use fdtd_wasm::rasterizer::{parse_svg_path, fill_path_on_grid, rasterize_path, rasterize_obstacles, try_rasterize_path, PathCommand};
 
 #[test]
 fn test_parse_svg_path_simple_rect() {
@@ -94,7 +94,19 @@ fn test_rasterize_obstacles_multiple_paths() {
     
     let mask = rasterize_obstacles(width, height, &paths);
     
-    assert_eq!(mask[1 * width + 1], 1.0);
+    assert_eq!(mask[width + 1], 1.0);
     assert_eq!(mask[8 * width + 8], 1.0);
     assert_eq!(mask[5 * width + 5], 0.0);
-}
\ No newline at end of file
+}
+#[test]
+fn test_try_rasterize_path_reports_bad_path() {
+    let width = 10;
+    let height = 10;
+    let mut grid = vec![0.0; width * height];
+
+    assert!(try_rasterize_path(width, height, "M 3 3 L 7 3 L 7 7 L 3 7 Z", &mut grid).is_ok());
+    assert_eq!(grid[5 * width + 5], 1.0);
+
+    let err = try_rasterize_path(width, height, "M 1 Q 2 2", &mut grid).unwrap_err();
+    assert!(err.contains("M 1 Q 2 2"));
+}
@@ -0,0 +1,101 @@
+use fdtd_wasm::parameters::{ProbeDefinition, ProbeShape, SignalType, SimulationParameters, SourceDefinition};
+use fdtd_wasm::run::StopCondition;
+use fdtd_wasm::volume::parameters::FieldComponent;
+use fdtd_wasm::FdtdSimulator;
+
+fn params(duration_steps: usize) -> SimulationParameters {
+    SimulationParameters {
+        width: 60, height: 50,
+        sources: vec![SourceDefinition { x: 15, y: 25, amplitude: 10.0, ..Default::default() }],
+        probes: vec![ProbeDefinition { name: "rx".to_string(), component: FieldComponent::Ez, shape: ProbeShape::Point { x: 35, y: 25 }, capacity: None }],
+        duration_steps,
+        ..Default::default()
+    }
+}
+
+fn pulse(duration_steps: usize) -> SimulationParameters {
+    SimulationParameters {
+        sources: vec![SourceDefinition { x: 30, y: 25, signal_type: SignalType::PulseSine, ..Default::default() }],
+        ..params(duration_steps)
+    }
+}
+
+#[test]
+fn test_run_stops_at_duration() {
+    let mut simulator = FdtdSimulator::from_parameters(params(100)).unwrap();
+    let status = simulator.advance(30, None);
+    assert_eq!((status.steps, status.time_step, status.duration_steps), (30, 30, 100));
+    assert!((status.progress - 0.3).abs() < 1e-12);
+    assert!(!status.finished && !status.condition_met && !simulator.is_finished());
+
+    let status = simulator.advance(200, None);
+    assert_eq!((status.steps, status.time_step), (70, 100));
+    assert!(status.finished && simulator.is_finished());
+    assert_eq!(simulator.get_progress(), 1.0);
+    assert_eq!(simulator.advance(usize::MAX, None).steps, 0);
+
+    // Single steps are still allowed past the end.
+    simulator.step();
+    assert_eq!(simulator.get_current_step(), 101);
+}
+
+#[test]
+fn test_batch_matches_single_steps() {
+    let mut single = FdtdSimulator::from_parameters(params(1000)).unwrap();
+    let mut batch = FdtdSimulator::from_parameters(params(1000)).unwrap();
+    for _ in 0..250 {
+        single.step();
+    }
+    assert_eq!(batch.advance(250, None).steps, 250);
+    assert_eq!(batch.state().ez, single.state().ez);
+    assert_eq!(batch.probe_samples("rx").unwrap(), single.probe_samples("rx").unwrap());
+}
+
+#[test]
+fn test_run_until_packet_decoded() {
+    let mut params = params(5000);
+    params.comms.symbol_duration = 40;
+    params.comms.deviation = 0.025;
+    params.comms.receiver_probe = Some("rx".to_string());
+    let mut simulator = FdtdSimulator::from_parameters(params.clone()).unwrap();
+    let mut reference = FdtdSimulator::from_parameters(params).unwrap();
+    simulator.send_message("Hi");
+    reference.send_message("Hi");
+
+    let status = simulator.advance(usize::MAX, Some(&StopCondition::PacketDecoded));
+    assert!(status.condition_met && !status.finished);
+    assert_eq!(simulator.get_received_text(), "Hi");
+    // The run stops on the very step that completes the packet.
+    while reference.get_received_text().is_empty() {
+        reference.step();
+    }
+    assert_eq!(status.time_step, reference.get_current_step());
+}
+
+#[test]
+fn test_run_until_probe_threshold() {
+    let mut simulator = FdtdSimulator::from_parameters(pulse(1000)).unwrap();
+    let condition = StopCondition::ProbeThreshold { probe: "rx".to_string(), threshold: 0.05 };
+    condition.validate(simulator.state()).unwrap();
+    let status = simulator.advance(usize::MAX, Some(&condition));
+    assert!(status.condition_met);
+    let samples = simulator.probe_samples("rx").unwrap();
+    let (last, earlier) = samples.split_last().unwrap();
+    assert!(last.abs() >= 0.05);
+    assert!(earlier.iter().all(|v| v.abs() < 0.05));
+
+    let unknown = StopCondition::ProbeThreshold { probe: "tx".to_string(), threshold: 0.05 };
+    assert!(unknown.validate(simulator.state()).is_err());
+}
+
+#[test]
+fn test_run_until_energy_rises_and_decays() {
+    let mut simulator = FdtdSimulator::from_parameters(pulse(2000)).unwrap();
+    let status = simulator.advance(usize::MAX, Some(&StopCondition::EnergyAbove { energy: 1.0 }));
+    assert!(status.condition_met);
+    assert!(simulator.get_energy() >= 1.0);
+
+    let status = simulator.advance(usize::MAX, Some(&StopCondition::EnergyBelow { energy: 0.01 }));
+    assert!(status.condition_met && !status.finished);
+    assert!(simulator.get_energy() <= 0.01);
+}
@@ -1,16 +1,16 @@
 use fdtd_wasm::FdtdSimulator;
-use fdtd_wasm::parameters::{SimulationParameters, SourceDefinition, SignalType};
+use fdtd_wasm::parameters::{SimulationParameters, SourceDefinition, GridDefinition, SignalType, SPEED_OF_LIGHT};
 use wasm_bindgen::JsValue;
-use serde_wasm_bindgen;
 use wasm_bindgen_test::*;
 
 #[wasm_bindgen_test]
 fn test_fdtd_simulator_new_succeeds_with_valid_config() {
     let params = SimulationParameters {
         width: 10, height: 10,
-        source: SourceDefinition { x: 5, y: 5, amplitude: 1.0, frequency: 1.0, signal_type: SignalType::ContinuousSine },
+        sources: vec![SourceDefinition { x: 5, y: 5, amplitude: 1.0, frequency: 1.0, signal_type: SignalType::ContinuousSine, ..Default::default() }],
         obstacles: vec![],
         duration_steps: 100,
+        ..Default::default()
     };
     let config_json = serde_wasm_bindgen::to_value(&params).unwrap();
     
@@ -28,9 +28,10 @@ fn test_fdtd_simulator_new_fails_with_invalid_config() {
 
     let invalid_params = SimulationParameters {
         width: 0, height: 10,
-        source: SourceDefinition { x: 0, y: 0, amplitude: 1.0, frequency: 1.0, signal_type: SignalType::ContinuousSine },
+        sources: vec![SourceDefinition { x: 0, y: 0, amplitude: 1.0, frequency: 1.0, signal_type: SignalType::ContinuousSine, ..Default::default() }],
         obstacles: vec![],
         duration_steps: 100,
+        ..Default::default()
     };
     let invalid_config_json = serde_wasm_bindgen::to_value(&invalid_params).unwrap();
     let simulator_invalid = FdtdSimulator::new(invalid_config_json);
@@ -41,9 +42,10 @@ fn test_fdtd_simulator_new_fails_with_invalid_config() {
 fn test_fdtd_simulator_get_current_step() {
     let params = SimulationParameters {
         width: 10, height: 10,
-        source: SourceDefinition { x: 5, y: 5, amplitude: 1.0, frequency: 1.0, signal_type: SignalType::ContinuousSine },
+        sources: vec![SourceDefinition { x: 5, y: 5, amplitude: 1.0, frequency: 1.0, signal_type: SignalType::ContinuousSine, ..Default::default() }],
         obstacles: vec![],
         duration_steps: 100,
+        ..Default::default()
     };
     let config_json = serde_wasm_bindgen::to_value(&params).unwrap();
     let simulator = FdtdSimulator::new(config_json).unwrap();
@@ -55,9 +57,10 @@ fn test_fdtd_simulator_get_current_step() {
 fn test_fdtd_simulator_step_advances_time() {
     let params = SimulationParameters {
         width: 10, height: 10,
-        source: SourceDefinition { x: 5, y: 5, amplitude: 1.0, frequency: 1.0, signal_type: SignalType::ContinuousSine },
+        sources: vec![SourceDefinition { x: 5, y: 5, amplitude: 1.0, frequency: 1.0, signal_type: SignalType::ContinuousSine, ..Default::default() }],
         obstacles: vec![],
         duration_steps: 100,
+        ..Default::default()
     };
     let config_json = serde_wasm_bindgen::to_value(&params).unwrap();
     let mut simulator = FdtdSimulator::new(config_json).unwrap();
@@ -71,9 +74,10 @@ fn test_fdtd_simulator_step_advances_time() {
 fn test_fdtd_simulator_comms() {
     let params = SimulationParameters {
         width: 10, height: 10,
-        source: SourceDefinition { x: 5, y: 5, amplitude: 1.0, frequency: 1.0, signal_type: SignalType::ContinuousSine },
+        sources: vec![SourceDefinition { x: 5, y: 5, amplitude: 1.0, frequency: 1.0, signal_type: SignalType::ContinuousSine, ..Default::default() }],
         obstacles: vec![],
         duration_steps: 100,
+        ..Default::default()
     };
     let config_json = serde_wasm_bindgen::to_value(&params).unwrap();
     let mut simulator = FdtdSimulator::new(config_json).unwrap();
@@ -87,4 +91,63 @@ fn test_fdtd_simulator_comms() {
     
     // Check initial status
     assert!(simulator.get_demodulator_status().contains("SearchPreamble"));
-}
\ No newline at end of file
+}
+#[test]
+fn test_fdtd_simulator_rasterizes_obstacles() {
+    let params = SimulationParameters {
+        width: 10, height: 10,
+        sources: vec![SourceDefinition { x: 1, y: 1, amplitude: 1.0, frequency: 1.0, signal_type: SignalType::ContinuousSine, ..Default::default() }],
+        obstacles: vec!["M 3 3 L 7 3 L 7 7 L 3 7 Z".to_string()],
+        duration_steps: 100,
+        ..Default::default()
+    };
+    let mut simulator = FdtdSimulator::from_parameters(params).unwrap();
+
+    // Obstacles are painted green by the renderer
+    let frame = simulator.get_frame_buffer();
+    let idx = (5 * 10 + 5) * 4;
+    assert_eq!(&frame[idx..idx + 4], &[0, 255, 0, 255]);
+
+    simulator.clear_obstacles();
+    let frame = simulator.get_frame_buffer();
+    assert_eq!(&frame[idx..idx + 4], &[0, 0, 0, 255]);
+
+    assert!(simulator.add_obstacle("M 0 0 L 2 0 L 2 2 L 0 2 Z").is_ok());
+    let frame = simulator.get_frame_buffer();
+    let idx = (10 + 1) * 4;
+    assert_eq!(&frame[idx..idx + 4], &[0, 255, 0, 255]);
+}
+
+#[test]
+fn test_fdtd_simulator_rejects_invalid_obstacle() {
+    let params = SimulationParameters {
+        width: 10, height: 10,
+        sources: vec![SourceDefinition { x: 1, y: 1, amplitude: 1.0, frequency: 1.0, signal_type: SignalType::ContinuousSine, ..Default::default() }],
+        obstacles: vec!["M 3 3 L 7 3 Z".to_string(), "M 3 X".to_string()],
+        duration_steps: 100,
+        ..Default::default()
+    };
+    let err = FdtdSimulator::from_parameters(params).err().unwrap();
+    assert!(err.contains("M 3 X"));
+}
+
+#[test]
+fn test_fdtd_simulator_physical_units() {
+    let mut params = SimulationParameters {
+        grid: GridDefinition { cell_size: Some(0.5), courant: Some(0.7) },
+        ..Default::default()
+    };
+    params.sources[0].frequency = 1e6;
+    params.comms.carrier_frequency = 10e6;
+    params.comms.deviation = 1e6;
+    params.comms.symbol_rate = Some(1e5);
+    let simulator = FdtdSimulator::from_parameters(params).unwrap();
+
+    let dt = simulator.get_time_step_seconds().unwrap();
+    assert!((dt - 0.7 * 0.5 / SPEED_OF_LIGHT).abs() < 1e-18);
+    assert_eq!(simulator.get_courant_number(), 0.7);
+
+    let params = SimulationParameters::default();
+    let simulator = FdtdSimulator::from_parameters(params).unwrap();
+    assert!(simulator.get_time_step_seconds().is_none());
+}
@@ -0,0 +1,153 @@
+use fdtd_wasm::engine::source_signal;
+use fdtd_wasm::parameters::{
+    validate_parameters, GridDefinition, SignalType, SimulationParameters, SourceDefinition, TransmitterDefinition,
+};
+use fdtd_wasm::precision::widen;
+use fdtd_wasm::state::SimulationState;
+use fdtd_wasm::step::step;
+
+fn run(params: &SimulationParameters, steps: usize, forced: Option<f64>) -> SimulationState {
+    let mut state = SimulationState::from_parameters(params).unwrap();
+    for _ in 0..steps {
+        step(params, &mut state, forced);
+    }
+    state
+}
+
+#[test]
+fn test_source_signal_phase_and_window() {
+    let source = SourceDefinition { amplitude: 2.0, frequency: 0.1, phase: std::f64::consts::FRAC_PI_2, ..Default::default() };
+    assert!((source_signal(&source, 0.0) - 2.0).abs() < 1e-12);
+
+    let windowed = SourceDefinition { frequency: 0.1, start_time: 10.0, stop_time: Some(20.0), ..Default::default() };
+    assert_eq!(source_signal(&windowed, 9.0), 0.0);
+    assert_eq!(source_signal(&windowed, 20.0), 0.0);
+    // The waveform restarts at the start time.
+    assert!((source_signal(&windowed, 12.5) - 1.0).abs() < 1e-12);
+}
+
+#[test]
+fn test_phase_offsets_the_carrier_only() {
+    use std::f64::consts::FRAC_PI_2;
+    // The pulse still spans one period from its start; only the carrier under it is shifted.
+    let pulse = SourceDefinition { frequency: 0.1, phase: FRAC_PI_2, signal_type: SignalType::PulseSine, ..Default::default() };
+    assert!((source_signal(&pulse, 0.0) - 1.0).abs() < 1e-12);
+    assert!((source_signal(&pulse, 9.0) - (0.2 * std::f64::consts::PI).cos()).abs() < 1e-12);
+    assert_eq!(source_signal(&pulse, 10.0), 0.0);
+
+    // The Gaussian envelope keeps its peak at the delay, where the shifted carrier is at its crest.
+    let burst = SignalType::ModulatedGaussian { bandwidth: 0.02, delay: Some(50.0) };
+    let modulated = SourceDefinition { frequency: 0.1, phase: FRAC_PI_2, signal_type: burst, ..Default::default() };
+    assert!((source_signal(&modulated, 50.0) - 1.0).abs() < 1e-12);
+}
+
+#[test]
+fn test_delayed_pulse_starts_at_start_time() {
+    let pulse = SourceDefinition { frequency: 0.1, signal_type: SignalType::PulseSquare, start_time: 30.0, ..Default::default() };
+    assert_eq!(source_signal(&pulse, 29.0), 0.0);
+    assert_eq!(source_signal(&pulse, 30.0), 1.0);
+    assert_eq!(source_signal(&pulse, 36.0), -1.0);
+    assert_eq!(source_signal(&pulse, 41.0), 0.0);
+}
+
+#[test]
+fn test_sources_superpose() {
+    let a = SourceDefinition { x: 20, y: 25, ..Default::default() };
+    let b = SourceDefinition { x: 35, y: 20, amplitude: 0.5, phase: 1.0, start_time: 5.0, ..Default::default() };
+    let with = |sources| SimulationParameters { width: 60, height: 50, sources, ..Default::default() };
+
+    let both = run(&with(vec![a.clone(), b.clone()]), 60, None);
+    let only_a = run(&with(vec![a]), 60, None);
+    let only_b = run(&with(vec![b]), 60, None);
+
+    assert!(both.ez.iter().any(|&v| v.abs() > 1e-3));
+    // Single-precision fields (the `f32` feature) round each run differently.
+    let tolerance = if cfg!(feature = "f32") { 1e-6 } else { 1e-12 };
+    for i in 0..both.ez.len() {
+        assert!(widen(both.ez[i] - only_a.ez[i] - only_b.ez[i]).abs() < tolerance);
+    }
+}
+
+#[test]
+fn test_transmitter_placement() {
+    let source = SourceDefinition { x: 20, y: 25, frequency: 0.05, ..Default::default() };
+    let transmitter = TransmitterDefinition { x: 40, y: 25, amplitude: 1.0 };
+
+    // Without a position the transmitter replaces the first source.
+    let implicit = SimulationParameters { width: 60, height: 50, sources: vec![source.clone()], ..Default::default() };
+    assert_eq!(implicit.transmitter(), TransmitterDefinition { x: 20, y: 25, amplitude: 1.0 });
+    let state = run(&implicit, 1, Some(0.25));
+    assert_eq!(state.ez[25 * 60 + 20], 0.25);
+
+    // With its own position the CW source keeps running.
+    let mut placed = implicit.clone();
+    placed.comms.transmitter = Some(transmitter);
+    let state = run(&placed, 5, Some(0.25));
+    let source_only = run(&implicit, 5, None);
+    assert!(source_only.ez[25 * 60 + 20].abs() > 0.0);
+    assert_eq!(state.ez[25 * 60 + 20], source_only.ez[25 * 60 + 20]);
+    assert_eq!(run(&placed, 1, Some(0.25)).ez[25 * 60 + 40], 0.25);
+}
+
+#[test]
+fn test_source_validation() {
+    let valid = SimulationParameters::default();
+    assert!(validate_parameters(&valid).is_ok());
+
+    let mut backwards = valid.clone();
+    backwards.sources[0].start_time = 10.0;
+    backwards.sources[0].stop_time = Some(5.0);
+    assert!(validate_parameters(&backwards).is_err());
+
+    let mut second_outside = valid.clone();
+    second_outside.sources.push(SourceDefinition { x: 100, y: 0, ..Default::default() });
+    assert!(validate_parameters(&second_outside).is_err());
+
+    let mut transmitter_outside = valid.clone();
+    transmitter_outside.comms.transmitter = Some(TransmitterDefinition { x: 0, y: 100, amplitude: 1.0 });
+    assert!(validate_parameters(&transmitter_outside).is_err());
+
+    // Envelope-only waveforms have no carrier for a phase to offset.
+    let mut phased = valid.clone();
+    phased.sources[0].phase = 1.0;
+    assert!(validate_parameters(&phased).is_ok());
+    phased.sources[0].signal_type = SignalType::Gaussian { width: 10.0, delay: None };
+    assert!(validate_parameters(&phased).is_err());
+    phased.sources[0].signal_type = SignalType::ContinuousSine;
+    phased.sources[0].phase = f64::NAN;
+    assert!(validate_parameters(&phased).is_err());
+
+    let mut unbounded = valid.clone();
+    unbounded.sources[0].start_time = f64::NAN;
+    assert!(validate_parameters(&unbounded).is_err());
+    unbounded.sources[0].start_time = 0.0;
+    unbounded.sources[0].stop_time = Some(f64::NAN);
+    assert!(validate_parameters(&unbounded).is_err());
+    unbounded.sources[0].stop_time = None;
+    unbounded.sources[0].ramp_time = Some(f64::NAN);
+    assert!(validate_parameters(&unbounded).is_err());
+    unbounded.sources[0].ramp_time = Some(f64::INFINITY);
+    assert!(validate_parameters(&unbounded).is_err());
+    unbounded.sources[0].ramp_time = None;
+    unbounded.sources[0].amplitude = f64::NAN;
+    assert!(validate_parameters(&unbounded).is_err());
+    unbounded.sources[0].amplitude = f64::INFINITY;
+    assert!(validate_parameters(&unbounded).is_err());
+
+    let silent = SimulationParameters { sources: vec![], ..valid };
+    assert!(validate_parameters(&silent).is_ok());
+    assert_eq!(silent.transmitter(), TransmitterDefinition { x: 50, y: 50, amplitude: 1.0 });
+}
+
+#[test]
+fn test_source_times_resolve_to_steps() {
+    let params = SimulationParameters {
+        grid: GridDefinition { cell_size: Some(1e-3), courant: None },
+        sources: vec![SourceDefinition { x: 50, y: 50, frequency: 1e9, start_time: 1e-10, stop_time: Some(2e-10), ..Default::default() }],
+        ..Default::default()
+    };
+    let dt = params.time_step_seconds().unwrap();
+    let resolved = params.resolve_units();
+    assert!((resolved.sources[0].start_time - 1e-10 / dt).abs() < 1e-9);
+    assert!((resolved.sources[0].stop_time.unwrap() - 2e-10 / dt).abs() < 1e-9);
+}
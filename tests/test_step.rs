@@ -6,9 +6,10 @@ use fdtd_wasm::state::SimulationState;
 fn test_step_advances_time() {
     let params = SimulationParameters {
         width: 10, height: 10,
-        source: SourceDefinition { x: 5, y: 5, amplitude: 1.0, frequency: 1.0, signal_type: SignalType::ContinuousSine },
+        sources: vec![SourceDefinition { x: 5, y: 5, amplitude: 1.0, frequency: 1.0, signal_type: SignalType::ContinuousSine, ..Default::default() }],
         obstacles: vec![],
         duration_steps: 100,
+        ..Default::default()
     };
     let mut state = SimulationState::new(10, 10);
     
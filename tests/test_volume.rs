@@ -0,0 +1,148 @@
+use fdtd_wasm::parameters::{
+    BoundaryDefinition, EdgeBoundary, GridDefinition, MaterialDefinition, SignalType, SimulationParameters,
+    SourceDefinition,
+};
+use fdtd_wasm::renderer::render_slice;
+use fdtd_wasm::precision::{widen, Real};
+use fdtd_wasm::state::SimulationState;
+use fdtd_wasm::step::step;
+use fdtd_wasm::volume::parameters::{
+    validate_volume_parameters, Axis, FaceBoundary, FieldComponent, VolumeBoundaryDefinition, VolumeParameters,
+    VolumeRegion,
+};
+use fdtd_wasm::volume::state::VolumeState;
+use fdtd_wasm::volume::step::step as step_volume;
+use fdtd_wasm::FdtdVolumeSimulator;
+
+fn pulse(x: usize, y: usize) -> SourceDefinition {
+    SourceDefinition { x, y, amplitude: 1.0, frequency: 0.05, signal_type: SignalType::PulseSine, ..Default::default() }
+}
+
+#[test]
+#[cfg_attr(feature = "f32", ignore = "the 3D solver always runs in f64")]
+fn test_single_layer_matches_2d_tmz() {
+    // With PEC z faces and a depth of 2 there is a single Ez layer and no z variation,
+    // which is exactly the 2D TMz problem.
+    let flat = SimulationParameters {
+        width: 60, height: 50,
+        sources: vec![pulse(30, 25)],
+        boundary: BoundaryDefinition::uniform(EdgeBoundary::Damping),
+        ..Default::default()
+    };
+    let volume = VolumeParameters {
+        width: 60, height: 50, depth: 2,
+        source: pulse(30, 25),
+        source_z: 0,
+        boundary: VolumeBoundaryDefinition { z_min: FaceBoundary::Pec, z_max: FaceBoundary::Pec, ..Default::default() },
+        ..Default::default()
+    };
+    let mut flat_state = SimulationState::from_parameters(&flat).unwrap();
+    let mut volume_state = VolumeState::from_parameters(&volume).unwrap();
+
+    for _ in 0..80 {
+        step(&flat, &mut flat_state, None);
+        step_volume(&volume, &mut volume_state);
+    }
+
+    let layer = 60 * 50;
+    assert!(flat_state.ez.iter().any(|&v| v.abs() > 1e-3));
+    let widened = |field: &[Real]| field.iter().map(|&v| widen(v)).collect::<Vec<f64>>();
+    assert_eq!(widened(&flat_state.ez), volume_state.ez[..layer]);
+    assert_eq!(widened(&flat_state.hx), volume_state.hx[..layer]);
+    assert_eq!(widened(&flat_state.hy), volume_state.hy[..layer]);
+    assert!(volume_state.hz.iter().all(|&v| v == 0.0));
+}
+
+#[test]
+fn test_dipole_radiates_symmetrically() {
+    let params = VolumeParameters {
+        width: 21, height: 21, depth: 21,
+        source: pulse(10, 10),
+        source_z: 10,
+        boundary: VolumeBoundaryDefinition::uniform(FaceBoundary::Pec),
+        ..Default::default()
+    };
+    let mut state = VolumeState::from_parameters(&params).unwrap();
+    for _ in 0..25 {
+        step_volume(&params, &mut state);
+    }
+
+    let ez = |x, y, z| state.ez[state.index(x, y, z)];
+    assert!(ez(14, 10, 10).abs() > 1e-4, "the pulse should have spread from the dipole");
+    assert!((ez(14, 10, 10) - ez(6, 10, 10)).abs() < 1e-12);
+    assert!((ez(14, 10, 10) - ez(10, 14, 10)).abs() < 1e-12);
+    assert!(state.ex.iter().chain(&state.ey).all(|v| v.is_finite()));
+}
+
+#[test]
+fn test_pec_and_dielectric_voxels() {
+    let params = VolumeParameters {
+        width: 20, height: 20, depth: 20,
+        source: pulse(5, 10),
+        source_z: 10,
+        materials: vec![MaterialDefinition { name: "glass".to_string(), relative_permittivity: 4.0, ..Default::default() }],
+        regions: vec![
+            VolumeRegion { material: "pec".to_string(), path: "M 9 7 L 12 7 L 12 13 L 9 13 Z".to_string(), z_start: 7, z_end: 13 },
+            VolumeRegion { material: "glass".to_string(), path: "M 14 5 L 18 5 L 18 15 L 14 15 Z".to_string(), z_start: 5, z_end: 15 },
+        ],
+        ..Default::default()
+    };
+    let mut state = VolumeState::from_parameters(&params).unwrap();
+    assert_eq!(state.materials[state.index(10, 10, 10)], 1);
+    assert_eq!(state.materials[state.index(16, 10, 10)], 2);
+    assert_eq!(state.materials[state.index(16, 10, 2)], 0);
+
+    for _ in 0..60 {
+        step_volume(&params, &mut state);
+    }
+    let idx = state.index(10, 10, 10);
+    assert_eq!((state.ex[idx], state.ey[idx], state.ez[idx]), (0.0, 0.0, 0.0));
+    assert!(state.ez[state.index(15, 10, 10)].abs() > 0.0);
+}
+
+#[test]
+fn test_validation() {
+    assert!(validate_volume_parameters(&VolumeParameters::default()).is_ok());
+
+    let unstable = VolumeParameters { grid: GridDefinition { cell_size: None, courant: Some(0.6) }, ..Default::default() };
+    assert!(validate_volume_parameters(&unstable).is_err());
+    let nan = VolumeParameters { grid: GridDefinition { cell_size: None, courant: Some(f64::NAN) }, ..Default::default() };
+    assert!(validate_volume_parameters(&nan).is_err());
+
+    let outside = VolumeParameters { source_z: 40, ..Default::default() };
+    assert!(validate_volume_parameters(&outside).is_err());
+
+    let region = VolumeRegion { material: "pec".to_string(), path: "M 0 0 L 1 0 L 1 1 Z".to_string(), z_start: 5, z_end: 41 };
+    let too_deep = VolumeParameters { regions: vec![region], ..Default::default() };
+    assert!(validate_volume_parameters(&too_deep).is_err());
+}
+
+#[test]
+fn test_slice_rendering() {
+    let mut state = VolumeState::new(6, 5, 4);
+    let idx = state.index(2, 3, 1);
+    state.ez[idx] = 1.0;
+
+    assert_eq!(state.slice_dimensions(Axis::X), (5, 4));
+    assert_eq!(render_slice(&state, FieldComponent::Ez, Axis::Y, 3).len(), 6 * 4 * 4);
+
+    let image = render_slice(&state, FieldComponent::Ez, Axis::Z, 1);
+    assert_eq!(image.len(), 6 * 5 * 4);
+    let pixel = (3 * 6 + 2) * 4;
+    assert_eq!(image[pixel..pixel + 4], [255, 0, 0, 255]);
+}
+
+#[test]
+fn test_simulator_slice_buffer() {
+    let mut sim = FdtdVolumeSimulator::from_parameters(VolumeParameters {
+        width: 12, height: 10, depth: 8,
+        source: pulse(6, 5),
+        source_z: 4,
+        ..Default::default()
+    }).unwrap();
+    sim.step();
+    assert_eq!(sim.slice_buffer("Ez", "x", 6).unwrap().len(), 10 * 8 * 4);
+    assert!(sim.slice_buffer("Ez", "z", 8).is_err());
+    assert!(sim.slice_buffer("Bz", "z", 0).is_err());
+    assert!(sim.slice_buffer("Ez", "w", 0).is_err());
+}
@@ -0,0 +1,178 @@
+use fdtd_wasm::engine::{compute_source_signal, interpolate_samples, raised_cosine_ramp, source_signal};
+use fdtd_wasm::parameters::{
+    validate_parameters, GridDefinition, Interpolation, SignalType, SimulationParameters, SourceDefinition,
+};
+use std::f64::consts::PI;
+
+/// Samples the waveform at every step and returns the DFT magnitude at `frequency`.
+fn spectrum_at(signal: &SignalType, carrier: f64, steps: usize, frequency: f64) -> f64 {
+    let (mut re, mut im) = (0.0, 0.0);
+    for n in 0..steps {
+        let v = compute_source_signal(n as f64, carrier, 1.0, signal);
+        let arg = 2.0 * PI * frequency * n as f64;
+        re += v * arg.cos();
+        im -= v * arg.sin();
+    }
+    (re * re + im * im).sqrt()
+}
+
+#[test]
+fn test_gaussian_peaks_at_delay() {
+    let signal = SignalType::Gaussian { width: 10.0, delay: None };
+    assert!(compute_source_signal(0.0, 0.05, 2.0, &signal).abs() < 1e-6);
+    assert_eq!(compute_source_signal(40.0, 0.05, 2.0, &signal), 2.0);
+
+    let delayed = SignalType::Gaussian { width: 10.0, delay: Some(25.0) };
+    assert_eq!(compute_source_signal(25.0, 0.05, 1.0, &delayed), 1.0);
+    assert!((compute_source_signal(35.0, 0.05, 1.0, &delayed) - (-1.0f64).exp()).abs() < 1e-12);
+}
+
+#[test]
+fn test_differentiated_gaussian_has_unit_peak_and_no_dc() {
+    let signal = SignalType::DifferentiatedGaussian { width: 8.0, delay: None };
+    let peak = compute_source_signal(32.0 - 8.0 / 2f64.sqrt(), 0.05, 1.0, &signal);
+    assert!((peak - 1.0).abs() < 1e-12);
+    let sum: f64 = (0..100).map(|n| compute_source_signal(n as f64, 0.05, 1.0, &signal)).sum();
+    assert!(sum.abs() < 1e-6);
+}
+
+#[test]
+fn test_modulated_gaussian_bandwidth() {
+    let signal = SignalType::ModulatedGaussian { bandwidth: 0.02, delay: None };
+    let centre = spectrum_at(&signal, 0.1, 400, 0.1);
+    let edge = spectrum_at(&signal, 0.1, 400, 0.12);
+    assert!((edge / centre - (-1.0f64).exp()).abs() < 0.02, "edge/centre = {}", edge / centre);
+    assert!(spectrum_at(&signal, 0.1, 400, 0.1) > spectrum_at(&signal, 0.1, 400, 0.09));
+}
+
+#[test]
+fn test_ricker_spectrum_peaks_at_frequency() {
+    let signal = SignalType::Ricker { delay: None };
+    let peak = spectrum_at(&signal, 0.05, 400, 0.05);
+    for f in [0.03, 0.04, 0.06, 0.07] {
+        assert!(spectrum_at(&signal, 0.05, 400, f) < peak);
+    }
+    assert!(compute_source_signal(0.0, 0.05, 1.0, &signal).abs() < 1e-4);
+}
+
+#[test]
+fn test_chirps_sweep_their_band() {
+    // Zero crossings are half a period apart, so their count gives the mean frequency of a window.
+    let mean_frequency = |signal: &SignalType, from: usize, to: usize| {
+        let values: Vec<f64> = (from..to).map(|n| compute_source_signal(n as f64, 0.01, 1.0, signal)).collect();
+        let crossings = values.windows(2).filter(|w| w[0].signum() != w[1].signum()).count();
+        crossings as f64 / 2.0 / (to - from) as f64
+    };
+    let linear = SignalType::LinearChirp { bandwidth: 0.04, duration: 10000.0 };
+    assert!((mean_frequency(&linear, 0, 1000) - 0.012).abs() < 0.001);
+    assert!((mean_frequency(&linear, 9000, 10000) - 0.048).abs() < 0.001);
+
+    // f(t) = 0.01 * 5^(t / T), averaged over the first and last tenth.
+    let exponential = SignalType::ExponentialChirp { bandwidth: 0.04, duration: 10000.0 };
+    let ln5 = 5f64.ln();
+    let first = 0.01 * (5f64.powf(0.1) - 1.0) / (0.1 * ln5);
+    let last = 0.01 * (5.0 - 5f64.powf(0.9)) / (0.1 * ln5);
+    assert!((mean_frequency(&exponential, 0, 1000) - first).abs() < 0.001);
+    assert!((mean_frequency(&exponential, 9000, 10000) - last).abs() < 0.001);
+
+    for signal in [linear, exponential] {
+        assert_eq!(compute_source_signal(10000.0, 0.01, 1.0, &signal), 0.0);
+    }
+}
+
+#[test]
+fn test_turn_on_ramp() {
+    assert_eq!(raised_cosine_ramp(0.0), 0.0);
+    assert!((raised_cosine_ramp(0.5) - 0.5).abs() < 1e-12);
+    assert_eq!(raised_cosine_ramp(2.0), 1.0);
+
+    let plain = SourceDefinition { frequency: 0.03, ..Default::default() };
+    let ramped = SourceDefinition { ramp_time: Some(20.0), ..plain.clone() };
+    assert_eq!(source_signal(&ramped, 0.0), 0.0);
+    assert!((source_signal(&ramped, 10.0) - 0.5 * source_signal(&plain, 10.0)).abs() < 1e-12);
+    assert_eq!(source_signal(&ramped, 25.0), source_signal(&plain, 25.0));
+}
+
+#[test]
+fn test_waveform_validation_and_units() {
+    let with = |signal_type| SimulationParameters {
+        sources: vec![SourceDefinition { x: 50, y: 50, signal_type, ..Default::default() }],
+        ..Default::default()
+    };
+    assert!(validate_parameters(&with(SignalType::Gaussian { width: 5.0, delay: None })).is_ok());
+    assert!(validate_parameters(&with(SignalType::Gaussian { width: 0.0, delay: None })).is_err());
+    assert!(validate_parameters(&with(SignalType::ModulatedGaussian { bandwidth: -1.0, delay: None })).is_err());
+    assert!(validate_parameters(&with(SignalType::LinearChirp { bandwidth: 0.01, duration: 0.0 })).is_err());
+    let mut no_frequency = with(SignalType::ContinuousSine);
+    no_frequency.sources[0].frequency = f64::NAN;
+    assert!(validate_parameters(&no_frequency).is_err());
+
+    let physical = SimulationParameters {
+        grid: GridDefinition { cell_size: Some(1e-3), courant: None },
+        ..with(SignalType::LinearChirp { bandwidth: 1e9, duration: 1e-9 })
+    };
+    let dt = physical.time_step_seconds().unwrap();
+    match physical.resolve_units().sources[0].signal_type {
+        SignalType::LinearChirp { bandwidth, duration } => {
+            assert!((bandwidth - 1e9 * dt).abs() < 1e-12);
+            assert!((duration - 1e-9 / dt).abs() < 1e-9);
+        },
+        ref other => panic!("unexpected waveform {:?}", other),
+    }
+}
+
+#[test]
+fn test_sampled_linear_interpolation() {
+    let signal = SignalType::Sampled { samples: vec![0.0, 1.0, -1.0], interval: 4.0, interpolation: Interpolation::Linear };
+    assert_eq!(compute_source_signal(4.0, 0.05, 2.0, &signal), 2.0);
+    assert_eq!(compute_source_signal(2.0, 0.05, 1.0, &signal), 0.5);
+    assert_eq!(compute_source_signal(6.0, 0.05, 1.0, &signal), 0.0);
+    assert_eq!(compute_source_signal(8.0, 0.05, 1.0, &signal), -1.0);
+    assert_eq!(compute_source_signal(8.5, 0.05, 1.0, &signal), 0.0);
+    assert_eq!(compute_source_signal(-1.0, 0.05, 1.0, &signal), 0.0);
+}
+
+#[test]
+fn test_sampled_sinc_reconstructs_band_limited_signal() {
+    // A tone at a quarter of the sample rate, recorded every 5 steps.
+    let samples: Vec<f64> = (0..64).map(|k| (2.0 * PI * 0.25 * k as f64 + 0.3).sin()).collect();
+    assert!((interpolate_samples(&samples, 10.0, Interpolation::Sinc) - samples[10]).abs() < 1e-12);
+
+    let (mut sinc_error, mut linear_error) = (0.0f64, 0.0f64);
+    for n in 100..220 {
+        let x = n as f64 / 5.0;
+        let exact = (2.0 * PI * 0.25 * x + 0.3).sin();
+        sinc_error = sinc_error.max((interpolate_samples(&samples, x, Interpolation::Sinc) - exact).abs());
+        linear_error = linear_error.max((interpolate_samples(&samples, x, Interpolation::Linear) - exact).abs());
+    }
+    assert!(sinc_error < 0.05, "sinc error {}", sinc_error);
+    assert!(linear_error > 2.0 * sinc_error);
+}
+
+#[test]
+fn test_sampled_validation_and_units() {
+    let with = |samples: Vec<f64>, interval| SimulationParameters {
+        grid: GridDefinition { cell_size: Some(1e-3), courant: None },
+        sources: vec![SourceDefinition {
+            x: 50, y: 50, frequency: 1e9,
+            signal_type: SignalType::Sampled { samples, interval, interpolation: Interpolation::Sinc },
+            ..Default::default()
+        }],
+        ..Default::default()
+    };
+    assert!(validate_parameters(&with(vec![], 1e-11)).is_err());
+    assert!(validate_parameters(&with(vec![1.0], 0.0)).is_err());
+    assert!(validate_parameters(&with(vec![0.0, f64::NAN], 1e-11)).is_err());
+
+    let params = with(vec![0.0, 1.0], 1e-11);
+    assert!(validate_parameters(&params).is_ok());
+    // A recording has no carrier, so its frequency may be left at zero.
+    let mut unset = params.clone();
+    unset.sources[0].frequency = 0.0;
+    assert!(validate_parameters(&unset).is_ok());
+    let dt = params.time_step_seconds().unwrap();
+    match &params.resolve_units().sources[0].signal_type {
+        SignalType::Sampled { interval, .. } => assert!((interval - 1e-11 / dt).abs() < 1e-9),
+        other => panic!("unexpected waveform {:?}", other),
+    }
+}
@@ -7,14 +7,14 @@ let isRunning = false;
 let idleCounter = 0;
 let currentScenarioConfig = null;
 let signalHistory = new Array(100).fill(0); // 100px wide
+const STEPS_PER_FRAME = 5;
 
 let WIDTH = 1000;
 let HEIGHT = 600;
 
 // --- Physical Constants ---
-let C_MS = 343.0; // Speed of sound in air (m/s)
-let DX_MM = 1.0;  // 1 pixel = 1 mm
-let DT = 0;       // Seconds per step (calculated)
+let DX_MM = 3500.0; // 1 pixel = 3.5 m
+let DT = 0;       // Seconds per step (reported by the simulator)
 let isQueueFrozen = false;
 
 // --- DOM Elements ---
@@ -29,9 +29,9 @@ const receptorQueueBody = document.getElementById('receptorQueueBody');
 const params = {
     scenario: 'free_space',
     modulation: 'FSK',
-    carrier_hz: 40000,
-    dev_hz: 5000,
-    baud: 1200,
+    carrier_hz: 10000000,
+    dev_hz: 1250000,
+    baud: 300000,
     power: 1.0,
     noise: 0.02,
     gain: 10,
@@ -104,6 +104,8 @@ paramsPane.addInput(params, 'power', {
 paramsPane.addInput(params, 'noise', {
     label: 'Noise Level',
     min: 0, max: 0.5, step: 0.01
+}).on('change', () => {
+    if (simulator) simulator.set_receiver_noise(params.noise);
 });
 
 paramsPane.addInput(params, 'gain', { label: 'Visual Gain', min: 1, max: 100 });
@@ -310,9 +312,8 @@ function createParabolaPath(vertexX, vertexY, focalLength, height, openingRight
 }
 
 function getScenarioConfig(type) {
-    // FDTD Normalization
-    const dt = (DX_MM / 1000.0) / (C_MS * Math.sqrt(2));
-    const freqNorm = params.carrier_hz * dt;
+    // Frequencies are in Hz; the simulator normalises them to its time step.
+    const freqHz = params.carrier_hz;
     const amp = 50.0 * Math.sqrt(params.power);
 
     if (type === 'double_parabola') {
@@ -329,7 +330,7 @@ function getScenarioConfig(type) {
                 x: smallFocusX,
                 y: HEIGHT / 2,
                 amplitude: amp,
-                frequency: freqNorm,
+                frequency: freqHz,
                 signal_type: 'ContinuousSine'
             },
             receiver: {
@@ -347,7 +348,7 @@ function getScenarioConfig(type) {
                 x: WIDTH / 4,
                 y: HEIGHT / 2,
                 amplitude: amp,
-                frequency: freqNorm,
+                frequency: freqHz,
                 signal_type: 'ContinuousSine'
             },
             receiver: {
@@ -362,7 +363,7 @@ function getScenarioConfig(type) {
                 x: WIDTH / 4,
                 y: HEIGHT / 2,
                 amplitude: amp,
-                frequency: freqNorm,
+                frequency: freqHz,
                 signal_type: 'ContinuousSine'
             },
             receiver: {
@@ -378,24 +379,30 @@ function getScenarioConfig(type) {
 
 function getConfig() {
     currentScenarioConfig = getScenarioConfig(params.scenario);
-    
-    // Calculate DT based on current physics
-    DT = (DX_MM / 1000.0) / (C_MS * Math.sqrt(2));
-    
-    const carrierNorm = params.carrier_hz * DT;
-    const devNorm = params.dev_hz * DT;
-    const durationSteps = Math.floor((1.0 / params.baud) / DT);
 
     return {
         width: WIDTH,
         height: HEIGHT,
-        source: currentScenarioConfig.source,
+        grid: {
+            cell_size: DX_MM / 1000.0,
+            // dt = dx / (c * sqrt(2)), the step the demo's settings were tuned for
+            courant: Math.SQRT1_2
+        },
+        sources: [currentScenarioConfig.source],
         comms: {
-            carrier_frequency: carrierNorm,
-            deviation: devNorm,
-            symbol_duration: durationSteps
+            carrier_frequency: params.carrier_hz,
+            deviation: params.dev_hz,
+            symbol_rate: params.baud,
+            receiver_probe: currentScenarioConfig.receiver ? 'receiver' : null,
+            receiver_noise: params.noise
         },
         obstacles: currentScenarioConfig.obstacles,
+        probes: currentScenarioConfig.receiver ? [{
+            name: 'receiver',
+            component: 'Ez',
+            shape: { Point: currentScenarioConfig.receiver },
+            capacity: signalHistory.length
+        }] : [],
         duration_steps: 100000
     };
 }
@@ -475,6 +482,7 @@ function resetSimulation() {
     try {
         const config = getConfig();
         simulator = new FdtdSimulator(config);
+        DT = simulator.get_time_step_seconds();
         simulator.set_comms_scheme(params.modulation === 'ASK');
         draw();
         updateStats();
@@ -501,47 +509,38 @@ function stopSimulation() {
 function renderLoop() {
     if (!isRunning) return;
 
-    for (let i = 0; i < 5; i++) {
-        simulator.step();
-
-        if (currentScenarioConfig && currentScenarioConfig.receiver) {
-            let val = simulator.get_field_at(currentScenarioConfig.receiver.x, currentScenarioConfig.receiver.y);
-
-            // Inject Noise
-            const noiseLevel = params.noise;
-            if (noiseLevel > 0) {
-                // Random noise between -noiseLevel and +noiseLevel
-                val += (Math.random() - 0.5) * 2 * noiseLevel;
-            }
-
-            signalHistory.push(val);
-            signalHistory.shift();
-
-            // Feed graphs (Scale acts as multiplier/Gain)
-            params.rxSignalValue = val * params.signalScale;
-            
-            let max = 0;
-            for(let j=0; j<signalHistory.length; j++) {
-                const abs = Math.abs(signalHistory[j]);
-                if (abs > max) max = abs;
-            }
-            params.rxSpectrumPeak = max * params.spectrumScale;
-
-            signalMonitor.refresh();
-            spectrumMonitor.refresh();
-
-            simulator.process_receiver_signal(val);
-
-            // Auto-pause logic: stop if idle for too long
-            if (params.packetState === 'Idle') {
-                idleCounter++;
-            } else {
-                idleCounter = 0;
-            }
+    // One call steps the whole frame; the simulator feeds the receiver probe, noise included,
+    // to the demodulator after every step.
+    const status = simulator.run(STEPS_PER_FRAME);
+
+    if (currentScenarioConfig && currentScenarioConfig.receiver) {
+        const samples = simulator.get_receiver_samples();
+        signalHistory.fill(0);
+        signalHistory.splice(signalHistory.length - samples.length, samples.length, ...samples);
+        const val = samples.length > 0 ? samples[samples.length - 1] : 0;
+
+        // Feed graphs (Scale acts as multiplier/Gain)
+        params.rxSignalValue = val * params.signalScale;
+
+        let max = 0;
+        for (let j = 0; j < signalHistory.length; j++) {
+            const abs = Math.abs(signalHistory[j]);
+            if (abs > max) max = abs;
+        }
+        params.rxSpectrumPeak = max * params.spectrumScale;
+
+        signalMonitor.refresh();
+        spectrumMonitor.refresh();
+
+        // Auto-pause logic: stop if idle for too long
+        if (params.packetState === 'Idle') {
+            idleCounter += status.steps;
+        } else {
+            idleCounter = 0;
         }
     }
 
-    if (idleCounter > 1000) { // About 200 frames at 5 steps per frame
+    if (idleCounter > 1000 || status.finished) { // About 200 frames at 5 steps per frame
         stopSimulation();
         idleCounter = 0;
     }
@@ -575,7 +574,6 @@ async function loadConfig() {
         params.baud = config.transmission.baud;
         params.power = config.transmission.power_w;
         
-        C_MS = config.physics.c_ms;
         DX_MM = config.physics.dx_mm;
         
         params.signalScale = config.ui.signalScale;